@@ -1,12 +1,18 @@
 use {
-    crate::{auth::UserAuth, Result, XploreError},
+    crate::{
+        auth::{UserAuth, GUEST_OPERATIONS},
+        endpoints::ApiEndpoint,
+        rate_limit::{self, RateLimitEvent, RateLimitInfo},
+        transport::{HttpRequest, HttpResponse, MultipartField, RequestBody},
+        ApiErrorDetails, Result, XploreError,
+    },
     reqwest::{
         header::{HeaderMap, HeaderValue},
-        multipart::Form,
-        Method,
+        Method, StatusCode,
     },
     serde::de::DeserializeOwned,
     serde_json::Value,
+    std::time::Duration,
 };
 
 pub const BEARER_TOKEN: &str =
@@ -14,62 +20,84 @@ pub const BEARER_TOKEN: &str =
 
 // pub const URL_USER_BY_REST_ID: &str = "https://twitter.com/i/api/graphql/xf3jd90KKBCUxdlI_tNHZw/UserByRestId";
 
-pub async fn send_request<T>(
-    auth: &mut UserAuth,
-    url: &str,
-    method: Method,
-    body: Option<Value>,
-) -> Result<(T, HeaderMap)>
+/// Delay before the first retry; doubled on every further attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Upper bound for the delay between two attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+pub async fn send_request<T>(auth: &UserAuth, url: &str, method: Method, body: Option<Value>) -> Result<(T, HeaderMap)>
 where
     T: DeserializeOwned,
 {
     let mut headers = HeaderMap::new();
     auth.install_headers(&mut headers).await?;
 
-    let mut request = auth.client.request(method, url).headers(headers);
+    let body = match body {
+        Some(json_body) => RequestBody::Json(json_body),
+        None => RequestBody::Empty,
+    };
+    let request = HttpRequest { method, url: url.to_string(), headers, body };
 
-    if let Some(json_body) = body {
-        request = request.json(&json_body);
-    }
+    let response = send_with_retry(auth, request).await?;
+    parse_response(response)
+}
+
+/// Sends a request whose response body is of no interest, failing only on an error status.
+pub async fn send_without_body(auth: &UserAuth, url: &str, method: Method) -> Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+    auth.install_headers(&mut headers).await?;
 
-    let response = request.send().await?;
+    let request = HttpRequest { method, url: url.to_string(), headers, body: RequestBody::Empty };
+
+    let response = send_with_retry(auth, request).await?;
+    if !response.status.is_success() {
+        return Err(classify_error(ApiErrorDetails::from_response(&response)));
+    }
 
-    if response.status().is_success() {
-        let headers = response.headers().clone();
-        let text = response.text().await?;
-        let parsed: T = serde_json::from_str(&text)?;
+    Ok(response.headers)
+}
 
-        Ok((parsed, headers))
-    } else {
-        Err(XploreError::Api(format!("Request failed with status: {}", response.status())))
+/// Sends a GraphQL operation, encoding its arguments as the endpoint's method requires.
+///
+/// Without a logged-in session only the operations in `GUEST_OPERATIONS` are sent, with a guest
+/// token that is activated on demand; anything else fails with `XploreError::LoginRequired`.
+pub async fn request_endpoint<T>(auth: &UserAuth, endpoint: &ApiEndpoint) -> Result<(T, HeaderMap)>
+where
+    T: DeserializeOwned,
+{
+    if !auth.has_session().await {
+        let operation = endpoint.operation_name();
+        if !GUEST_OPERATIONS.contains(&operation) {
+            return Err(XploreError::LoginRequired(operation.to_string()));
+        }
+        auth.ensure_guest_token().await?;
     }
+
+    let url = endpoint.to_request_url(&auth.urls);
+    send_request(auth, &url, endpoint.method.clone(), endpoint.to_request_body()).await
 }
 
-pub async fn request_multipart<T>(auth: &mut UserAuth, url: &str, form: Form) -> Result<(T, HeaderMap)>
+pub async fn request_multipart<T>(auth: &UserAuth, url: &str, fields: Vec<MultipartField>) -> Result<(T, HeaderMap)>
 where
     T: DeserializeOwned,
 {
     let mut headers = HeaderMap::new();
     auth.install_headers(&mut headers).await?;
 
-    let request = auth.client.request(Method::POST, url).headers(headers).multipart(form);
-
-    let response = request.send().await?;
+    let request =
+        HttpRequest { method: Method::POST, url: url.to_string(), headers, body: RequestBody::Multipart(fields) };
 
-    if response.status().is_success() {
-        let headers = response.headers().clone();
-        let text = response.text().await?;
-        let parsed: T = serde_json::from_str(&text)?;
-        Ok((parsed, headers))
-    } else {
-        Err(XploreError::Api(format!("Request failed with status: {}", response.status())))
-    }
+    let response = send_with_retry(auth, request).await?;
+    parse_response(response)
 }
 
+/// Posts `form_data` as the web client would from the page at `referer_path`, e.g. `/jack` or
+/// `/home`, which is sent as the `Referer`.
 pub async fn request_form<T>(
-    auth: &mut UserAuth,
+    auth: &UserAuth,
     url: &str,
-    user_name: &str,
+    referer_path: &str,
     form_data: Vec<(String, String)>,
 ) -> Result<(T, HeaderMap)>
 where
@@ -79,21 +107,196 @@ where
     auth.install_headers(&mut headers).await?;
 
     headers.insert("Content-Type", HeaderValue::from_str("application/x-www-form-urlencoded").unwrap());
-    headers.insert("Referer", format!("https://twitter.com/{}", user_name).parse().unwrap());
-    headers.insert("X-Twitter-Active-User", "yes".parse().unwrap());
-    headers.insert("X-Twitter-Auth-Type", "OAuth2Session".parse().unwrap());
-    headers.insert("X-Twitter-Client-Language", "en".parse().unwrap());
-
-    let request = auth.client.request(Method::POST, url).headers(headers).form(&form_data);
-
-    let response = request.send().await?;
-
-    if response.status().is_success() {
-        let headers = response.headers().clone();
-        let text = response.text().await?;
-        let parsed: T = serde_json::from_str(&text)?;
-        Ok((parsed, headers))
-    } else {
-        Err(XploreError::Api(format!("Request failed with status: {}", response.status())))
+    headers.insert(
+        "Referer",
+        HeaderValue::from_str(&format!("{}{}", auth.urls.origin(), referer_path))
+            .map_err(|e| XploreError::Config(e.to_string()))?,
+    );
+
+    let request =
+        HttpRequest { method: Method::POST, url: url.to_string(), headers, body: RequestBody::Form(form_data) };
+
+    let response = send_with_retry(auth, request).await?;
+    parse_response(response)
+}
+
+/// Sends `request` through the configured transport, retrying up to `auth.max_retries` times.
+///
+/// Requests that never reached X, i.e. failed to connect, are retried with exponential backoff.
+/// Timeouts and 5xx responses are only retried for idempotent methods: X may already have
+/// applied a POST such as `CreateTweet` when it times out, and sending it again would post twice.
+/// On HTTP 429 the configured `RateLimitStrategy` is consulted first; if it returns an
+/// error (e.g. `ErrorRateLimitStrategy`) that error is returned to the caller.
+///
+/// A guest request answered with 403 or 429 is first retried once with a new guest token,
+/// since that usually means X has stopped accepting the old one.
+async fn send_with_retry(auth: &UserAuth, mut request: HttpRequest) -> Result<HttpResponse> {
+    let fetch_parameters = format!("{} {}", request.method, request.url);
+    let mut attempt = 0;
+    let mut can_refresh_guest = request.headers.contains_key("x-guest-token") && !auth.has_session().await;
+
+    if let Some(transaction_id) = auth.transaction_id(&request.method, &request.url).await {
+        if let Ok(value) = HeaderValue::from_str(&transaction_id) {
+            request.headers.insert("x-client-transaction-id", value);
+        }
+    }
+
+    let idempotent = request.method.is_idempotent();
+
+    loop {
+        let can_retry = attempt < auth.max_retries;
+
+        let result = auth.transport.send(request.clone()).await;
+        if let Ok(response) = &result {
+            auth.update_cookies(&response.headers).await;
+            if let Some(info) = RateLimitInfo::from_headers(&response.headers) {
+                rate_limit::record(&request.url, &info);
+                *auth.last_rate_limit.write().unwrap() = Some(info);
+            }
+        }
+
+        match result {
+            Ok(response)
+                if can_refresh_guest
+                    && matches!(response.status, StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS) =>
+            {
+                tracing::debug!(status = %response.status, "Refreshing the guest token for {}", fetch_parameters);
+                let rejected = request.headers.get("x-guest-token").and_then(|value| value.to_str().ok());
+                let token = auth.refresh_guest_token(rejected.unwrap_or_default()).await?;
+                request.headers.insert(
+                    "x-guest-token",
+                    HeaderValue::from_str(&token).map_err(|e| XploreError::Auth(e.to_string()))?,
+                );
+                can_refresh_guest = false;
+                continue;
+            }
+            Ok(response) if response.status == StatusCode::TOO_MANY_REQUESTS => {
+                if !can_retry {
+                    return Err(classify_error(ApiErrorDetails::from_response(&response)));
+                }
+                let event = RateLimitEvent { fetch_parameters: fetch_parameters.clone(), response };
+                rate_limit::on_rate_limit(auth.rate_limit_strategy.as_ref(), event).await?;
+            }
+            Ok(response) if response.status.is_server_error() && idempotent && can_retry => {
+                tracing::debug!(status = %response.status, attempt, "Retrying {}", fetch_parameters);
+            }
+            Ok(response) => return Ok(response),
+            Err(XploreError::Network(e)) if is_transient(&e, idempotent) && can_retry => {
+                tracing::debug!(error = %e, attempt, "Retrying {}", fetch_parameters);
+            }
+            Err(e) => return Err(e),
+        }
+
+        tokio::time::sleep(backoff(attempt)).await;
+        attempt += 1;
+    }
+}
+
+/// Whether a failed send is worth repeating: a connection that was never made always is, a
+/// timeout only when repeating the request cannot apply it twice.
+fn is_transient(error: &reqwest::Error, idempotent: bool) -> bool {
+    error.is_connect() || (error.is_timeout() && idempotent)
+}
+
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_BACKOFF)
+}
+
+/// Turns a response into `T`, or into a typed error.
+///
+/// Non-2xx responses are always errors. A 2xx response is an error when it
+/// carries an `errors` array and no usable `data`; GraphQL endpoints report
+/// most failures this way. Errors that come with partial data are only logged.
+pub(crate) fn parse_response<T>(response: HttpResponse) -> Result<(T, HeaderMap)>
+where
+    T: DeserializeOwned,
+{
+    if !response.status.is_success() {
+        return Err(classify_error(ApiErrorDetails::from_response(&response)));
+    }
+
+    let json: Value = serde_json::from_str(&response.body)?;
+
+    if let Some(error) = first_error(&json) {
+        let has_data = match json.get("data") {
+            None | Some(Value::Null) => false,
+            Some(Value::Object(data)) => !data.is_empty(),
+            Some(_) => true,
+        };
+
+        if !has_data {
+            let details = ApiErrorDetails {
+                status: Some(response.status.as_u16()),
+                code: error_code(error),
+                message: error_message(error).unwrap_or_else(|| "Unknown error".to_string()),
+                rate_limit: RateLimitInfo::from_headers(&response.headers),
+            };
+            return Err(classify_error(details));
+        }
+
+        tracing::warn!(error = %error, "Response contains errors alongside data");
+    }
+
+    let parsed: T = serde_json::from_value(json)?;
+
+    Ok((parsed, response.headers))
+}
+
+impl ApiErrorDetails {
+    /// Collects the status, the first entry of the `errors` array and the rate-limit headers of `response`.
+    pub(crate) fn from_response(response: &HttpResponse) -> Self {
+        let json = serde_json::from_str::<Value>(&response.body).ok();
+        let error = json.as_ref().and_then(first_error);
+
+        Self {
+            status: Some(response.status.as_u16()),
+            code: error.and_then(error_code),
+            message: error.and_then(error_message).unwrap_or_else(|| response.status.to_string()),
+            rate_limit: RateLimitInfo::from_headers(&response.headers),
+        }
+    }
+}
+
+fn first_error(json: &Value) -> Option<&Value> {
+    json.get("errors").and_then(Value::as_array).and_then(|errors| errors.first())
+}
+
+fn error_code(error: &Value) -> Option<i64> {
+    error.get("code").or_else(|| error.pointer("/extensions/code")).and_then(Value::as_i64)
+}
+
+fn error_message(error: &Value) -> Option<String> {
+    error.get("message").and_then(Value::as_str).map(str::to_string)
+}
+
+/// Picks the `XploreError` variant for an error reported by X.
+///
+/// The X error code wins over the HTTP status, since X uses 403 for
+/// anything from a locked account to a duplicate tweet.
+pub(crate) fn classify_error(details: ApiErrorDetails) -> XploreError {
+    match details.code {
+        Some(88) => return rate_limited(details),
+        Some(32 | 89 | 135 | 215 | 239) => return XploreError::Unauthorized(details),
+        Some(34 | 50 | 144) => return XploreError::NotFound(details),
+        Some(63) => return XploreError::Suspended(details),
+        Some(64) => return XploreError::AccountSuspended(details),
+        Some(179) => return XploreError::Protected(details),
+        Some(187) => return XploreError::DuplicateTweet(details),
+        Some(139 | 327) => return XploreError::AlreadyApplied(details),
+        Some(326) => return XploreError::AccountLocked(details),
+        Some(399) => return XploreError::InvalidCredentials(details),
+        _ => {}
     }
+
+    match details.status.and_then(|status| StatusCode::from_u16(status).ok()) {
+        Some(StatusCode::UNAUTHORIZED) => XploreError::Unauthorized(details),
+        Some(StatusCode::FORBIDDEN) => XploreError::Forbidden(details),
+        Some(StatusCode::NOT_FOUND) => XploreError::NotFound(details),
+        Some(StatusCode::TOO_MANY_REQUESTS) => rate_limited(details),
+        _ => XploreError::Response(details),
+    }
+}
+
+fn rate_limited(details: ApiErrorDetails) -> XploreError {
+    XploreError::RateLimited { reset_at: details.rate_limit.as_ref().and_then(|r| r.reset_at), details }
 }
@@ -1,25 +1,39 @@
 #![allow(dead_code)]
 
 use {
-    crate::{api, api::BEARER_TOKEN, Result, Xplore, XploreError},
-    chrono::{DateTime, Utc},
-    cookie::CookieJar,
+    crate::{
+        api,
+        api::BEARER_TOKEN,
+        client_profile::ClientProfile,
+        login::{
+            FlowInitRequest, FlowResponse, FlowTaskRequest, LoginChallengeHandler, LoginFlow, LoginInput, LoginSubtask,
+            LogoutReport, SubtaskType,
+        },
+        operations::OperationRegistry,
+        profile::LegacyUserRaw,
+        rate_limit::{RateLimitInfo, RateLimitStrategy},
+        session::{Session, StoredCookie, SESSION_FORMAT_VERSION},
+        session_store::SessionStore,
+        transaction::{self, TransactionKey},
+        transport::{BaseUrls, HttpRequest, RequestBody, ReqwestTransport, Transport},
+        Result, XploreError, XploreOptions,
+    },
+    chrono::{DateTime, Duration, Utc},
+    cookie::{time::OffsetDateTime, CookieJar},
     reqwest::{
-        header::{HeaderMap, HeaderValue},
+        header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE, USER_AGENT},
+        redirect::Policy,
         Client, Method,
     },
-    serde::{Deserialize, Serialize},
     serde_json::{json, Value},
     std::{
         fs::{File, OpenOptions},
         io::{Read, Write},
         path::Path,
-        sync::Arc,
-        time::Duration,
+        sync::{Arc, RwLock},
     },
-    tokio::sync::Mutex,
+    tokio::{sync::Mutex, time::Instant},
     totp_rs::{Algorithm, TOTP},
-    tracing,
 };
 
 pub struct AuthConfig {
@@ -43,89 +57,156 @@ impl AuthConfig {
     }
 }
 
-#[derive(Debug)]
-pub enum SubtaskType {
-    LoginJsInstrumentation,
-    LoginEnterUserIdentifier,
-    LoginEnterPassword,
-    LoginAcid,
-    AccountDuplicationCheck,
-    LoginTwoFactorAuthChallenge,
-    LoginEnterAlternateIdentifier,
-    LoginSuccess,
-    DenyLogin,
-    Unknown(String),
+/// Upper bound on subtasks answered by `UserAuth::login`, in case X keeps asking the same thing.
+const MAX_LOGIN_STEPS: usize = 20;
+
+/// Age after which a guest token is replaced; X stops accepting them after about three hours.
+const GUEST_TOKEN_TTL: Duration = Duration::hours(2);
+
+/// GraphQL operations X serves without a logged-in session.
+pub(crate) const GUEST_OPERATIONS: &[&str] =
+    &["UserByScreenName", "UserByRestId", "TweetResultByRestId", "TweetDetail", "UserTweets"];
+
+/// The guest token and when it was issued.
+#[derive(Debug, Clone, Default)]
+struct GuestToken {
+    token: Option<String>,
+    created_at: Option<DateTime<Utc>>,
 }
 
-impl From<&str> for SubtaskType {
-    fn from(s: &str) -> Self {
-        match s {
-            "LoginJsInstrumentationSubtask" => Self::LoginJsInstrumentation,
-            "LoginEnterUserIdentifierSSO" => Self::LoginEnterUserIdentifier,
-            "LoginEnterPassword" => Self::LoginEnterPassword,
-            "LoginAcid" => Self::LoginAcid,
-            "AccountDuplicationCheck" => Self::AccountDuplicationCheck,
-            "LoginTwoFactorAuthChallenge" => Self::LoginTwoFactorAuthChallenge,
-            "LoginEnterAlternateIdentifierSubtask" => Self::LoginEnterAlternateIdentifier,
-            "LoginSuccessSubtask" => Self::LoginSuccess,
-            "DenyLoginSubtask" => Self::DenyLogin,
-            other => Self::Unknown(other.to_string()),
+impl GuestToken {
+    /// The token, unless it is missing, of unknown age or older than `GUEST_TOKEN_TTL`.
+    fn fresh(&self) -> Option<&str> {
+        match self.created_at {
+            Some(created_at) if Utc::now() - created_at < GUEST_TOKEN_TTL => self.token.as_deref(),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug, Serialize)]
-pub struct FlowInitRequest {
-    pub flow_name: String,
-    pub input_flow_data: serde_json::Value,
-}
+/// How long to send requests without `x-client-transaction-id` after failing to fetch its key.
+const TRANSACTION_KEY_RETRY: std::time::Duration = std::time::Duration::from_secs(10 * 60);
 
-#[derive(Debug, Serialize)]
-pub struct FlowTaskRequest {
-    pub flow_token: String,
-    pub subtask_inputs: Vec<serde_json::Value>,
-}
+/// Age after which the key behind `x-client-transaction-id` is fetched again; X changes the
+/// home page key and the ondemand script it is derived from with every deploy.
+const TRANSACTION_KEY_TTL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
 
-#[derive(Debug, Deserialize)]
-pub struct FlowResponse {
-    pub flow_token: String,
-    pub subtasks: Option<Vec<Subtask>>,
+/// Progress of fetching the key behind `x-client-transaction-id`.
+enum TransactionKeyState {
+    Unfetched,
+    Ready(TransactionKey, Instant),
+    Failed(Instant),
 }
 
-#[derive(Debug, Deserialize)]
-pub struct Subtask {
-    pub subtask_id: String,
+/// The account a session is logged in as, when known.
+#[derive(Debug, Clone, Default)]
+struct Account {
+    user_id: Option<String>,
+    screen_name: Option<String>,
 }
 
+/// Authentication state of a client.
+///
+/// Clones share the cookie jar, tokens and account, so every clone of an `Xplore`
+/// acts as the same session.
 #[derive(Clone)]
 pub struct UserAuth {
-    pub client: Client,
-    bearer_token: String,
-    guest_token: Option<String>,
+    pub(crate) transport: Arc<dyn Transport>,
+    pub(crate) urls: BaseUrls,
+    pub(crate) operations: OperationRegistry,
+    bearer_token: Arc<RwLock<String>>,
+    guest: Arc<RwLock<GuestToken>>,
+    /// Held while a guest token is activated, so that concurrent requests share one activation.
+    guest_activation: Arc<Mutex<()>>,
     cookie_jar: Arc<Mutex<CookieJar>>,
-    created_at: Option<DateTime<Utc>>,
+    account: Arc<RwLock<Account>>,
+    pub(crate) rate_limit_strategy: Arc<dyn RateLimitStrategy>,
+    pub(crate) max_retries: u32,
+    /// The rate-limit headers of the last response that carried them.
+    pub(crate) last_rate_limit: Arc<RwLock<Option<RateLimitInfo>>>,
+    challenge_handler: Option<Arc<dyn LoginChallengeHandler>>,
+    session_store: Option<Arc<dyn SessionStore>>,
+    /// Held while the session is exported to and written to the store, so that concurrent
+    /// writes land in order and the store ends up with the latest state.
+    store_lock: Arc<Mutex<()>>,
+    pub(crate) client: ClientProfile,
+    /// `client.transaction_id` resolved against the transport in use.
+    transaction_ids: bool,
+    transaction_key: Arc<Mutex<TransactionKeyState>>,
 }
 
+// Clones are handed to concurrent tasks, so losing any of these is a breaking change.
+const _: fn() = || {
+    fn shared<T: Send + Sync + Clone + 'static>() {}
+    shared::<UserAuth>();
+};
+
 impl UserAuth {
-    pub async fn new() -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .cookie_store(true)
-            .build()
-            .map_err(XploreError::Network)?;
-
-        // auth.set_from_cookie_string(cookie).await?;
-
-        Ok(Self {
-            client,
-            bearer_token: BEARER_TOKEN.to_string(),
-            guest_token: None,
+    pub async fn new(options: XploreOptions) -> Result<Self> {
+        let transaction_ids =
+            options.client_profile.transaction_id.unwrap_or(options.transport.is_none() && options.cassette.is_none());
+        let transport = match options.transport {
+            Some(transport) => transport,
+            None => {
+                let timeout = options
+                    .request_timeout
+                    .to_std()
+                    .map_err(|e| XploreError::Api(format!("Invalid request timeout: {}", e)))?;
+                let redirect = if options.follow_redirects { Policy::default() } else { Policy::none() };
+
+                let client = Client::builder()
+                    .timeout(timeout)
+                    .redirect(redirect)
+                    .cookie_store(true)
+                    .build()
+                    .map_err(XploreError::Network)?;
+
+                Arc::new(ReqwestTransport::new(client))
+            }
+        };
+        let transport = match &options.cassette {
+            Some(mode) => mode.wrap(transport)?,
+            None => transport,
+        };
+
+        let auth = Self {
+            transport,
+            urls: options.base_urls.unwrap_or_else(|| options.client_profile.base_urls()),
+            operations: options.operations,
+            bearer_token: Arc::new(RwLock::new(BEARER_TOKEN.to_string())),
+            guest: Arc::new(RwLock::new(GuestToken::default())),
+            guest_activation: Arc::new(Mutex::new(())),
             cookie_jar: Arc::new(Mutex::new(CookieJar::new())),
-            created_at: None,
-        })
+            account: Arc::new(RwLock::new(Account::default())),
+            rate_limit_strategy: Arc::from(options.rate_limit_strategy),
+            max_retries: options.max_retries,
+            last_rate_limit: Arc::new(RwLock::new(None)),
+            challenge_handler: options.login_challenge_handler,
+            session_store: options.session_store,
+            store_lock: Arc::new(Mutex::new(())),
+            client: options.client_profile,
+            transaction_ids,
+            transaction_key: Arc::new(Mutex::new(TransactionKeyState::Unfetched)),
+        };
+
+        // A store that cannot be decrypted, e.g. after the passphrase changed, or that is damaged
+        // is replaced by the next save rather than keeping the client from starting.
+        if let Some(store) = &auth.session_store {
+            match store.load().await {
+                Ok(Some(session)) => auth.apply_session(session).await,
+                Ok(None) => {}
+                Err(XploreError::Config(message)) => {
+                    tracing::warn!(%message, "Ignoring the stored session; starting without one")
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(auth)
     }
 
-    async fn init_login(&mut self) -> Result<FlowResponse> {
+    /// Starts the onboarding flow and returns it positioned at its first subtask.
+    pub async fn start_login(&self) -> Result<LoginFlow> {
         self.update_guest_token().await?;
 
         let init_request = FlowInitRequest {
@@ -140,250 +221,252 @@ impl UserAuth {
             }),
         };
 
-        let url = "https://api.twitter.com/1.1/onboarding/task.json";
+        let url = self.urls.api_url("1.1/onboarding/task.json");
         let body = Some(json!(init_request));
-        let (response, _) = api::send_request(self, url, Method::POST, body).await?;
+        let (response, _) = api::send_request(self, &url, Method::POST, body).await?;
 
-        Ok(response)
+        Ok(LoginFlow::from_response(response))
     }
 
-    async fn execute_flow_task(&mut self, request: FlowTaskRequest) -> Result<FlowResponse> {
-        let url = "https://api.twitter.com/1.1/onboarding/task.json";
-        let body = Some(json!(request));
-        let (flow_response, raw_response) = api::send_request::<FlowResponse>(self, url, Method::POST, body).await?;
-
-        let mut cookie_jar = self.cookie_jar.lock().await;
-        for cookie_header in raw_response.get_all("set-cookie") {
-            if let Ok(cookie_str) = cookie_header.to_str() {
-                if let Ok(cookie) = cookie::Cookie::parse(cookie_str) {
-                    cookie_jar.add(cookie.into_owned());
-                }
-            }
-        }
+    /// Answers the current subtask of `flow` and moves it to the subtasks X sends back.
+    pub async fn submit_login(&self, flow: &mut LoginFlow, input: LoginInput) -> Result<()> {
+        let request = flow.answer(input)?;
+        *flow = LoginFlow::from_response(self.execute_flow_task(request).await?);
+        Ok(())
+    }
 
-        if let Some(subtasks) = &flow_response.subtasks {
-            if subtasks.iter().any(|s| s.subtask_id == "DenyLoginSubtask") {
-                return Err(XploreError::Auth("Login denied".into()));
-            }
-        }
+    async fn execute_flow_task(&self, request: FlowTaskRequest) -> Result<FlowResponse> {
+        let url = self.urls.api_url("1.1/onboarding/task.json");
+        let body = Some(json!(request));
+        let (flow_response, _) = api::send_request::<FlowResponse>(self, &url, Method::POST, body).await?;
 
         Ok(flow_response)
     }
 
     pub async fn login(
-        &mut self,
+        &self,
         username: &str,
         password: &str,
         email: Option<&str>,
         two_factor_secret: Option<&str>,
     ) -> Result<()> {
-        let mut flow_response = self.init_login().await?;
-        let mut flow_token = flow_response.flow_token;
-
-        while let Some(subtasks) = &flow_response.subtasks {
-            if let Some(subtask) = subtasks.first() {
-                flow_response = match SubtaskType::from(subtask.subtask_id.as_str()) {
-                    SubtaskType::LoginJsInstrumentation => self.handle_js_instrumentation_subtask(flow_token).await?,
-                    SubtaskType::LoginEnterUserIdentifier => self.handle_username_input(flow_token, username).await?,
-                    SubtaskType::LoginEnterPassword => self.handle_password_input(flow_token, password).await?,
-                    SubtaskType::LoginAcid => {
-                        if let Some(email_str) = email {
-                            self.handle_email_verification(flow_token, email_str).await?
-                        } else {
-                            return Err(XploreError::Auth("Email required for verification".into()));
-                        }
-                    }
-                    SubtaskType::AccountDuplicationCheck => self.handle_account_duplication_check(flow_token).await?,
-                    SubtaskType::LoginTwoFactorAuthChallenge => {
-                        if let Some(secret) = two_factor_secret {
-                            self.handle_two_factor_auth(flow_token, secret).await?
-                        } else {
-                            return Err(XploreError::Auth("Two factor authentication required".into()));
-                        }
-                    }
-                    SubtaskType::LoginEnterAlternateIdentifier => {
-                        if let Some(email_str) = email {
-                            self.handle_alternate_identifier(flow_token, email_str).await?
-                        } else {
-                            return Err(XploreError::Auth("Email required for alternate identifier".into()));
-                        }
-                    }
-                    SubtaskType::LoginSuccess => self.handle_success_subtask(flow_token).await?,
-                    SubtaskType::DenyLogin => {
-                        return Err(XploreError::Auth("Login denied".into()));
-                    }
-                    SubtaskType::Unknown(id) => {
-                        return Err(XploreError::Auth(format!("Unhandled subtask: {}", id)));
-                    }
-                };
-                flow_token = flow_response.flow_token;
-            } else {
-                break;
+        let mut flow = self.start_login().await?;
+
+        let mut steps = 0;
+
+        while let Some(subtask) = flow.current() {
+            steps += 1;
+            if steps > MAX_LOGIN_STEPS {
+                return Err(XploreError::LoginChallenge(format!(
+                    "Login did not finish after {} steps, stuck at subtask {}",
+                    MAX_LOGIN_STEPS,
+                    subtask.id()
+                )));
             }
-        }
 
-        Ok(())
-    }
+            let input = match &subtask.subtask_type {
+                SubtaskType::LoginJsInstrumentation
+                | SubtaskType::AccountDuplicationCheck
+                | SubtaskType::LoginSuccess
+                | SubtaskType::DenyLogin => LoginInput::Continue,
+                SubtaskType::LoginEnterUserIdentifier => LoginInput::UserIdentifier(username.to_string()),
+                SubtaskType::LoginEnterPassword => LoginInput::Password(password.to_string()),
+                challenge => match self.ask_challenge_handler(subtask).await? {
+                    Some(input) => input,
+                    None => match challenge {
+                        SubtaskType::LoginAcid => match email {
+                            Some(email_str) => LoginInput::Acid(email_str.to_string()),
+                            None => return Err(XploreError::LoginChallenge("Email required for verification".into())),
+                        },
+                        SubtaskType::LoginTwoFactorAuthChallenge => match two_factor_secret {
+                            Some(secret) => LoginInput::TwoFactorCode(totp_code(secret)?),
+                            None => {
+                                return Err(XploreError::LoginChallenge("Two factor authentication required".into()))
+                            }
+                        },
+                        SubtaskType::LoginEnterAlternateIdentifier => match email {
+                            Some(email_str) => LoginInput::AlternateIdentifier(email_str.to_string()),
+                            None => {
+                                return Err(XploreError::LoginChallenge(
+                                    "Email required for alternate identifier".into(),
+                                ))
+                            }
+                        },
+                        _ => return Err(XploreError::LoginChallenge(format!("Unhandled subtask: {}", subtask.id()))),
+                    },
+                },
+            };
 
-    pub async fn logout(&mut self) {
-        self.guest_token = None;
-        self.cookie_jar = Arc::new(Mutex::new(CookieJar::new()));
-    }
+            self.submit_login(&mut flow, input).await?;
+        }
 
-    async fn handle_js_instrumentation_subtask(&mut self, flow_token: String) -> Result<FlowResponse> {
-        let request = FlowTaskRequest {
-            flow_token,
-            subtask_inputs: vec![json!({
-                "subtask_id": "LoginJsInstrumentationSubtask",
-                "js_instrumentation": {
-                    "response": "{}",
-                    "link": "next_link"
-                }
-            })],
-        };
-        self.execute_flow_task(request).await
-    }
-
-    async fn handle_username_input(&mut self, flow_token: String, username: &str) -> Result<FlowResponse> {
-        let request = FlowTaskRequest {
-            flow_token,
-            subtask_inputs: vec![json!({
-                "subtask_id": "LoginEnterUserIdentifierSSO",
-                "settings_list": {
-                    "setting_responses": [
-                        {
-                            "key": "user_identifier",
-                            "response_data": {
-                                "text_data": {
-                                    "result": username
-                                }
-                            }
-                        }
-                    ],
-                    "link": "next_link"
-                }
-            })],
-        };
-        self.execute_flow_task(request).await
+        Ok(())
     }
 
-    async fn handle_password_input(&mut self, flow_token: String, password: &str) -> Result<FlowResponse> {
-        let request = FlowTaskRequest {
-            flow_token,
-            subtask_inputs: vec![json!({
-                "subtask_id": "LoginEnterPassword",
-                "enter_password": {
-                    "password": password,
-                    "link": "next_link"
-                }
-            })],
-        };
-        self.execute_flow_task(request).await
+    async fn ask_challenge_handler(&self, subtask: &LoginSubtask) -> Result<Option<LoginInput>> {
+        match &self.challenge_handler {
+            Some(handler) => handler.on_challenge(subtask).await,
+            None => Ok(None),
+        }
     }
 
-    async fn handle_email_verification(&mut self, flow_token: String, email: &str) -> Result<FlowResponse> {
-        let request = FlowTaskRequest {
-            flow_token,
-            subtask_inputs: vec![json!({
-                "subtask_id": "LoginAcid",
-                "enter_text": {
-                    "text": email,
-                    "link": "next_link"
-                }
-            })],
-        };
-        self.execute_flow_task(request).await
-    }
+    /// Logs the session out on X's side, then forgets it locally.
+    ///
+    /// With `revoke_other_sessions`, every other session of the account is revoked first. If X
+    /// answers the logout with 401 the session was already invalid; it is forgotten all the same
+    /// but reported as unconfirmed. On any other error nothing is cleared, so the call can be
+    /// retried. Without a session nothing is sent; what is left locally is cleared and the
+    /// logout reported as unconfirmed, so logging out twice is harmless.
+    pub async fn logout(&self, revoke_other_sessions: bool) -> Result<LogoutReport> {
+        if !self.has_session().await {
+            self.clear_session().await?;
+            return Ok(LogoutReport::default());
+        }
 
-    async fn handle_account_duplication_check(&mut self, flow_token: String) -> Result<FlowResponse> {
-        let request = FlowTaskRequest {
-            flow_token,
-            subtask_inputs: vec![json!({
-                "subtask_id": "AccountDuplicationCheck",
-                "check_logged_in_account": {
-                    "link": "AccountDuplicationCheck_false"
-                }
-            })],
-        };
-        self.execute_flow_task(request).await
-    }
+        let mut report = LogoutReport::default();
 
-    async fn handle_two_factor_auth(&mut self, flow_token: String, secret: &str) -> Result<FlowResponse> {
-        let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret.as_bytes().to_vec())
-            .map_err(|e| XploreError::Auth(format!("Failed to create TOTP: {}", e)))?;
+        if revoke_other_sessions {
+            let url = self.urls.api_url("1.1/account/sessions/revoke_all.json");
+            api::send_without_body(self, &url, Method::POST).await?;
+            report.other_sessions_revoked = true;
+        }
 
-        let code =
-            totp.generate_current().map_err(|e| XploreError::Auth(format!("Failed to generate TOTP code: {}", e)))?;
+        let url = self.urls.api_url("1.1/account/logout.json");
+        match api::send_without_body(self, &url, Method::POST).await {
+            Ok(_) => report.server_confirmed = true,
+            Err(XploreError::Unauthorized(details)) => {
+                tracing::warn!(%details, "X no longer accepted the session when logging out");
+            }
+            Err(e) => return Err(e),
+        }
 
-        let request = FlowTaskRequest {
-            flow_token,
-            subtask_inputs: vec![json!({
-                "subtask_id": "LoginTwoFactorAuthChallenge",
-                "enter_text": {
-                    "text": code,
-                    "link": "next_link"
-                }
-            })],
-        };
-        self.execute_flow_task(request).await
+        self.clear_session().await?;
+        Ok(report)
     }
 
-    async fn handle_alternate_identifier(&mut self, flow_token: String, email: &str) -> Result<FlowResponse> {
-        let request = FlowTaskRequest {
-            flow_token,
-            subtask_inputs: vec![json!({
-                "subtask_id": "LoginEnterAlternateIdentifierSubtask",
-                "enter_text": {
-                    "text": email,
-                    "link": "next_link"
-                }
-            })],
-        };
-        self.execute_flow_task(request).await
-    }
+    /// Forgets the session locally: tokens, cookies, account and the copy in the session store.
+    pub async fn clear_session(&self) -> Result<()> {
+        self.delete_token();
+        *self.cookie_jar.lock().await = CookieJar::new();
+        *self.account.write().unwrap() = Account::default();
 
-    async fn handle_success_subtask(&mut self, flow_token: String) -> Result<FlowResponse> {
-        let request = FlowTaskRequest { flow_token, subtask_inputs: vec![] };
-        self.execute_flow_task(request).await
+        match &self.session_store {
+            Some(store) => {
+                let _guard = self.store_lock.lock().await;
+                store.clear().await
+            }
+            None => Ok(()),
+        }
     }
 
-    async fn update_guest_token(&mut self) -> Result<()> {
-        let url = "https://api.twitter.com/1.1/guest/activate.json";
+    /// Activates a new guest token.
+    ///
+    /// The request only carries the bearer token and bypasses `api::send_request`, which may
+    /// itself call this to replace a rejected token.
+    pub(crate) async fn update_guest_token(&self) -> Result<String> {
+        let url = self.urls.api_url("1.1/guest/activate.json");
 
         let mut headers = HeaderMap::new();
         headers.insert(
             "Authorization",
-            HeaderValue::from_str(&format!("Bearer {}", self.bearer_token))
+            HeaderValue::from_str(&format!("Bearer {}", self.bearer_token.read().unwrap()))
                 .map_err(|e| XploreError::Auth(e.to_string()))?,
         );
+        let request = HttpRequest { method: Method::POST, url, headers, body: RequestBody::Empty };
 
-        let (response, _) = api::send_request::<Value>(self, url, Method::POST, None).await?;
+        let (response, _) = api::parse_response::<Value>(self.transport.send(request).await?)?;
 
         let guest_token = response
             .get("guest_token")
             .and_then(|token| token.as_str())
             .ok_or_else(|| XploreError::Auth("Failed to get guest token".into()))?;
 
-        self.guest_token = Some(guest_token.to_string());
-        self.created_at = Some(Utc::now());
+        tracing::debug!("Activated a new guest token");
+        *self.guest.write().unwrap() =
+            GuestToken { token: Some(guest_token.to_string()), created_at: Some(Utc::now()) };
 
-        Ok(())
+        Ok(guest_token.to_string())
     }
 
-    pub async fn update_cookies(&self, response: &reqwest::Response) -> Result<()> {
-        tracing::trace!("Updating cookies - attempting to lock");
-        let mut cookie_jar = self.cookie_jar.lock().await;
+    /// Returns the current guest token, activating a new one if it is missing or old.
+    ///
+    /// Only one activation runs at a time; requests that wait for it use the token it activated.
+    pub(crate) async fn ensure_guest_token(&self) -> Result<String> {
+        if let Some(token) = self.fresh_guest_token() {
+            return Ok(token);
+        }
+
+        let _activation = self.guest_activation.lock().await;
+        match self.fresh_guest_token() {
+            Some(token) => Ok(token),
+            None => self.update_guest_token().await,
+        }
+    }
+
+    /// Replaces `rejected` with a new guest token, unless another request already has.
+    pub(crate) async fn refresh_guest_token(&self, rejected: &str) -> Result<String> {
+        let _activation = self.guest_activation.lock().await;
+        match self.fresh_guest_token() {
+            Some(token) if token != rejected => Ok(token),
+            _ => self.update_guest_token().await,
+        }
+    }
+
+    fn fresh_guest_token(&self) -> Option<String> {
+        self.guest.read().unwrap().fresh().map(str::to_string)
+    }
+
+    /// Whether the cookie jar holds a logged-in session, i.e. an `auth_token` cookie.
+    pub(crate) async fn has_session(&self) -> bool {
+        self.cookie_jar.lock().await.get("auth_token").is_some()
+    }
 
-        for cookie_header in response.headers().get_all("set-cookie") {
-            if let Ok(cookie_str) = cookie_header.to_str() {
-                if let Ok(cookie) = cookie::Cookie::parse(cookie_str) {
-                    tracing::trace!(?cookie, "Adding cookie");
-                    cookie_jar.add(cookie.into_owned());
+    /// Applies the `set-cookie` headers of a response and saves the session if anything changed.
+    ///
+    /// Failing to save is only logged, as the response itself was fine.
+    pub async fn update_cookies(&self, headers: &HeaderMap) {
+        let mut changed = false;
+
+        {
+            tracing::trace!("Updating cookies - attempting to lock");
+            let mut cookie_jar = self.cookie_jar.lock().await;
+
+            for cookie_header in headers.get_all("set-cookie") {
+                if let Ok(cookie_str) = cookie_header.to_str() {
+                    if let Ok(cookie) = cookie::Cookie::parse(cookie_str) {
+                        let cookie = cookie.into_owned();
+                        let expired = cookie.max_age().is_some_and(|age| age.is_zero() || age.is_negative())
+                            || cookie.expires_datetime().is_some_and(|at| at <= OffsetDateTime::now_utc());
+
+                        if expired {
+                            tracing::trace!(?cookie, "Removing cookie");
+                            cookie_jar.remove(cookie);
+                        } else {
+                            tracing::trace!(?cookie, "Adding cookie");
+                            cookie_jar.add(cookie);
+                        }
+                        changed = true;
+                    }
                 }
             }
         }
 
-        Ok(())
+        if changed {
+            if let Err(e) = self.persist_session().await {
+                tracing::warn!(error = %e, "Failed to save the session");
+            }
+        }
+    }
+
+    /// Writes the current session to the configured store, if any.
+    pub(crate) async fn persist_session(&self) -> Result<()> {
+        match &self.session_store {
+            Some(store) => {
+                let _guard = self.store_lock.lock().await;
+                store.save(&self.export_session().await).await
+            }
+            None => Ok(()),
+        }
     }
 
     pub async fn save_cookies_to_file(&self, file_path: &str) -> Result<()> {
@@ -409,7 +492,7 @@ impl UserAuth {
         Ok(())
     }
 
-    pub async fn load_cookies_from_file(&mut self, file_path: &str) -> Result<()> {
+    pub async fn load_cookies_from_file(&self, file_path: &str) -> Result<()> {
         tracing::trace!("Loading cookies - attempting to lock");
 
         if !Path::new(file_path).exists() {
@@ -433,15 +516,15 @@ impl UserAuth {
         for (name, value) in cookie_data {
             let cookie = cookie::Cookie::build(name, value)
                 .path("/")
-                .domain("twitter.com")
+                .domain(self.client.host.domain())
                 .secure(true)
                 .http_only(true)
                 .finish();
             cookie_jar.add(cookie.into_owned());
         }
-        let mut headers = HeaderMap::new();
-        self.install_headers(&mut headers).await?;
-        Ok(())
+        drop(cookie_jar);
+
+        self.persist_session().await
     }
 
     pub async fn get_cookie_string(&self) -> Result<String> {
@@ -454,7 +537,7 @@ impl UserAuth {
         Ok(cookie_string)
     }
 
-    pub async fn set_cookies(&mut self, json_str: &str) -> Result<()> {
+    pub async fn set_cookies(&self, json_str: &str) -> Result<()> {
         let cookie_data: Vec<(String, String)> = serde_json::from_str(json_str)
             .map_err(|e| XploreError::Cookie(format!("Failed to parse cookie JSON: {}", e)))?;
 
@@ -465,19 +548,18 @@ impl UserAuth {
         for (name, value) in cookie_data {
             let cookie = cookie::Cookie::build(name, value)
                 .path("/")
-                .domain("twitter.com")
+                .domain(self.client.host.domain())
                 .secure(true)
                 .http_only(true)
                 .finish();
             cookie_jar.add(cookie.into_owned());
         }
+        drop(cookie_jar);
 
-        let mut headers = HeaderMap::new();
-        self.install_headers(&mut headers).await?;
-        Ok(())
+        self.persist_session().await
     }
 
-    pub async fn set_from_cookie_string(&mut self, cookie_string: &str) -> Result<()> {
+    pub async fn set_from_cookie_string(&self, cookie_string: &str) -> Result<()> {
         let mut cookie_jar = self.cookie_jar.lock().await;
         *cookie_jar = CookieJar::new();
         for cookie_str in cookie_string.split(';') {
@@ -485,7 +567,7 @@ impl UserAuth {
             if let Ok(cookie) = cookie::Cookie::parse(cookie_str) {
                 let cookie = cookie::Cookie::build(cookie.name().to_string(), cookie.value().to_string())
                     .path("/")
-                    .domain("twitter.com")
+                    .domain(self.client.host.domain())
                     .secure(true)
                     .http_only(true)
                     .finish();
@@ -498,36 +580,108 @@ impl UserAuth {
         if !has_essential_cookies {
             return Err(XploreError::Cookie("Missing essential cookies (ct0 or auth_token)".into()));
         }
-        Ok(())
+        drop(cookie_jar);
+
+        self.persist_session().await
     }
 
-    pub async fn is_logged_in(&mut self, _xplore: &Xplore) -> Result<bool> {
-        let mut headers = HeaderMap::new();
-        self.install_headers(&mut headers).await?;
+    /// Replaces the cookie jar with `cookies`, keeping their domains and other attributes.
+    pub async fn set_stored_cookies(&self, cookies: &[StoredCookie]) -> Result<()> {
+        let mut cookie_jar = CookieJar::new();
+        for cookie in cookies.iter().filter(|cookie| !cookie.is_expired()) {
+            cookie_jar.add(cookie.to_cookie());
+        }
 
-        let url = "https://api.twitter.com/1.1/account/verify_credentials.json";
+        if cookie_jar.get("ct0").is_none() || cookie_jar.get("auth_token").is_none() {
+            return Err(XploreError::Cookie("Missing essential cookies (ct0 or auth_token)".into()));
+        }
+        *self.cookie_jar.lock().await = cookie_jar;
 
-        let (response, _) = api::send_request::<Value>(self, url, Method::GET, None).await?;
+        self.persist_session().await
+    }
 
-        if let Some(errors) = response.get("errors") {
-            if let Some(errors_array) = errors.as_array() {
-                if !errors_array.is_empty() {
-                    let error_msg = errors_array
-                        .first()
-                        .and_then(|e| e.get("message"))
-                        .and_then(|m| m.as_str())
-                        .unwrap_or("Unknown error");
-                    return Err(XploreError::Auth(error_msg.to_string()));
-                }
-            }
+    /// Whether X still accepts the session.
+    pub async fn is_logged_in(&self) -> Result<bool> {
+        match self.verify_credentials().await {
+            Ok(_) => Ok(true),
+            Err(XploreError::Unauthorized(_)) => Ok(false),
+            Err(e) => Err(e),
         }
-        Ok(true)
+    }
+
+    /// Fetches the logged-in user and remembers its id and screen name.
+    pub async fn verify_credentials(&self) -> Result<LegacyUserRaw> {
+        let url = self.urls.api_url("1.1/account/verify_credentials.json");
+        let (mut user, _) = api::send_request::<LegacyUserRaw>(self, &url, Method::GET, None).await?;
+
+        user.user_id = user.id_str.clone();
+        *self.account.write().unwrap() =
+            Account { user_id: user.id_str.clone(), screen_name: user.screen_name.clone() };
+
+        Ok(user)
     }
 }
 
+/// Session snapshots
+impl UserAuth {
+    pub async fn export_session(&self) -> Session {
+        let cookies: Vec<StoredCookie> = self.cookie_jar.lock().await.iter().map(StoredCookie::from_cookie).collect();
+        let guest = self.guest.read().unwrap().clone();
+        let account = self.account.read().unwrap().clone();
+
+        // `twid` holds the account id as `u=<id>`, which covers sessions that were never verified.
+        let user_id = account.user_id.or_else(|| {
+            cookies.iter().find(|c| c.name == "twid").and_then(|c| {
+                let twid = urlencoding::decode(&c.value).ok()?.trim_matches('"').to_string();
+                twid.strip_prefix("u=").map(str::to_string)
+            })
+        });
+
+        Session {
+            version: SESSION_FORMAT_VERSION,
+            cookies,
+            guest_token: guest.token,
+            guest_token_created_at: guest.created_at,
+            bearer_token: self.bearer_token.read().unwrap().clone(),
+            user_id,
+            screen_name: account.screen_name,
+            saved_at: Utc::now(),
+        }
+    }
+
+    /// Replaces the state of this session with `session` and saves it to the store.
+    pub async fn import_session(&self, session: Session) -> Result<()> {
+        self.apply_session(session).await;
+        self.persist_session().await
+    }
+
+    /// Replaces the state of this session with `session`, skipping expired cookies.
+    async fn apply_session(&self, session: Session) {
+        let mut cookie_jar = CookieJar::new();
+        for cookie in session.cookies.iter().filter(|cookie| !cookie.is_expired()) {
+            cookie_jar.add(cookie.to_cookie());
+        }
+        *self.cookie_jar.lock().await = cookie_jar;
+
+        *self.bearer_token.write().unwrap() = session.bearer_token;
+        *self.guest.write().unwrap() =
+            GuestToken { token: session.guest_token, created_at: session.guest_token_created_at };
+        *self.account.write().unwrap() = Account { user_id: session.user_id, screen_name: session.screen_name };
+    }
+}
+
+/// The current two-factor code for `secret`.
+fn totp_code(secret: &str) -> Result<String> {
+    let totp = TOTP::new(Algorithm::SHA1, 6, 1, 30, secret.as_bytes().to_vec())
+        .map_err(|e| XploreError::Auth(format!("Failed to create TOTP: {}", e)))?;
+
+    totp.generate_current().map_err(|e| XploreError::Auth(format!("Failed to generate TOTP code: {}", e)))
+}
+
 impl UserAuth {
     pub async fn install_headers(&self, headers: &mut HeaderMap) -> Result<()> {
         let cookie_jar = self.cookie_jar.lock().await;
+        let has_session = cookie_jar.get("auth_token").is_some();
         let cookies: Vec<_> = cookie_jar.iter().collect();
         if !cookies.is_empty() {
             let cookie_header =
@@ -545,27 +699,62 @@ impl UserAuth {
         }
         headers.insert(
             "Authorization",
-            HeaderValue::from_str(&format!("Bearer {}", self.bearer_token))
+            HeaderValue::from_str(&format!("Bearer {}", self.bearer_token.read().unwrap()))
                 .map_err(|e| XploreError::Auth(e.to_string()))?,
         );
-        if let Some(token) = &self.guest_token {
+        let guest_token = self.guest.read().unwrap().token.clone();
+        if let Some(token) = &guest_token {
             headers
                 .insert("x-guest-token", HeaderValue::from_str(token).map_err(|e| XploreError::Auth(e.to_string()))?);
         }
+        let header = |value: &str| HeaderValue::from_str(value).map_err(|e| XploreError::Config(e.to_string()));
+        headers.insert(USER_AGENT, header(&self.client.user_agent)?);
+        headers.insert(ACCEPT_LANGUAGE, header(&self.client.accept_language)?);
+        headers.insert("x-twitter-client-language", header(&self.client.client_language)?);
         headers.insert("x-twitter-active-user", HeaderValue::from_static("yes"));
-        headers.insert("x-twitter-client-language", HeaderValue::from_static("en"));
-        headers.insert("x-twitter-auth-type", HeaderValue::from_static("OAuth2Client"));
+        if has_session {
+            headers.insert("x-twitter-auth-type", HeaderValue::from_static("OAuth2Session"));
+        }
 
         Ok(())
     }
 
+    /// A fresh `x-client-transaction-id` for a request, or `None` if the profile turns it off
+    /// or its key cannot be fetched.
+    pub(crate) async fn transaction_id(&self, method: &Method, url: &str) -> Option<String> {
+        if !self.transaction_ids {
+            return None;
+        }
+        let path = url::Url::parse(url).ok()?.path().to_string();
+
+        let mut state = self.transaction_key.lock().await;
+        let fetch = match &*state {
+            TransactionKeyState::Unfetched => true,
+            TransactionKeyState::Ready(_, fetched_at) => fetched_at.elapsed() >= TRANSACTION_KEY_TTL,
+            TransactionKeyState::Failed(at) => at.elapsed() >= TRANSACTION_KEY_RETRY,
+        };
+        if fetch {
+            *state = match transaction::fetch_transaction_key(self).await {
+                Ok(key) => TransactionKeyState::Ready(key, Instant::now()),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Sending requests without x-client-transaction-id");
+                    TransactionKeyState::Failed(Instant::now())
+                }
+            };
+        }
+
+        match &*state {
+            TransactionKeyState::Ready(key, _) => Some(key.generate(method.as_str(), &path)),
+            _ => None,
+        }
+    }
+
     pub async fn get_cookies(&self) -> Result<Vec<cookie::Cookie<'_>>> {
         let jar = self.cookie_jar.lock().await;
         Ok(jar.iter().map(|c| c.to_owned()).collect())
     }
 
-    pub fn delete_token(&mut self) {
-        self.guest_token = None;
-        self.created_at = None;
+    pub fn delete_token(&self) {
+        *self.guest.write().unwrap() = GuestToken::default();
     }
 }
@@ -0,0 +1,343 @@
+//! Record-and-replay of HTTP traffic.
+//!
+//! A cassette is a JSON file holding every request made through the client
+//! together with the response it got. [`RecordingTransport`] writes one while
+//! forwarding requests to a real transport; [`ReplayTransport`] serves the
+//! recorded responses back without touching the network. Cookies, tokens and
+//! passwords are scrubbed before anything is written to disk, and so are the
+//! cursors and user identifiers of recorded requests, query strings included.
+
+use {
+    crate::{
+        transport::{HttpRequest, HttpResponse, MultipartValue, RequestBody, Transport},
+        Result, XploreError,
+    },
+    async_trait::async_trait,
+    reqwest::{
+        header::{HeaderMap, HeaderName, HeaderValue},
+        StatusCode,
+    },
+    serde::{Deserialize, Serialize},
+    serde_json::{Map, Value},
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+    },
+};
+
+pub const CASSETTE_VERSION: u32 = 1;
+
+const SCRUBBED: &str = "[SCRUBBED]";
+
+/// Headers that are never written to a cassette.
+const SENSITIVE_HEADERS: &[&str] =
+    &["authorization", "cookie", "set-cookie", "x-csrf-token", "x-guest-token", "x-client-transaction-id"];
+
+/// JSON and form fields whose values are replaced with `[SCRUBBED]`.
+const SENSITIVE_FIELDS: &[&str] = &[
+    "access_token",
+    "auth_token",
+    "ct0",
+    "flow_token",
+    "guest_token",
+    "oauth_token",
+    "oauth_token_secret",
+    "password",
+];
+
+/// Request fields that say who or what was looked at; scrubbed from requests only, since
+/// responses need their IDs and cursors to be parsed.
+const IDENTIFYING_FIELDS: &[&str] = &["cursor", "screen_name", "userId", "user_id"];
+
+/// How a client should use a cassette file.
+#[derive(Debug, Clone)]
+pub enum CassetteMode {
+    /// Forward requests to the network and record them to the file.
+    Record(PathBuf),
+    /// Serve responses from the file without touching the network.
+    Replay(PathBuf),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub version: u32,
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let cassette: Cassette = serde_json::from_str(&contents)?;
+
+        if cassette.version != CASSETTE_VERSION {
+            return Err(XploreError::InvalidResponse(format!(
+                "Unsupported cassette version {} (expected {})",
+                cassette.version, CASSETTE_VERSION
+            )));
+        }
+
+        Ok(cassette)
+    }
+
+    /// Writes the cassette to `path`; this blocks, so keep it off hot async paths.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+impl RecordedRequest {
+    fn from_request(request: &HttpRequest) -> Self {
+        let body = match &request.body {
+            RequestBody::Empty => None,
+            RequestBody::Json(json) => Some(scrub_json(json.clone(), is_identifying)),
+            RequestBody::Form(pairs) => Some(Value::Object(
+                pairs.iter().map(|(name, value)| (name.clone(), scrub_field(name, value, is_identifying))).collect(),
+            )),
+            RequestBody::Multipart(fields) => Some(Value::Object(
+                fields
+                    .iter()
+                    .map(|field| {
+                        let value = match &field.value {
+                            MultipartValue::Text(text) => scrub_field(&field.name, text, is_identifying),
+                            MultipartValue::Bytes(bytes) => Value::String(format!("<{} bytes>", bytes.len())),
+                        };
+                        (field.name.clone(), value)
+                    })
+                    .collect(),
+            )),
+        };
+
+        Self {
+            method: request.method.to_string(),
+            url: scrub_url(&request.url),
+            headers: scrub_headers(&request.headers),
+            body,
+        }
+    }
+}
+
+impl RecordedResponse {
+    fn from_response(response: &HttpResponse) -> Self {
+        let body = match serde_json::from_str::<Value>(&response.body) {
+            Ok(json) => scrub_json(json, is_sensitive).to_string(),
+            Err(_) => response.body.clone(),
+        };
+
+        Self { status: response.status.as_u16(), headers: scrub_headers(&response.headers), body }
+    }
+
+    fn to_response(&self) -> Result<HttpResponse> {
+        let status = StatusCode::from_u16(self.status)
+            .map_err(|e| XploreError::InvalidResponse(format!("Invalid status in cassette: {}", e)))?;
+
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            if let (Ok(name), Ok(value)) = (HeaderName::try_from(name.as_str()), HeaderValue::from_str(value)) {
+                headers.append(name, value);
+            }
+        }
+
+        Ok(HttpResponse { status, headers, body: self.body.clone() })
+    }
+}
+
+fn scrub_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| !SENSITIVE_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| value.to_str().ok().map(|value| (name.to_string(), value.to_string())))
+        .collect()
+}
+
+fn is_sensitive(name: &str) -> bool {
+    SENSITIVE_FIELDS.contains(&name)
+}
+
+fn is_identifying(name: &str) -> bool {
+    is_sensitive(name) || IDENTIFYING_FIELDS.contains(&name)
+}
+
+fn scrub_field(name: &str, value: &str, scrub: fn(&str) -> bool) -> Value {
+    if scrub(name) {
+        Value::String(SCRUBBED.to_string())
+    } else {
+        Value::String(value.to_string())
+    }
+}
+
+fn scrub_json(value: Value, scrub: fn(&str) -> bool) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    if scrub(&key) {
+                        (key, Value::String(SCRUBBED.to_string()))
+                    } else {
+                        (key, scrub_json(value, scrub))
+                    }
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(|item| scrub_json(item, scrub)).collect()),
+        other => other,
+    }
+}
+
+/// Scrubs the query of `url`: identifying parameters are replaced, and parameters holding JSON,
+/// such as GraphQL `variables`, are scrubbed field by field.
+fn scrub_url(url: &str) -> String {
+    let mut parsed = match url::Url::parse(url) {
+        Ok(parsed) if parsed.query().is_some() => parsed,
+        Ok(_) => return url.to_string(),
+        Err(_) => return without_query(url).to_string(),
+    };
+
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if is_identifying(&name) {
+                SCRUBBED.to_string()
+            } else {
+                match serde_json::from_str::<Value>(&value) {
+                    Ok(json @ Value::Object(_)) => scrub_json(json, is_identifying).to_string(),
+                    _ => value.into_owned(),
+                }
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+
+    parsed.to_string()
+}
+
+/// Forwards requests to `inner` and records every interaction to a cassette file.
+///
+/// Interactions are kept in memory and written once, when the transport is dropped
+/// (i.e. with the last clone of the client) or when [`RecordingTransport::save`] is called.
+pub struct RecordingTransport {
+    inner: Arc<dyn Transport>,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl RecordingTransport {
+    /// Starts a new cassette at `path`, replacing any existing file.
+    pub fn new(inner: Arc<dyn Transport>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            cassette: Mutex::new(Cassette { version: CASSETTE_VERSION, interactions: Vec::new() }),
+        }
+    }
+
+    /// Writes what was recorded so far to the cassette file.
+    pub fn save(&self) -> Result<()> {
+        self.cassette.lock().unwrap().save(&self.path)
+    }
+}
+
+impl Drop for RecordingTransport {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            tracing::warn!(error = %e, "Failed to write cassette {}", self.path.display());
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for RecordingTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let recorded_request = RecordedRequest::from_request(&request);
+        let response = self.inner.send(request).await?;
+
+        self.cassette
+            .lock()
+            .unwrap()
+            .interactions
+            .push(Interaction { request: recorded_request, response: RecordedResponse::from_response(&response) });
+
+        Ok(response)
+    }
+}
+
+/// Serves responses from a cassette without touching the network.
+///
+/// Each request is answered with the first unused interaction whose method and
+/// URL, scrubbed as it would have been recorded, match exactly. If there is none,
+/// the query string is ignored and the first unused interaction with the same
+/// method and path is used instead.
+pub struct ReplayTransport {
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl ReplayTransport {
+    pub fn new(cassette: Cassette) -> Self {
+        Self { interactions: Mutex::new(cassette.interactions.into_iter().map(|i| (i, false)).collect()) }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+}
+
+fn without_query(url: &str) -> &str {
+    url.split('?').next().unwrap_or(url)
+}
+
+#[async_trait]
+impl Transport for ReplayTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let method = request.method.to_string();
+        let url = scrub_url(&request.url);
+        let mut interactions = self.interactions.lock().unwrap();
+
+        let exact =
+            interactions.iter().position(|(i, used)| !used && i.request.method == method && i.request.url == url);
+        let position = exact.or_else(|| {
+            interactions.iter().position(|(i, used)| {
+                !used && i.request.method == method && without_query(&i.request.url) == without_query(&request.url)
+            })
+        });
+
+        match position {
+            Some(index) => {
+                interactions[index].1 = true;
+                interactions[index].0.response.to_response()
+            }
+            None => Err(XploreError::Api(format!("No recorded response for {} {}", request.method, request.url))),
+        }
+    }
+}
+
+impl CassetteMode {
+    pub(crate) fn wrap(&self, inner: Arc<dyn Transport>) -> Result<Arc<dyn Transport>> {
+        Ok(match self {
+            CassetteMode::Record(path) => Arc::new(RecordingTransport::new(inner, path.clone())),
+            CassetteMode::Replay(path) => Arc::new(ReplayTransport::from_file(path)?),
+        })
+    }
+}
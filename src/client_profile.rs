@@ -0,0 +1,100 @@
+//! How the client presents itself to X.
+//!
+//! X compares requests with what its own web client sends. A [`ClientProfile`] keeps the user
+//! agent, languages and host consistent across every request, and turns on the
+//! `x-client-transaction-id` header that several GraphQL endpoints now require.
+//!
+//! ```no_run
+//! # use xplore::{client_profile::{ClientProfile, HostDomain}, XploreOptions};
+//! let options = XploreOptions {
+//!     client_profile: ClientProfile {
+//!         accept_language: "de-DE,de;q=0.9,en;q=0.8".to_string(),
+//!         client_language: "de".to_string(),
+//!         host: HostDomain::Twitter,
+//!         ..Default::default()
+//!     },
+//!     ..Default::default()
+//! };
+//! ```
+
+use {
+    crate::transport::BaseUrls,
+    serde::{Deserialize, Serialize},
+};
+
+/// User agent of a current desktop Chrome.
+pub const DEFAULT_USER_AGENT: &str =
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0.0.0 Safari/537.36";
+
+/// The domain requests are sent to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HostDomain {
+    /// `x.com`, which the web client uses.
+    #[default]
+    X,
+    /// `twitter.com`, which still serves the same API.
+    Twitter,
+}
+
+impl HostDomain {
+    pub fn domain(&self) -> &'static str {
+        match self {
+            HostDomain::X => "x.com",
+            HostDomain::Twitter => "twitter.com",
+        }
+    }
+}
+
+/// The user agent, languages and host used for every request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClientProfile {
+    /// `User-Agent` header.
+    ///
+    /// Default: [`DEFAULT_USER_AGENT`]
+    pub user_agent: String,
+
+    /// `Accept-Language` header.
+    ///
+    /// Default: `en-US,en;q=0.9`
+    pub accept_language: String,
+
+    /// `x-twitter-client-language` header, the language of the interface.
+    ///
+    /// Default: `en`
+    pub client_language: String,
+
+    /// Domain of the API, upload, GraphQL and web hosts, unless `XploreOptions::base_urls` is set.
+    ///
+    /// Default: `HostDomain::X`
+    pub host: HostDomain,
+
+    /// Whether to send `x-client-transaction-id`. Generating it needs the x.com home page and
+    /// one script; the key taken from them is refetched after an hour, and a failed fetch is
+    /// retried after ten minutes.
+    ///
+    /// `None` sends it only through the built-in HTTP client, i.e. when neither
+    /// `XploreOptions::transport` nor `XploreOptions::cassette` is set, so that mocks and
+    /// cassettes see only the requests made on the caller's behalf.
+    ///
+    /// Default: None
+    pub transaction_id: Option<bool>,
+}
+
+impl Default for ClientProfile {
+    fn default() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            accept_language: "en-US,en;q=0.9".to_string(),
+            client_language: "en".to_string(),
+            host: HostDomain::X,
+            transaction_id: None,
+        }
+    }
+}
+
+impl ClientProfile {
+    /// The base URLs of every host on the profile's domain.
+    pub fn base_urls(&self) -> BaseUrls {
+        BaseUrls::for_domain(self.host.domain())
+    }
+}
@@ -0,0 +1,165 @@
+//! Reading cookies exported from a browser.
+//!
+//! Two formats are understood: the Netscape `cookies.txt` format written by curl, wget and the
+//! "Get cookies.txt" extensions, and the JSON arrays exported by EditThisCookie and
+//! Cookie-Editor. Either way the cookies keep the domain, path, expiry and flags they were
+//! exported with. Cookies for sites other than x.com and twitter.com are left out, and so are
+//! expired ones, so a stale export fails with a missing `auth_token` rather than a 401 later.
+
+use {
+    crate::{session::StoredCookie, Result, XploreError},
+    chrono::{DateTime, TimeZone, Utc},
+    serde::Deserialize,
+    serde_json::Value,
+    std::{fs, path::Path},
+};
+
+/// Domains whose cookies are imported, including their subdomains.
+const X_DOMAINS: &[&str] = &["x.com", "twitter.com"];
+
+/// Prefix curl gives to the lines of `HttpOnly` cookies, which would otherwise be comments.
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// Reads a cookie export, detecting its format from the contents.
+pub fn load_cookie_file(path: impl AsRef<Path>) -> Result<Vec<StoredCookie>> {
+    parse_cookie_export(&fs::read_to_string(path)?)
+}
+
+/// Parses a cookie export in either format: JSON if it starts with `[`, `cookies.txt` otherwise.
+pub fn parse_cookie_export(contents: &str) -> Result<Vec<StoredCookie>> {
+    if contents.trim_start().starts_with('[') {
+        parse_browser_json(contents)
+    } else {
+        parse_netscape(contents)
+    }
+}
+
+/// Parses the Netscape `cookies.txt` format.
+///
+/// Every line holds seven tab-separated fields: domain, subdomain flag, path, secure flag,
+/// expiry as a Unix timestamp (`0` for session cookies), name and value.
+pub fn parse_netscape(contents: &str) -> Result<Vec<StoredCookie>> {
+    let mut cookies = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [domain, _, path, secure, expires, name, value] = fields[..] else {
+            return Err(XploreError::Cookie(format!(
+                "Line {} of cookies.txt has {} fields, expected 7",
+                index + 1,
+                fields.len()
+            )));
+        };
+
+        let expires = expires.parse::<i64>().map_err(|_| {
+            XploreError::Cookie(format!("Invalid expiry {:?} on line {} of cookies.txt", expires, index + 1))
+        })?;
+
+        cookies.push(StoredCookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain: Some(normalize_domain(domain)),
+            path: Some(path.to_string()),
+            expires: if expires == 0 { None } else { timestamp(expires as f64) },
+            secure: secure.eq_ignore_ascii_case("TRUE"),
+            http_only,
+            same_site: None,
+        });
+    }
+
+    Ok(keep_usable(cookies))
+}
+
+/// A cookie as exported by EditThisCookie or Cookie-Editor.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BrowserCookie {
+    name: String,
+    value: String,
+    domain: Option<String>,
+    path: Option<String>,
+    /// Seconds since the epoch, possibly fractional. Some exporters call it `expiry` or `expires`.
+    #[serde(alias = "expiry", alias = "expires")]
+    expiration_date: Option<Value>,
+    #[serde(default)]
+    session: bool,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    same_site: Option<String>,
+}
+
+/// Parses a JSON array of cookies as exported by EditThisCookie or Cookie-Editor.
+pub fn parse_browser_json(contents: &str) -> Result<Vec<StoredCookie>> {
+    let exported: Vec<BrowserCookie> =
+        serde_json::from_str(contents).map_err(|e| XploreError::Cookie(format!("Invalid cookie export: {}", e)))?;
+
+    let cookies = exported
+        .into_iter()
+        .map(|cookie| {
+            let expires = match (&cookie.expiration_date, cookie.session) {
+                (Some(Value::Number(seconds)), false) => seconds.as_f64().and_then(timestamp),
+                _ => None,
+            };
+
+            StoredCookie {
+                name: cookie.name,
+                value: cookie.value,
+                domain: cookie.domain.as_deref().map(normalize_domain),
+                path: cookie.path,
+                expires,
+                secure: cookie.secure,
+                http_only: cookie.http_only,
+                same_site: cookie.same_site.as_deref().and_then(same_site),
+            }
+        })
+        .collect();
+
+    Ok(keep_usable(cookies))
+}
+
+/// Drops cookies that are expired or belong to other sites.
+fn keep_usable(cookies: Vec<StoredCookie>) -> Vec<StoredCookie> {
+    cookies
+        .into_iter()
+        .filter(|cookie| {
+            let domain = cookie.domain.as_deref().unwrap_or_default();
+            X_DOMAINS.iter().any(|x| domain == *x || domain.ends_with(&format!(".{}", x)))
+        })
+        .filter(|cookie| {
+            if cookie.is_expired() {
+                tracing::debug!(name = %cookie.name, expires = ?cookie.expires, "Skipping expired cookie");
+            }
+            !cookie.is_expired()
+        })
+        .collect()
+}
+
+/// Strips the leading dot that marks a domain cookie; the cookie crate adds it back as needed.
+fn normalize_domain(domain: &str) -> String {
+    domain.trim().trim_start_matches('.').to_ascii_lowercase()
+}
+
+fn timestamp(seconds: f64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(seconds.trunc() as i64, 0).single()
+}
+
+/// Maps the `sameSite` values of the browser extensions to the attribute value.
+fn same_site(value: &str) -> Option<String> {
+    match value.to_ascii_lowercase().as_str() {
+        "strict" => Some("Strict".to_string()),
+        "lax" => Some("Lax".to_string()),
+        "none" | "no_restriction" => Some("None".to_string()),
+        _ => None,
+    }
+}
@@ -0,0 +1,137 @@
+//! Discovery of current GraphQL query IDs from X's web client.
+//!
+//! The x.com home page loads a `main.<hash>.js` bundle that embeds one object per
+//! GraphQL operation:
+//!
+//! ```text
+//! {queryId:"G3KGOASz96M-Qu0nwmGXNg",operationName:"UserByScreenName",operationType:"query",
+//!  metadata:{featureSwitches:["hidden_profile_likes_enabled",...],fieldToggles:["withAuxiliaryUserLabels"]}}
+//! ```
+//!
+//! [`Xplore::refresh_operations`](crate::Xplore::refresh_operations) fetches the page and the bundle; the parsing functions
+//! are pure so they can be run against saved copies.
+
+use {
+    crate::{
+        api,
+        auth::UserAuth,
+        operations::OperationKind,
+        transport::{HttpRequest, HttpResponse, RequestBody},
+        ApiErrorDetails, Result, XploreError,
+    },
+    lazy_static::lazy_static,
+    regex::Regex,
+    reqwest::{
+        header::{HeaderMap, HeaderValue, ACCEPT, ACCEPT_LANGUAGE, USER_AGENT},
+        Method,
+    },
+    std::collections::HashMap,
+};
+
+lazy_static! {
+    static ref MAIN_BUNDLE: Regex =
+        Regex::new(r#"https://abs\.twimg\.com/responsive-web/client-web(?:-legacy)?/main\.[0-9a-zA-Z]+\.js"#).unwrap();
+    static ref OPERATION: Regex = Regex::new(concat!(
+        r#"queryId:"(?P<id>[\w-]+)",operationName:"(?P<name>\w+)",operationType:"(?P<kind>\w+)","#,
+        r#"metadata:\{featureSwitches:\[(?P<features>[^\]]*)\](?:,fieldToggles:\[(?P<toggles>[^\]]*)\])?"#
+    ))
+    .unwrap();
+    static ref QUOTED: Regex = Regex::new(r#""([^"]+)""#).unwrap();
+    static ref FEATURE_DEFAULT: Regex = Regex::new(r#""([a-z0-9_]+)":\{"value":(true|false)\}"#).unwrap();
+}
+
+/// One GraphQL operation found in the web client bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredOperation {
+    pub query_id: String,
+    pub operation_name: String,
+    pub kind: OperationKind,
+    pub feature_switches: Vec<String>,
+    pub field_toggles: Vec<String>,
+}
+
+/// The result of a discovery run.
+#[derive(Debug, Clone)]
+pub struct Discovery {
+    /// URL of the bundle the operations were read from.
+    pub bundle_url: String,
+    pub operations: Vec<DiscoveredOperation>,
+    /// Default values of feature switches, as published in the home page.
+    pub feature_defaults: HashMap<String, bool>,
+}
+
+/// Finds the URL of the `main.*.js` bundle in the x.com home page.
+pub fn find_main_bundle_url(html: &str) -> Option<String> {
+    MAIN_BUNDLE.find(html).map(|m| m.as_str().to_string())
+}
+
+/// Extracts every `queryId`/`operationName`/`featureSwitches` triple from a bundle.
+///
+/// Operations of a type other than `query` or `mutation` (e.g. subscriptions) are skipped.
+pub fn parse_bundle(bundle: &str) -> Vec<DiscoveredOperation> {
+    let quoted = |list: Option<regex::Match>| {
+        list.map(|list| QUOTED.captures_iter(list.as_str()).map(|c| c[1].to_string()).collect()).unwrap_or_default()
+    };
+
+    OPERATION
+        .captures_iter(bundle)
+        .filter_map(|captures| {
+            let kind = match &captures["kind"] {
+                "query" => OperationKind::Query,
+                "mutation" => OperationKind::Mutation,
+                _ => return None,
+            };
+
+            Some(DiscoveredOperation {
+                query_id: captures["id"].to_string(),
+                operation_name: captures["name"].to_string(),
+                kind,
+                feature_switches: quoted(captures.name("features")),
+                field_toggles: quoted(captures.name("toggles")),
+            })
+        })
+        .collect()
+}
+
+/// Extracts the default feature switch values embedded in the x.com home page.
+pub fn parse_feature_defaults(html: &str) -> HashMap<String, bool> {
+    FEATURE_DEFAULT.captures_iter(html).map(|c| (c[1].to_string(), &c[2] == "true")).collect()
+}
+
+/// Fetches the x.com home page and its main bundle, and parses both.
+pub(crate) async fn discover_operations(auth: &UserAuth) -> Result<Discovery> {
+    let html = fetch_text(auth, &auth.urls.web_url("")).await?;
+    let bundle_url = find_main_bundle_url(&html)
+        .ok_or_else(|| XploreError::InvalidResponse("No main.*.js bundle found in the x.com home page".into()))?;
+    let bundle = fetch_text(auth, &bundle_url).await?;
+
+    let operations = parse_bundle(&bundle);
+    if operations.is_empty() {
+        return Err(XploreError::InvalidResponse(format!("No GraphQL operations found in {}", bundle_url)));
+    }
+
+    Ok(Discovery { bundle_url, operations, feature_defaults: parse_feature_defaults(&html) })
+}
+
+/// Fetches a page of the web client the way a browser would, bypassing retries and the API headers.
+pub(crate) async fn fetch_text(auth: &UserAuth, url: &str) -> Result<String> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        USER_AGENT,
+        HeaderValue::from_str(&auth.client.user_agent).map_err(|e| XploreError::Config(e.to_string()))?,
+    );
+    headers.insert(
+        ACCEPT_LANGUAGE,
+        HeaderValue::from_str(&auth.client.accept_language).map_err(|e| XploreError::Config(e.to_string()))?,
+    );
+    headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
+
+    let request = HttpRequest { method: Method::GET, url: url.to_string(), headers, body: RequestBody::Empty };
+    let response: HttpResponse = auth.transport.send(request).await?;
+
+    if !response.status.is_success() {
+        return Err(api::classify_error(ApiErrorDetails::from_response(&response)));
+    }
+
+    Ok(response.body)
+}
@@ -1,6 +1,10 @@
 #![allow(dead_code)]
 
-use {std::collections::HashMap, urlencoding};
+use {
+    crate::{operations::OperationRegistry, transport::BaseUrls, Result},
+    reqwest::Method,
+    serde_json::{json, Map, Value},
+};
 
 // Constants for default options matching TypeScript
 pub const DEFAULT_EXPANSIONS: &[&str] = &[
@@ -37,195 +41,160 @@ pub const DEFAULT_TWEET_FIELDS: &[&str] = &[
     "note_tweet",
 ];
 
+/// A GraphQL operation together with its arguments, usually built with
+/// `OperationRegistry::endpoint`.
+///
+/// Queries (`GET`) carry `variables`, `features` and `fieldToggles` in the URL;
+/// mutations (`POST`) carry them in a JSON body along with the `queryId`.
 #[derive(Debug, Clone)]
 pub struct ApiEndpoint {
-    pub url: String,
-    pub variables: Option<HashMap<String, serde_json::Value>>,
-    pub features: Option<HashMap<String, bool>>,
-    pub field_toggles: Option<HashMap<String, bool>>,
+    /// Path of the operation relative to the GraphQL base URL, e.g. `xOhkmRac04YFZmOzU9PJHg/TweetDetail`.
+    pub path: String,
+    pub method: Method,
+    pub variables: Option<Value>,
+    pub features: Option<Value>,
+    pub field_toggles: Option<Value>,
 }
 
 impl ApiEndpoint {
-    pub fn to_request_url(&self) -> String {
-        let mut params = Vec::new();
+    pub fn with_variables(mut self, variables: Value) -> Self {
+        self.variables = Some(variables);
+        self
+    }
 
-        if let Some(variables) = &self.variables {
-            params.push(format!("variables={}", urlencoding::encode(&serde_json::to_string(&variables).unwrap())));
-        }
+    /// The `queryId` part of the path.
+    pub fn query_id(&self) -> &str {
+        self.path.split('/').next().unwrap_or_default()
+    }
 
-        if let Some(features) = &self.features {
-            params.push(format!("features={}", urlencoding::encode(&serde_json::to_string(&features).unwrap())));
-        }
+    /// The operation name part of the path, e.g. `TweetDetail`.
+    pub fn operation_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// The URL to send the operation to; arguments are only included for `GET`.
+    pub fn to_request_url(&self, urls: &BaseUrls) -> String {
+        let url = urls.graphql_url(&self.path);
 
-        if let Some(toggles) = &self.field_toggles {
-            params.push(format!("fieldToggles={}", urlencoding::encode(&serde_json::to_string(&toggles).unwrap())));
+        if self.method != Method::GET {
+            return url;
         }
 
+        let params = self
+            .arguments()
+            .map(|(name, value)| format!("{}={}", name, urlencoding::encode(&value.to_string())))
+            .collect::<Vec<_>>();
+
         if params.is_empty() {
-            self.url.clone()
+            url
         } else {
-            format!("{}?{}", self.url, params.join("&"))
+            format!("{}?{}", url, params.join("&"))
         }
     }
+
+    /// The JSON body of the operation, or `None` for `GET`.
+    pub fn to_request_body(&self) -> Option<Value> {
+        if self.method == Method::GET {
+            return None;
+        }
+
+        let mut body = self.arguments().map(|(name, value)| (name.to_string(), value.clone())).collect::<Map<_, _>>();
+        body.insert("queryId".to_string(), Value::String(self.query_id().to_string()));
+
+        Some(Value::Object(body))
+    }
+
+    fn arguments(&self) -> impl Iterator<Item = (&'static str, &Value)> {
+        [("variables", &self.variables), ("features", &self.features), ("fieldToggles", &self.field_toggles)]
+            .into_iter()
+            .filter_map(|(name, value)| value.as_ref().map(|value| (name, value)))
+    }
 }
 
 pub struct Endpoints;
 
 impl Endpoints {
-    pub fn tweet_detail(tweet_id: &str) -> ApiEndpoint {
-        ApiEndpoint {
-            url: "https://twitter.com/i/api/graphql/xOhkmRac04YFZmOzU9PJHg/TweetDetail".to_string(),
-            variables: Some(HashMap::from([
-                ("focalTweetId".to_string(), tweet_id.into()),
-                ("with_rux_injections".to_string(), false.into()),
-                ("includePromotedContent".to_string(), true.into()),
-                ("withCommunity".to_string(), true.into()),
-                ("withQuickPromoteEligibilityTweetFields".to_string(), true.into()),
-                ("withBirdwatchNotes".to_string(), true.into()),
-                ("withVoice".to_string(), true.into()),
-                ("withV2Timeline".to_string(), true.into()),
-            ])),
-            features: Some(HashMap::from([
-                ("responsive_web_graphql_exclude_directive_enabled".to_string(), true),
-                ("verified_phone_label_enabled".to_string(), false),
-                ("creator_subscriptions_tweet_preview_api_enabled".to_string(), true),
-                ("responsive_web_graphql_timeline_navigation_enabled".to_string(), true),
-                ("responsive_web_graphql_skip_user_profile_image_extensions_enabled".to_string(), false),
-                ("tweetypie_unmention_optimization_enabled".to_string(), true),
-                ("responsive_web_edit_tweet_api_enabled".to_string(), true),
-                ("graphql_is_translatable_rweb_tweet_is_translatable_enabled".to_string(), true),
-                ("view_counts_everywhere_api_enabled".to_string(), true),
-                ("longform_notetweets_consumption_enabled".to_string(), true),
-                ("tweet_awards_web_tipping_enabled".to_string(), false),
-                ("freedom_of_speech_not_reach_fetch_enabled".to_string(), true),
-                ("standardized_nudges_misinfo".to_string(), true),
-                ("responsive_web_twitter_article_tweet_consumption_enabled".to_string(), false),
-                ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled".to_string(), true),
-                ("longform_notetweets_rich_text_read_enabled".to_string(), true),
-                ("longform_notetweets_inline_media_enabled".to_string(), true),
-                ("responsive_web_media_download_video_enabled".to_string(), false),
-                ("responsive_web_enhance_cards_enabled".to_string(), false),
-            ])),
-            field_toggles: Some(HashMap::from([("withArticleRichContentState".to_string(), false)])),
-        }
+    pub fn tweet_detail(operations: &OperationRegistry, tweet_id: &str) -> Result<ApiEndpoint> {
+        Ok(operations.endpoint("TweetDetail")?.with_variables(json!({
+            "focalTweetId": tweet_id,
+            "with_rux_injections": false,
+            "includePromotedContent": true,
+            "withCommunity": true,
+            "withQuickPromoteEligibilityTweetFields": true,
+            "withBirdwatchNotes": true,
+            "withVoice": true,
+            "withV2Timeline": true,
+        })))
     }
 
-    pub fn tweet_by_rest_id(tweet_id: &str) -> ApiEndpoint {
-        ApiEndpoint {
-            url: "https://twitter.com/i/api/graphql/DJS3BdhUhcaEpZ7B7irJDg/TweetResultByRestId".to_string(),
-            variables: Some(HashMap::from([
-                ("tweetId".to_string(), tweet_id.into()),
-                ("withCommunity".to_string(), false.into()),
-                ("includePromotedContent".to_string(), false.into()),
-                ("withVoice".to_string(), false.into()),
-            ])),
-            features: Some(HashMap::from([
-                ("creator_subscriptions_tweet_preview_api_enabled".to_string(), true),
-                ("tweetypie_unmention_optimization_enabled".to_string(), true),
-                ("responsive_web_edit_tweet_api_enabled".to_string(), true),
-                ("graphql_is_translatable_rweb_tweet_is_translatable_enabled".to_string(), true),
-                ("view_counts_everywhere_api_enabled".to_string(), true),
-                ("longform_notetweets_consumption_enabled".to_string(), true),
-                ("responsive_web_twitter_article_tweet_consumption_enabled".to_string(), false),
-                ("tweet_awards_web_tipping_enabled".to_string(), false),
-                ("freedom_of_speech_not_reach_fetch_enabled".to_string(), true),
-                ("standardized_nudges_misinfo".to_string(), true),
-            ])),
-            field_toggles: None,
-        }
+    pub fn tweet_by_rest_id(operations: &OperationRegistry, tweet_id: &str) -> Result<ApiEndpoint> {
+        Ok(operations.endpoint("TweetResultByRestId")?.with_variables(json!({
+            "tweetId": tweet_id,
+            "withCommunity": false,
+            "includePromotedContent": false,
+            "withVoice": false,
+        })))
     }
 
-    pub fn user_tweets(user_id: &str, count: i32, cursor: Option<&str>) -> ApiEndpoint {
-        let mut variables = HashMap::from([
-            ("userId".to_string(), user_id.into()),
-            ("count".to_string(), count.into()),
-            ("includePromotedContent".to_string(), true.into()),
-            ("withQuickPromoteEligibilityTweetFields".to_string(), true.into()),
-            ("withVoice".to_string(), true.into()),
-            ("withV2Timeline".to_string(), true.into()),
-        ]);
+    pub fn user_tweets(
+        operations: &OperationRegistry,
+        user_id: &str,
+        count: i32,
+        cursor: Option<&str>,
+    ) -> Result<ApiEndpoint> {
+        let mut variables = json!({
+            "userId": user_id,
+            "count": count,
+            "includePromotedContent": true,
+            "withQuickPromoteEligibilityTweetFields": true,
+            "withVoice": true,
+            "withV2Timeline": true,
+        });
 
         if let Some(cursor_value) = cursor {
-            variables.insert("cursor".to_string(), cursor_value.into());
+            variables["cursor"] = json!(cursor_value);
         }
 
-        ApiEndpoint {
-            url: "https://twitter.com/i/api/graphql/V7H0Ap3_Hh2FyS75OCDO3Q/UserTweets".to_string(),
-            variables: Some(variables),
-            features: Some(HashMap::from([
-                ("rweb_tipjar_consumption_enabled".to_string(), true),
-                ("responsive_web_graphql_exclude_directive_enabled".to_string(), true),
-                ("verified_phone_label_enabled".to_string(), false),
-                ("creator_subscriptions_tweet_preview_api_enabled".to_string(), true),
-                ("responsive_web_graphql_timeline_navigation_enabled".to_string(), true),
-                ("responsive_web_graphql_skip_user_profile_image_extensions_enabled".to_string(), false),
-                ("communities_web_enable_tweet_community_results_fetch".to_string(), true),
-                ("c9s_tweet_anatomy_moderator_badge_enabled".to_string(), true),
-                ("articles_preview_enabled".to_string(), true),
-                ("tweetypie_unmention_optimization_enabled".to_string(), true),
-                ("responsive_web_edit_tweet_api_enabled".to_string(), true),
-                ("graphql_is_translatable_rweb_tweet_is_translatable_enabled".to_string(), true),
-                ("view_counts_everywhere_api_enabled".to_string(), true),
-                ("longform_notetweets_consumption_enabled".to_string(), true),
-                ("responsive_web_twitter_article_tweet_consumption_enabled".to_string(), true),
-                ("tweet_awards_web_tipping_enabled".to_string(), false),
-                ("creator_subscriptions_quote_tweet_preview_enabled".to_string(), false),
-                ("freedom_of_speech_not_reach_fetch_enabled".to_string(), true),
-                ("standardized_nudges_misinfo".to_string(), true),
-                ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled".to_string(), true),
-                ("rweb_video_timestamps_enabled".to_string(), true),
-                ("longform_notetweets_rich_text_read_enabled".to_string(), true),
-                ("longform_notetweets_inline_media_enabled".to_string(), true),
-                ("responsive_web_enhance_cards_enabled".to_string(), false),
-            ])),
-            field_toggles: Some(HashMap::from([("withArticlePlainText".to_string(), false)])),
-        }
+        Ok(operations.endpoint("UserTweets")?.with_variables(variables))
     }
 
-    pub fn user_tweets_and_replies(user_id: &str, count: i32, cursor: Option<&str>) -> ApiEndpoint {
-        let mut variables = HashMap::from([
-            ("userId".to_string(), user_id.into()),
-            ("count".to_string(), count.into()),
-            ("includePromotedContent".to_string(), true.into()),
-            ("withCommunity".to_string(), true.into()),
-            ("withVoice".to_string(), true.into()),
-            ("withV2Timeline".to_string(), true.into()),
-        ]);
+    pub fn user_tweets_and_replies(
+        operations: &OperationRegistry,
+        user_id: &str,
+        count: i32,
+        cursor: Option<&str>,
+    ) -> Result<ApiEndpoint> {
+        let mut variables = json!({
+            "userId": user_id,
+            "count": count,
+            "includePromotedContent": true,
+            "withCommunity": true,
+            "withVoice": true,
+            "withV2Timeline": true,
+        });
 
         if let Some(cursor_value) = cursor {
-            variables.insert("cursor".to_string(), cursor_value.into());
+            variables["cursor"] = json!(cursor_value);
         }
 
-        ApiEndpoint {
-            url: "https://twitter.com/i/api/graphql/E4wA5vo2sjVyvpliUffSCw/UserTweetsAndReplies".to_string(),
-            variables: Some(variables),
-            features: Some(HashMap::from([
-                ("rweb_tipjar_consumption_enabled".to_string(), true),
-                ("responsive_web_graphql_exclude_directive_enabled".to_string(), true),
-                ("verified_phone_label_enabled".to_string(), false),
-                ("creator_subscriptions_tweet_preview_api_enabled".to_string(), true),
-                ("responsive_web_graphql_timeline_navigation_enabled".to_string(), true),
-                ("responsive_web_graphql_skip_user_profile_image_extensions_enabled".to_string(), false),
-                ("communities_web_enable_tweet_community_results_fetch".to_string(), true),
-                ("c9s_tweet_anatomy_moderator_badge_enabled".to_string(), true),
-                ("articles_preview_enabled".to_string(), true),
-                ("tweetypie_unmention_optimization_enabled".to_string(), true),
-                ("responsive_web_edit_tweet_api_enabled".to_string(), true),
-                ("graphql_is_translatable_rweb_tweet_is_translatable_enabled".to_string(), true),
-                ("view_counts_everywhere_api_enabled".to_string(), true),
-                ("longform_notetweets_consumption_enabled".to_string(), true),
-                ("responsive_web_twitter_article_tweet_consumption_enabled".to_string(), true),
-                ("tweet_awards_web_tipping_enabled".to_string(), false),
-                ("creator_subscriptions_quote_tweet_preview_enabled".to_string(), false),
-                ("freedom_of_speech_not_reach_fetch_enabled".to_string(), true),
-                ("standardized_nudges_misinfo".to_string(), true),
-                ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled".to_string(), true),
-                ("rweb_video_timestamps_enabled".to_string(), true),
-                ("longform_notetweets_rich_text_read_enabled".to_string(), true),
-                ("longform_notetweets_inline_media_enabled".to_string(), true),
-                ("responsive_web_enhance_cards_enabled".to_string(), false),
-            ])),
-            field_toggles: Some(HashMap::from([("withArticlePlainText".to_string(), false)])),
+        Ok(operations.endpoint("UserTweetsAndReplies")?.with_variables(variables))
+    }
+
+    pub fn list_latest_tweets(
+        operations: &OperationRegistry,
+        list_id: &str,
+        count: i32,
+        cursor: Option<&str>,
+    ) -> Result<ApiEndpoint> {
+        let mut variables = json!({
+            "listId": list_id,
+            "count": count,
+        });
+
+        if let Some(cursor_value) = cursor {
+            variables["cursor"] = json!(cursor_value);
         }
+
+        Ok(operations.endpoint("ListLatestTweetsTimeline")?.with_variables(variables))
     }
 }
@@ -0,0 +1,52 @@
+//! Helpers for the files the client writes: sessions, encrypted stores and search cursors.
+
+use {
+    crate::Result,
+    std::{
+        fs::{self, OpenOptions},
+        io::Write,
+        path::{Path, PathBuf},
+        process,
+        sync::atomic::{AtomicU64, Ordering},
+    },
+};
+
+/// Suffix of the next temporary file, unique within the process.
+static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+/// Replaces `path` with `contents` by writing a temporary file next to it and renaming it over,
+/// so readers never see a half-written file.
+///
+/// Every call writes its own temporary file, so concurrent writers cannot interleave: the last
+/// rename wins. With `private`, the file is created readable by its owner only (on Unix).
+pub(crate) fn write_atomic(path: &Path, contents: &[u8], private: bool) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.{}.tmp", process::id(), NEXT_TMP.fetch_add(1, Ordering::Relaxed)));
+    let tmp = PathBuf::from(tmp);
+
+    let result = write_and_rename(&tmp, path, contents, private);
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+fn write_and_rename(tmp: &Path, path: &Path, contents: &[u8], private: bool) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+
+    let mut file = options.open(tmp)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(tmp, path)?;
+    Ok(())
+}
@@ -5,34 +5,58 @@
 mod api;
 mod api_utils;
 mod auth;
+pub mod cassette;
+pub mod client_profile;
+pub mod cookie_import;
+pub mod discovery;
 mod endpoints;
+mod fs_util;
+pub mod login;
+pub mod operations;
+pub mod pagination;
+pub mod pool;
 pub mod profile;
-mod rate_limit;
+pub mod rate_limit;
 pub mod relationship;
 pub mod search;
+pub mod session;
+pub mod session_store;
+pub mod text;
 mod timeline_v1;
 mod timeline_v2;
+pub mod transaction;
+pub mod transport;
 mod trend;
 pub mod tweets;
 
 use {
     crate::{
         auth::UserAuth,
-        profile::{get_profile, get_user_id, Profile},
-        rate_limit::RateLimitStrategy,
-        search::SearchMode,
+        cassette::CassetteMode,
+        client_profile::ClientProfile,
+        login::{LoginChallengeHandler, LoginFlow, LoginInput, LogoutReport},
+        operations::OperationRegistry,
+        profile::{get_current_account, get_profile, get_user_id, CurrentAccount, Profile},
+        rate_limit::{RateLimitInfo, RateLimitStrategy, WaitingRateLimitStrategy},
+        search::{SearchMode, SearchSession},
+        session::Session,
+        session_store::SessionStore,
         timeline_v1::{QueryProfilesResponse, QueryTweetsResponse},
         timeline_v2::QueryTweetsResponse as V2QueryTweetsResponse,
+        transport::{BaseUrls, Transport},
         trend::get_trend,
         tweets::{
-            create_long_tweet, fetch_list_tweets, fetch_tweets_and_replies, fetch_tweets_and_replies_by_user_id,
-            get_user_tweets, like_tweet, post_tweet, read_tweet, retweet, send_quote_tweet, Tweet,
-            TweetRetweetResponse,
+            bookmark_tweet, create_long_tweet, delete_tweet, fetch_list_tweets, fetch_tweets_and_replies,
+            fetch_tweets_and_replies_by_user_id, get_poll, get_user_tweets, like_tweet, pin_tweet, post_poll,
+            post_thread, post_tweet, read_tweet, retweet, send_quote_tweet, unbookmark_tweet, unlike_tweet,
+            unpin_tweet, unretweet, NewPoll, PollV2, PostedTweet, ThreadPart, Tweet, TweetActionOutcome,
         },
     },
-    chrono::Duration,
-    serde::Deserialize,
+    chrono::{DateTime, Duration, Utc},
+    futures::Stream,
+    serde::{Deserialize, Serialize},
     serde_json::Value,
+    std::{fmt, path::Path, sync::Arc},
     thiserror::Error,
 };
 
@@ -50,8 +74,68 @@ pub enum XploreError {
     #[serde(skip)]
     Network(#[from] reqwest::Error),
 
-    #[error("Rate limit exceeded")]
-    RateLimit,
+    #[error("Not found: {0}")]
+    NotFound(ApiErrorDetails),
+
+    /// The user that was looked up is suspended.
+    #[error("Account suspended: {0}")]
+    Suspended(ApiErrorDetails),
+
+    /// The logged-in account itself is suspended (X error code 64).
+    #[error("Your account is suspended: {0}")]
+    AccountSuspended(ApiErrorDetails),
+
+    #[error("Account is protected: {0}")]
+    Protected(ApiErrorDetails),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(ApiErrorDetails),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(ApiErrorDetails),
+
+    #[error("Rate limit exceeded: {details}")]
+    RateLimited { reset_at: Option<DateTime<Utc>>, details: ApiErrorDetails },
+
+    #[error("Account locked: {0}")]
+    AccountLocked(ApiErrorDetails),
+
+    /// X rejected the username or password given during login.
+    #[error("Invalid credentials: {0}")]
+    InvalidCredentials(ApiErrorDetails),
+
+    /// The operation is not available to guests; log in or set a cookie first.
+    #[error("Login required for {0}")]
+    LoginRequired(String),
+
+    /// X ended the login flow with `DenyLoginSubtask`.
+    #[error("Login denied: {0}")]
+    LoginDenied(String),
+
+    /// The login flow reached a subtask it had no answer for.
+    #[error("Login challenge not handled: {0}")]
+    LoginChallenge(String),
+
+    /// The text is over the weighted length X accepts; nothing was sent.
+    #[error("Tweet is {length} characters long, over the limit of {max}")]
+    TweetTooLong { length: usize, max: usize },
+
+    #[error("Duplicate tweet: {0}")]
+    DuplicateTweet(ApiErrorDetails),
+
+    /// The tweet is already in the requested state, e.g. already liked or retweeted.
+    #[error("Already done: {0}")]
+    AlreadyApplied(ApiErrorDetails),
+
+    /// A thread stopped part-way. `posted` holds the IDs of the tweets created before the
+    /// failure, oldest first; `deleted` those removed again because rollback was requested, in
+    /// the same order.
+    #[error("Thread failed after {} tweets: {source}", posted.len())]
+    ThreadFailed { posted: Vec<String>, deleted: Vec<String>, source: Box<XploreError> },
+
+    /// Any other error reported by X, either as a non-2xx status or in the `errors` array.
+    #[error("Request failed: {0}")]
+    Response(ApiErrorDetails),
 
     #[error("Invalid response format: {0}")]
     InvalidResponse(String),
@@ -59,6 +143,9 @@ pub enum XploreError {
     #[error("Missing environment variable: {0}")]
     EnvVar(String),
 
+    #[error("Configuration error: {0}")]
+    Config(String),
+
     #[error("Cookie error: {0}")]
     Cookie(String),
 
@@ -71,6 +158,58 @@ pub enum XploreError {
     Io(#[from] std::io::Error),
 }
 
+impl XploreError {
+    /// The details reported by X, for errors that came from a response.
+    pub fn details(&self) -> Option<&ApiErrorDetails> {
+        match self {
+            XploreError::NotFound(details)
+            | XploreError::Suspended(details)
+            | XploreError::AccountSuspended(details)
+            | XploreError::Protected(details)
+            | XploreError::Unauthorized(details)
+            | XploreError::Forbidden(details)
+            | XploreError::RateLimited { details, .. }
+            | XploreError::AccountLocked(details)
+            | XploreError::InvalidCredentials(details)
+            | XploreError::DuplicateTweet(details)
+            | XploreError::AlreadyApplied(details)
+            | XploreError::Response(details) => Some(details),
+            XploreError::ThreadFailed { source, .. } => source.details(),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status of the response the error came from, if any.
+    pub fn status(&self) -> Option<u16> {
+        self.details().and_then(|details| details.status)
+    }
+}
+
+/// What X reported about a failed request.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApiErrorDetails {
+    /// HTTP status of the response.
+    pub status: Option<u16>,
+    /// X error code from the `errors` array, e.g. `144` for a missing tweet.
+    pub code: Option<i64>,
+    /// Message from the `errors` array, or the status text when there is none.
+    pub message: String,
+    /// The `x-rate-limit-*` headers of the response, if present.
+    pub rate_limit: Option<RateLimitInfo>,
+}
+
+impl fmt::Display for ApiErrorDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        match (self.status, self.code) {
+            (Some(status), Some(code)) => write!(f, " (status {}, code {})", status, code),
+            (Some(status), None) => write!(f, " (status {})", status),
+            (None, Some(code)) => write!(f, " (code {})", code),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
 /// Configuration options for the Xplore scraper.
 pub struct XploreOptions {
     /// The rate limiting strategy to use when the scraper hits API limits.
@@ -90,22 +229,115 @@ pub struct XploreOptions {
     ///
     /// Default: true
     pub follow_redirects: bool,
+
+    /// Transport used to send requests. When `None`, a `reqwest` client built from the
+    /// options above is used.
+    ///
+    /// Default: None
+    pub transport: Option<Arc<dyn Transport>>,
+
+    /// User agent, languages and host domain the client presents to X.
+    ///
+    /// Default: `ClientProfile::default()`, a desktop Chrome on x.com
+    pub client_profile: ClientProfile,
+
+    /// Base URLs of the API, upload and GraphQL hosts, overriding the domain of `client_profile`.
+    ///
+    /// Default: None, i.e. `client_profile.base_urls()`
+    pub base_urls: Option<BaseUrls>,
+
+    /// Records traffic to, or replays it from, a cassette file.
+    ///
+    /// Default: None
+    pub cassette: Option<CassetteMode>,
+
+    /// Query IDs and feature switches of the GraphQL operations.
+    ///
+    /// Default: `OperationRegistry::default()`, the operations built into this release
+    pub operations: OperationRegistry,
+
+    /// Answers login challenges such as an emailed confirmation code.
+    ///
+    /// Default: None
+    pub login_challenge_handler: Option<Arc<dyn LoginChallengeHandler>>,
+
+    /// Where the session is loaded from on start and saved to whenever X sets cookies.
+    ///
+    /// A stored session that cannot be decrypted or parsed is ignored with a warning, and
+    /// replaced by the next save.
+    ///
+    /// Default: None
+    pub session_store: Option<Arc<dyn SessionStore>>,
 }
 
+impl Default for XploreOptions {
+    fn default() -> Self {
+        Self {
+            rate_limit_strategy: Box::new(WaitingRateLimitStrategy),
+            request_timeout: Duration::seconds(30),
+            max_retries: 3,
+            follow_redirects: true,
+            transport: None,
+            client_profile: ClientProfile::default(),
+            base_urls: None,
+            cassette: None,
+            operations: OperationRegistry::default(),
+            login_challenge_handler: None,
+            session_store: None,
+        }
+    }
+}
+
+/// A client for X's API.
+///
+/// Cloning is cheap and every clone shares the same session, so one logged-in
+/// client can serve concurrent requests from several tasks.
+#[derive(Clone)]
 pub struct Xplore {
     auth: UserAuth,
 }
 
 impl Xplore {
-    pub async fn new(_options: Option<XploreOptions>) -> Result<Self> {
-        let auth = UserAuth::new().await?;
+    /// Creates a new client. When `options` is `None`, `XploreOptions::default()` is used.
+    pub async fn new(options: Option<XploreOptions>) -> Result<Self> {
+        let auth = UserAuth::new(options.unwrap_or_default()).await?;
         Ok(Self { auth })
     }
+
+    /// The GraphQL operation table used by this client.
+    ///
+    /// Updates take effect on the next request, e.g. after X rotates a query ID:
+    ///
+    /// ```no_run
+    /// # async fn run(xplore: &xplore::Xplore) -> xplore::Result<()> {
+    /// xplore.operations().set_query_id("UserByScreenName", "xc8f1g7BYqr6VTzTbvNlGw")?;
+    /// xplore.operations().load_file("operations.toml")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn operations(&self) -> &OperationRegistry {
+        &self.auth.operations
+    }
+
+    /// The `x-rate-limit-*` headers of the last response that carried them.
+    ///
+    /// X counts requests per endpoint, so this describes whichever endpoint was called last.
+    pub fn rate_limit(&self) -> Option<RateLimitInfo> {
+        self.auth.last_rate_limit.read().unwrap().clone()
+    }
+
+    /// Refreshes the operation table from the query IDs currently served by x.com.
+    ///
+    /// Returns the names of the operations whose entry changed.
+    pub async fn refresh_operations(&self) -> Result<Vec<String>> {
+        let discovery = discovery::discover_operations(&self.auth).await?;
+        Ok(self.auth.operations.refresh(&discovery.operations, &discovery.feature_defaults))
+    }
 }
 
-///! Login's API collection
+/// Login's API collection
 impl Xplore {
-    ///! Login Method
+    /// Login Method
     ///
     /// Authenticates a user with the provided credentials.
     ///
@@ -125,36 +357,69 @@ impl Xplore {
     /// - Network error occurred during authentication
     /// - Server rejected the login request
     pub async fn login(
-        &mut self,
+        &self,
         username: &str,
         password: &str,
         email: Option<&str>,
         two_factor_secret: Option<&str>,
     ) -> Result<bool> {
-        let _ = self.auth.login(username, password, email, two_factor_secret).await;
+        self.auth.login(username, password, email, two_factor_secret).await?;
 
         Ok(true)
     }
 
-    ///! Logout Method
+    /// Starts a login that the caller drives one subtask at a time.
+    ///
+    /// # Returns
+    /// * `Result<LoginFlow>` - The flow, positioned at the first subtask X asked for.
+    ///
+    /// # Errors
+    /// Returns an error if no guest token can be obtained or X rejects the start of the flow.
+    pub async fn start_login(&self) -> Result<LoginFlow> {
+        self.auth.start_login().await
+    }
+
+    /// Answers the current subtask of a login started with [`Xplore::start_login`].
+    ///
+    /// # Arguments
+    /// * `flow` - The login in progress; it is moved to the subtasks X sends back.
+    /// * `input` - The answer to `flow.current()`.
+    ///
+    /// # Errors
+    /// Returns `XploreError::InvalidCredentials` or `XploreError::AccountLocked` when X rejects
+    /// the answer, `XploreError::LoginDenied` when the current subtask is `DenyLogin`, and
+    /// `XploreError::LoginChallenge` when `input` does not fit the current subtask.
+    pub async fn submit_login(&self, flow: &mut LoginFlow, input: LoginInput) -> Result<()> {
+        self.auth.submit_login(flow, input).await
+    }
+
+    /// Logout Method
     ///
-    /// Terminates the current user session.
+    /// Terminates the current user session on X's side and clears the `ct0` and `auth_token`
+    /// cookies, the tokens and the stored session.
     ///
     /// # Returns
-    /// * `Result<bool>` - Returns `Ok(true)` if logout was successful, or an error if logout failed.
+    /// * `Result<bool>` - Returns `Ok(true)` if X confirmed the logout, or `Ok(false)` if there was
+    ///   no session or X had already invalidated it; it is cleared locally either way.
     ///
     /// # Errors
     /// Returns an error if:
-    /// - No active session was found
     /// - Network error occurred during logout
     /// - Server rejected the logout request
-    pub async fn logout(&mut self) -> Result<bool> {
-        self.auth.logout().await;
+    ///
+    /// The session is kept after an error, so the logout can be retried.
+    pub async fn logout(&self) -> Result<bool> {
+        Ok(self.auth.logout(false).await?.server_confirmed)
+    }
 
-        Ok(true)
+    /// Revokes every other session of the account, then logs this one out like [`Xplore::logout`].
+    ///
+    /// Meant for decommissioning an account: afterwards no cookie issued for it should work.
+    pub async fn logout_everywhere(&self) -> Result<LogoutReport> {
+        self.auth.logout(true).await
     }
 
-    ///! Set Cookie Method
+    /// Set Cookie Method
     ///
     /// Sets the authentication cookie from a raw cookie string.
     ///
@@ -162,15 +427,38 @@ impl Xplore {
     /// * `cookie` - The raw cookie string containing authentication information.
     ///
     /// # Returns
-    /// This method does not return a value, but may fail silently if the cookie is invalid.
+    /// * `Result<()>` - Returns `Ok(())` if the cookie was installed.
     ///
     /// # Errors
-    /// Errors may occur internally during cookie parsing and validation, but are currently ignored.
-    pub async fn set_cookie(&mut self, cookie: &str) {
-        let _ = self.auth.set_from_cookie_string(cookie).await;
+    /// Returns `XploreError::Cookie` if the string lacks the `ct0` or `auth_token` cookie.
+    pub async fn set_cookie(&self, cookie: &str) -> Result<()> {
+        self.auth.set_from_cookie_string(cookie).await
     }
 
-    ///! Get Cookie Method
+    /// Sets the session cookies from a browser export.
+    ///
+    /// Accepts a Netscape `cookies.txt` file or the JSON array exported by EditThisCookie or
+    /// Cookie-Editor, see [`cookie_import`]. Cookies keep the domains they were exported with;
+    /// expired cookies and cookies of other sites are skipped.
+    ///
+    /// # Errors
+    /// Returns `XploreError::Cookie` if the file cannot be parsed or holds no unexpired `ct0` and
+    /// `auth_token` cookies.
+    pub async fn import_cookies(&self, path: impl AsRef<Path>) -> Result<()> {
+        let cookies = cookie_import::load_cookie_file(path)?;
+        self.auth.set_stored_cookies(&cookies).await
+    }
+
+    /// Whether the client has no logged-in session.
+    ///
+    /// Guests can still look up profiles, tweets and user timelines; the guest token this needs
+    /// is activated on first use and replaced when it gets old or X rejects it. Other
+    /// operations fail with `XploreError::LoginRequired`.
+    pub async fn is_guest(&self) -> bool {
+        !self.auth.has_session().await
+    }
+
+    /// Get Cookie Method
     ///
     /// Retrieves the current authentication cookie as a string.
     ///
@@ -182,39 +470,93 @@ impl Xplore {
     /// - No active session exists
     /// - Cookie could not be serialized to string
     /// - Network error occurred during cookie retrieval
-    pub async fn get_cookie(&mut self) -> Result<String> {
+    pub async fn get_cookie(&self) -> Result<String> {
         self.auth.get_cookie_string().await
     }
+
+    /// Save Session Method
+    ///
+    /// Writes the cookies with their attributes, the guest and bearer tokens and the account of
+    /// this session to a versioned JSON file.
+    ///
+    /// # Arguments
+    /// * `path` - The file to write; it is replaced atomically and, on Unix, readable by its owner only.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be written.
+    pub async fn save_session(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.auth.export_session().await.save(path)
+    }
+
+    /// Load Session Method
+    ///
+    /// Restores a session written by [`Xplore::save_session`] (or a cookie file in the older
+    /// `(name, value)` format) and checks it against X.
+    ///
+    /// # Arguments
+    /// * `path` - The session file to read.
+    ///
+    /// # Returns
+    /// * `Result<bool>` - `Ok(true)` if X accepts the restored session as logged in.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or parsed, or if the check itself fails for a
+    /// reason other than the session being logged out.
+    pub async fn load_session(&self, path: impl AsRef<Path>) -> Result<bool> {
+        self.restore_session(Session::load(path)?).await
+    }
+
+    /// Returns a snapshot of this session, for storing it elsewhere than in a file.
+    pub async fn session(&self) -> Session {
+        self.auth.export_session().await
+    }
+
+    /// Replaces this session with `session` and checks it against X, like [`Xplore::load_session`].
+    pub async fn restore_session(&self, session: Session) -> Result<bool> {
+        self.auth.import_session(session).await?;
+        self.auth.is_logged_in().await
+    }
 }
 
-///! Profile's API collection
+/// Profile's API collection
 impl Xplore {
-    ///! Fetches the profile of a user by their screen name.
+    /// Fetches the logged-in account: its profile and its settings.
+    ///
+    /// Cheap enough to serve as a health check before a long job, since it fails fast when X no
+    /// longer accepts the session.
+    /// # Errors
+    /// Returns `XploreError::LoginRequired` for guests and `XploreError::Unauthorized` if the
+    /// session was invalidated.
+    pub async fn me(&self) -> Result<CurrentAccount> {
+        get_current_account(&self.auth).await
+    }
+
+    /// Fetches the profile of a user by their screen name.
     /// # Arguments
     /// * `screen_name` - The screen name of the user whose profile is to be fetched.
     /// # Returns
     /// * `Result<Profile>` - A result containing the user's profile if successful, or an error if not.
     /// # Errors
     /// Returns an error if the profile cannot be fetched, such as if the user does not exist or if there is a network issue.
-    pub async fn get_profile(&mut self, screen_name: &str) -> Result<Profile> {
-        get_profile(&mut self.auth, screen_name).await
+    pub async fn get_profile(&self, screen_name: &str) -> Result<Profile> {
+        get_profile(&self.auth, screen_name).await
     }
 
-    ///! Fetches the user ID of a user by their screen name.
+    /// Fetches the user ID of a user by their screen name.
     /// # Arguments
     /// * `screen_name` - The screen name of the user whose ID is to be fetched.
     /// # Returns
     /// * `Result<String>` - A result containing the user's ID if successful, or an error if not.
     /// # Errors
     /// Returns an error if the user ID cannot be fetched, such as if the user does not exist or if there is a network issue.
-    pub async fn get_user_id(&mut self, screen_name: &str) -> Result<String> {
-        get_user_id(&mut self.auth, screen_name).await
+    pub async fn get_user_id(&self, screen_name: &str) -> Result<String> {
+        get_user_id(&self.auth, screen_name).await
     }
 }
 
-///! Search's API collection
+/// Search's API collection
 impl Xplore {
-    ///! Searches for tweets based on a query string.
+    /// Searches for tweets based on a query string.
     /// # Arguments
     /// * `query` - The search query string to find tweets.
     /// * `max_tweets` - The maximum number of tweets to return.
@@ -225,16 +567,35 @@ impl Xplore {
     /// # Errors
     /// Returns an error if the search fails, such as if the query is invalid or if there is a network issue.
     pub async fn search_tweets(
-        &mut self,
+        &self,
         query: &str,
         max_tweets: i32,
         search_mode: SearchMode,
         cursor: Option<String>,
     ) -> Result<QueryTweetsResponse> {
-        search::search_tweets(&mut self.auth, query, max_tweets, search_mode, cursor).await
+        search::search_tweets(&self.auth, query, max_tweets, search_mode, cursor).await
     }
 
-    ///! Searches for user profiles based on a query string.
+    /// Streams every tweet matching a query, following the pagination cursor.
+    /// # Arguments
+    /// * `query` - The search query string to find tweets.
+    /// * `search_mode` - The mode of search to be used (e.g., recent, popular).
+    /// * `limit` - The maximum number of tweets to yield, or `None` to exhaust the timeline.
+    /// # Returns
+    /// * `impl Stream<Item = Result<Tweet>>` - A stream that requests further pages as it is polled.
+    /// # Errors
+    /// The stream yields an error and ends if a page cannot be fetched, including when the
+    /// rate-limit strategy refuses to wait for the next window.
+    pub fn search_tweets_stream(
+        &self,
+        query: &str,
+        search_mode: SearchMode,
+        limit: Option<usize>,
+    ) -> impl Stream<Item = Result<Tweet>> + Send {
+        pagination::search_tweets(self.auth.clone(), query, search_mode, limit)
+    }
+
+    /// Searches for user profiles based on a query string.
     /// # Arguments
     /// * `query` - The search query string to find user profiles.
     /// * `max_profiles` - The maximum number of profiles to return.
@@ -244,18 +605,60 @@ impl Xplore {
     /// # Errors
     /// Returns an error if the search fails, such as if the query is invalid or if there is a network issue.
     pub async fn search_profiles(
-        &mut self,
+        &self,
         query: &str,
         max_profiles: i32,
         cursor: Option<String>,
     ) -> Result<QueryProfilesResponse> {
-        search::search_profiles(&mut self.auth, query, max_profiles, cursor).await
+        search::search_profiles(&self.auth, query, max_profiles, cursor).await
+    }
+
+    /// Streams every user profile matching a query, following the pagination cursor.
+    /// # Arguments
+    /// * `query` - The search query string to find user profiles.
+    /// * `limit` - The maximum number of profiles to yield, or `None` to exhaust the timeline.
+    /// # Returns
+    /// * `impl Stream<Item = Result<Profile>>` - A stream that requests further pages as it is polled.
+    /// # Errors
+    /// The stream yields an error and ends if a page cannot be fetched.
+    pub fn search_profiles_stream(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+    ) -> impl Stream<Item = Result<Profile>> + Send {
+        pagination::search_profiles(self.auth.clone(), query, limit)
+    }
+
+    /// Fetches the next page of a resumable tweet search.
+    /// # Arguments
+    /// * `session` - The search session; its cursor is advanced and, if it has a file, saved.
+    /// * `max_tweets` - The maximum number of tweets to return.
+    /// # Returns
+    /// * `Result<Vec<Tweet>>` - The tweets of the page, empty once the session is exhausted.
+    /// # Errors
+    /// Returns `XploreError::Config` for a session in `SearchMode::Users`, or an error if the page
+    /// cannot be fetched or the session cannot be saved.
+    pub async fn next_search_tweets(&self, session: &mut SearchSession, max_tweets: i32) -> Result<Vec<Tweet>> {
+        search::next_session_tweets(&self.auth, session, max_tweets).await
+    }
+
+    /// Fetches the next page of a resumable profile search.
+    /// # Arguments
+    /// * `session` - The search session; its cursor is advanced and, if it has a file, saved.
+    /// * `max_profiles` - The maximum number of profiles to return.
+    /// # Returns
+    /// * `Result<Vec<Profile>>` - The profiles of the page, empty once the session is exhausted.
+    /// # Errors
+    /// Returns `XploreError::Config` unless the session is in `SearchMode::Users`, or an error if
+    /// the page cannot be fetched or the session cannot be saved.
+    pub async fn next_search_profiles(&self, session: &mut SearchSession, max_profiles: i32) -> Result<Vec<Profile>> {
+        search::next_session_profiles(&self.auth, session, max_profiles).await
     }
 }
 
-///! Relationship's API collection
+/// Relationship's API collection
 impl Xplore {
-    ///! Fetches the home timeline with a specified count and a list of seen tweet IDs.
+    /// Fetches the home timeline with a specified count and a list of seen tweet IDs.
     /// # Arguments
     /// * `count` - The number of tweets to return.
     /// * `seen_tweet_ids` - A vector of tweet IDs that have already been seen.
@@ -263,11 +666,11 @@ impl Xplore {
     /// * `Result<Vec<Value>>` - A result containing a vector of tweets if successful, or an error if not.
     /// # Errors
     /// Returns an error if the home timeline cannot be fetched, such as if there is a network issue or if the user is not authenticated.
-    pub async fn get_home_timeline(&mut self, count: i32, seen_tweet_ids: Vec<String>) -> Result<Vec<Value>> {
+    pub async fn get_home_timeline(&self, count: i32, seen_tweet_ids: Vec<String>) -> Result<Vec<Value>> {
         relationship::get_home_timeline(self, count, seen_tweet_ids).await
     }
 
-    ///! Fetches the relationship status between the authenticated user and another user.
+    /// Fetches the relationship status between the authenticated user and another user.
     /// # Arguments
     /// * `user_id` - The ID of the user whose relationship status is to be fetched.
     /// # Returns
@@ -275,7 +678,7 @@ impl Xplore {
     /// # Errors
     /// Returns an error if the relationship status cannot be fetched, such as if the user does not exist or if there is a network issue.
     pub async fn get_following(
-        &mut self,
+        &self,
         user_id: &str,
         count: i32,
         cursor: Option<String>,
@@ -283,7 +686,23 @@ impl Xplore {
         relationship::get_following(self, user_id, count, cursor).await
     }
 
-    ///! Fetches the followers of a user.
+    /// Streams the accounts a user follows, following the pagination cursor.
+    /// # Arguments
+    /// * `user_id` - The ID of the user whose followings are to be fetched.
+    /// * `limit` - The maximum number of profiles to yield, or `None` to exhaust the timeline.
+    /// # Returns
+    /// * `impl Stream<Item = Result<Profile>>` - A stream that requests further pages as it is polled.
+    /// # Errors
+    /// The stream yields an error and ends if a page cannot be fetched.
+    pub fn get_following_stream(
+        &self,
+        user_id: &str,
+        limit: Option<usize>,
+    ) -> impl Stream<Item = Result<Profile>> + Send {
+        pagination::relationships(self.auth.clone(), "Following", user_id, limit)
+    }
+
+    /// Fetches the followers of a user.
     /// # Arguments
     /// * `user_id` - The ID of the user whose followers are to be fetched
     /// * `count` - The maximum number of followers to return.
@@ -293,7 +712,7 @@ impl Xplore {
     /// # Errors
     /// Returns an error if the followers cannot be fetched, such as if the user does not exist or if there is a network issue.
     pub async fn get_followers(
-        &mut self,
+        &self,
         user_id: &str,
         count: i32,
         cursor: Option<String>,
@@ -301,83 +720,205 @@ impl Xplore {
         relationship::get_followers(self, user_id, count, cursor).await
     }
 
-    ///! Follows a user by their username.
+    /// Streams the followers of a user, following the pagination cursor.
+    /// # Arguments
+    /// * `user_id` - The ID of the user whose followers are to be fetched.
+    /// * `limit` - The maximum number of profiles to yield, or `None` to exhaust the timeline.
+    /// # Returns
+    /// * `impl Stream<Item = Result<Profile>>` - A stream that requests further pages as it is polled.
+    /// # Errors
+    /// The stream yields an error and ends if a page cannot be fetched.
+    pub fn get_followers_stream(
+        &self,
+        user_id: &str,
+        limit: Option<usize>,
+    ) -> impl Stream<Item = Result<Profile>> + Send {
+        pagination::relationships(self.auth.clone(), "Followers", user_id, limit)
+    }
+
+    /// Follows a user by their username.
     /// # Arguments
     /// * `username` - The username of the user to follow.
     /// # Returns
     /// * `Result<()>` - A result indicating success or failure.
     /// # Errors    
     /// Returns an error if the follow action fails, such as if the user does not exist or if there is a network issue.
-    pub async fn follow(&mut self, username: &str) -> Result<()> {
+    pub async fn follow(&self, username: &str) -> Result<()> {
         relationship::follow(self, username).await
     }
 
-    ///! Unfollows a user by their username.
+    /// Unfollows a user by their username.
     /// # Arguments
     /// * `username` - The username of the user to unfollow.
     /// # Returns
     /// * `Result<()>` - A result indicating success or failure.
     /// # Errors
     /// Returns an error if the unfollow action fails, such as if the user does not
-    pub async fn unfollow(&mut self, username: &str) -> Result<()> {
+    pub async fn unfollow(&self, username: &str) -> Result<()> {
         relationship::unfollow(self, username).await
     }
 }
 
-///! Tweet's API collection
+/// Tweet's API collection
 impl Xplore {
-    ///! Posts a tweet with optional media attachments.
+    /// Posts a tweet with optional media attachments.
+    ///
+    /// Text whose weighted length (see [`text::weighted_length`]) is over `MAX_TWEET_LENGTH` is
+    /// posted as a long tweet.
     /// # Arguments
     /// * `text` - The text content of the tweet.
     /// * `reply_to` - An optional tweet ID to reply to.
     /// * `media_data` - An optional vector of tuples containing media data and their corresponding media types.
     /// # Returns
-    /// * `Result<Value>` - A result containing the response from the tweet posting if successful, or an error if not.
+    /// * `Result<PostedTweet>` - The new tweet with its ID and permalink if successful, or an error if not.
     /// # Errors
-    /// Returns an error if the tweet cannot be posted, such as if the text is too long, if the media data is invalid, or if there is a network issue.
+    /// Returns `XploreError::TweetTooLong` before sending anything if the text is over `MAX_LONG_TWEET_LENGTH`, or an error if the tweet cannot be posted, such as if the media data is invalid, or if there is a network issue.
     pub async fn post_tweet(
-        &mut self,
+        &self,
         text: &str,
         reply_to: Option<&str>,
         media_data: Option<Vec<(Vec<u8>, String)>>,
-    ) -> Result<Value> {
+    ) -> Result<PostedTweet> {
         post_tweet(self, text, reply_to, media_data).await
     }
 
-    ///! reads a tweet by its ID.
+    /// Posts a tweet with a poll.
+    /// # Arguments
+    /// * `text` - The text content of the tweet.
+    /// * `poll` - The choices and duration of the poll.
+    /// * `reply_to` - An optional tweet ID to reply to.
+    /// # Returns
+    /// * `Result<PostedTweet>` - The new tweet with its ID and permalink if successful, or an error if not.
+    /// # Errors
+    /// Returns `XploreError::Config` before sending anything if the poll is outside X's limits.
+    pub async fn post_poll(&self, text: &str, poll: &NewPoll, reply_to: Option<&str>) -> Result<PostedTweet> {
+        post_poll(self, text, poll, reply_to).await
+    }
+
+    /// Fetches the live results of a tweet's poll.
+    /// # Arguments
+    /// * `tweet_id` - The ID of the tweet with the poll.
+    /// # Returns
+    /// * `Result<PollV2>` - The choices with their current vote counts, and whether voting is still open.
+    pub async fn get_poll(&self, tweet_id: &str) -> Result<PollV2> {
+        get_poll(self, tweet_id).await
+    }
+
+    /// Posts a thread, each part replying to the one before.
+    /// # Arguments
+    /// * `parts` - The tweets of the thread in order; parts over `MAX_TWEET_LENGTH` are posted as long tweets.
+    /// * `rollback` - Whether to delete the tweets already posted if a later part fails.
+    /// # Returns
+    /// * `Result<Vec<PostedTweet>>` - The posted tweets in order if every part was posted.
+    /// # Errors
+    /// Returns `XploreError::ThreadFailed` with the IDs posted so far, and those deleted again, if a part fails.
+    pub async fn post_thread(&self, parts: Vec<ThreadPart>, rollback: bool) -> Result<Vec<PostedTweet>> {
+        post_thread(self, parts, rollback).await
+    }
+
+    /// reads a tweet by its ID.
     /// # Arguments
     /// * `tweet_id` - The ID of the tweet to be read.
     /// # Returns
     /// * `Result<Tweet>` - A result containing the tweet if successful, or an error if not.
     /// # Errors
     /// Returns an error if the tweet cannot be read, such as if the tweet does not exist or if there is a network issue.
-    pub async fn read_tweet(&mut self, tweet_id: &str) -> Result<Tweet> {
+    pub async fn read_tweet(&self, tweet_id: &str) -> Result<Tweet> {
         read_tweet(self, tweet_id).await
     }
 
-    ///! Retweets a tweet by its ID.
+    /// Retweets a tweet by its ID.
     /// # Arguments
     /// * `tweet_id` - The ID of the tweet to be retweeted.
     /// # Returns
-    /// * `Result<TweetRetweetResponse>` - A result containing the retweet response if successful, or an error if not.
+    /// * `Result<TweetActionOutcome>` - `AlreadyApplied` if the account had already retweeted it.
     /// # Errors
-    /// Returns an error if the retweet action fails, such as if the tweet does not exist, if the user has already retweeted it, or if there is a network issue.
-    pub async fn retweet(&mut self, tweet_id: &str) -> Result<TweetRetweetResponse> {
+    /// Returns an error if the retweet action fails, such as if the tweet does not exist or if there is a network issue.
+    pub async fn retweet(&self, tweet_id: &str) -> Result<TweetActionOutcome> {
         retweet(self, tweet_id).await
     }
 
-    ///! Likes a tweet by its ID.
+    /// Likes a tweet by its ID.
     /// # Arguments
     /// * `tweet_id` - The ID of the tweet to be liked.
     /// # Returns
-    /// * `Result<Value>` - A result containing the response from the like action if successful, or an error if not.
+    /// * `Result<TweetActionOutcome>` - `AlreadyApplied` if the account already liked it.
     /// # Errors
-    /// Returns an error if the like action fails, such as if the tweet does not exist, if the user has already liked it, or if there is a network issue.
-    pub async fn like_tweet(&mut self, tweet_id: &str) -> Result<Value> {
+    /// Returns an error if the like action fails, such as if the tweet does not exist or if there is a network issue.
+    pub async fn like_tweet(&self, tweet_id: &str) -> Result<TweetActionOutcome> {
         like_tweet(self, tweet_id).await
     }
 
-    ///! Gets a user's tweets.
+    /// Deletes one of the account's tweets.
+    /// # Arguments
+    /// * `tweet_id` - The ID of the tweet to be deleted.
+    /// # Returns
+    /// * `Result<TweetActionOutcome>` - `AlreadyApplied` if the tweet no longer exists, or never did.
+    pub async fn delete_tweet(&self, tweet_id: &str) -> Result<TweetActionOutcome> {
+        delete_tweet(self, tweet_id).await
+    }
+
+    /// Removes the like from a tweet.
+    /// # Arguments
+    /// * `tweet_id` - The ID of the tweet to be unliked.
+    /// # Returns
+    /// * `Result<TweetActionOutcome>` - `AlreadyApplied` if the tweet was not liked.
+    /// # Errors
+    /// Returns `XploreError::NotFound` if the tweet does not exist.
+    pub async fn unlike_tweet(&self, tweet_id: &str) -> Result<TweetActionOutcome> {
+        unlike_tweet(self, tweet_id).await
+    }
+
+    /// Undoes a retweet.
+    /// # Arguments
+    /// * `tweet_id` - The ID of the retweeted tweet, not of the retweet.
+    /// # Returns
+    /// * `Result<TweetActionOutcome>` - `AlreadyApplied` if the tweet was not retweeted.
+    /// # Errors
+    /// Returns `XploreError::NotFound` if the tweet does not exist.
+    pub async fn unretweet(&self, tweet_id: &str) -> Result<TweetActionOutcome> {
+        unretweet(self, tweet_id).await
+    }
+
+    /// Bookmarks a tweet.
+    /// # Arguments
+    /// * `tweet_id` - The ID of the tweet to be bookmarked.
+    /// # Returns
+    /// * `Result<TweetActionOutcome>` - `AlreadyApplied` if the tweet was already bookmarked.
+    pub async fn bookmark_tweet(&self, tweet_id: &str) -> Result<TweetActionOutcome> {
+        bookmark_tweet(self, tweet_id).await
+    }
+
+    /// Removes a tweet from the bookmarks.
+    /// # Arguments
+    /// * `tweet_id` - The ID of the tweet to be unbookmarked.
+    /// # Returns
+    /// * `Result<TweetActionOutcome>` - `AlreadyApplied` if the tweet was not bookmarked.
+    /// # Errors
+    /// Returns `XploreError::NotFound` if the tweet does not exist.
+    pub async fn unbookmark_tweet(&self, tweet_id: &str) -> Result<TweetActionOutcome> {
+        unbookmark_tweet(self, tweet_id).await
+    }
+
+    /// Pins one of the account's tweets to its profile, replacing the pinned tweet if there is one.
+    /// # Arguments
+    /// * `tweet_id` - The ID of the tweet to be pinned.
+    pub async fn pin_tweet(&self, tweet_id: &str) -> Result<TweetActionOutcome> {
+        pin_tweet(self, tweet_id).await
+    }
+
+    /// Unpins a tweet from the account's profile.
+    /// # Arguments
+    /// * `tweet_id` - The ID of the pinned tweet.
+    /// # Returns
+    /// * `Result<TweetActionOutcome>` - `AlreadyApplied` if the tweet was not pinned.
+    /// # Errors
+    /// Returns `XploreError::NotFound` if the tweet does not exist.
+    pub async fn unpin_tweet(&self, tweet_id: &str) -> Result<TweetActionOutcome> {
+        unpin_tweet(self, tweet_id).await
+    }
+
+    /// Gets a user's tweets.
     /// # Arguments
     /// * `user_id` - The ID of the user whose tweets are to be fetched.
     /// * `limit` - The maximum number of tweets to return.
@@ -385,29 +926,29 @@ impl Xplore {
     /// * `Result<Vec<Tweet>>` - A result containing a vector of tweets if successful, or an error if not.
     /// # Errors
     /// Returns an error if the tweets cannot be fetched, such as if the user does not exist or if there is a network issue.
-    pub async fn get_user_tweets(&mut self, user_id: &str, limit: usize) -> Result<Vec<Tweet>> {
+    pub async fn get_user_tweets(&self, user_id: &str, limit: usize) -> Result<Vec<Tweet>> {
         get_user_tweets(self, user_id, limit).await
     }
 
-    ///! Sends a quote tweet with optional media attachments.
+    /// Sends a quote tweet with optional media attachments.
     /// # Arguments
     /// * `text` - The text content of the quote tweet.
     /// * `quoted_tweet_id` - The ID of the tweet being quoted.
     /// * `media_data` - An optional vector of tuples containing media data and their corresponding media types.
     /// # Returns
-    /// * `Result<Value>` - A result containing the response from the quote tweet action if successful, or an error if not.
+    /// * `Result<PostedTweet>` - The new quote tweet with its ID and permalink if successful, or an error if not.
     /// # Errors
     /// Returns an error if the quote tweet cannot be sent, such as if the text is too long, if the quoted tweet does not exist, if the media data is invalid, or if there is a network issue.
     pub async fn send_quote_tweet(
-        &mut self,
+        &self,
         text: &str,
         quoted_tweet_id: &str,
         media_data: Option<Vec<(Vec<u8>, String)>>,
-    ) -> Result<Value> {
+    ) -> Result<PostedTweet> {
         send_quote_tweet(self, text, quoted_tweet_id, media_data).await
     }
 
-    ///! Fetches tweets and replies from a user's timeline.
+    /// Fetches tweets and replies from a user's timeline.
     /// # Arguments
     /// * `username` - The screen name of the user whose tweets and replies are to be fetched.
     /// * `max_tweets` - The maximum number of tweets to return.
@@ -417,7 +958,7 @@ impl Xplore {
     /// # Errors
     /// Returns an error if the tweets and replies cannot be fetched, such as if the user does not exist or if there is a network issue.
     pub async fn fetch_tweets_and_replies(
-        &mut self,
+        &self,
         username: &str,
         max_tweets: i32,
         cursor: Option<&str>,
@@ -425,7 +966,23 @@ impl Xplore {
         fetch_tweets_and_replies(self, username, max_tweets, cursor).await
     }
 
-    ///! Fetches tweets and replies from a user's timeline by their user ID.
+    /// Streams the tweets and replies of a user, following the pagination cursor.
+    /// # Arguments
+    /// * `username` - The screen name of the user whose tweets and replies are to be fetched.
+    /// * `limit` - The maximum number of tweets to yield, or `None` to exhaust the timeline.
+    /// # Returns
+    /// * `impl Stream<Item = Result<Tweet>>` - A stream that requests further pages as it is polled.
+    /// # Errors
+    /// The stream yields an error and ends if the user cannot be resolved or a page cannot be fetched.
+    pub fn fetch_tweets_and_replies_stream(
+        &self,
+        username: &str,
+        limit: Option<usize>,
+    ) -> impl Stream<Item = Result<Tweet>> + Send {
+        pagination::tweets_and_replies(self.auth.clone(), username, limit)
+    }
+
+    /// Fetches tweets and replies from a user's timeline by their user ID.
     /// # Arguments
     /// * `user_id` - The ID of the user whose tweets and replies are to be fetched.
     /// * `max_tweets` - The maximum number of tweets to return.
@@ -435,7 +992,7 @@ impl Xplore {
     /// # Errors
     /// Returns an error if the tweets and replies cannot be fetched, such as if the user does not exist or if there is a network issue.
     pub async fn fetch_tweets_and_replies_by_user_id(
-        &mut self,
+        &self,
         user_id: &str,
         max_tweets: i32,
         cursor: Option<&str>,
@@ -443,7 +1000,7 @@ impl Xplore {
         fetch_tweets_and_replies_by_user_id(self, user_id, max_tweets, cursor).await
     }
 
-    ///! Fetches tweets from a list by its ID.
+    /// Fetches tweets from a list by its ID.
     /// # Arguments
     /// * `list_id` - The ID of the list whose tweets are to be fetched.
     /// * `max_tweets` - The maximum number of tweets to return.
@@ -452,38 +1009,51 @@ impl Xplore {
     /// * `Result<Value>` - A result containing the response with tweets if successful, or an error if not.
     /// # Errors
     /// Returns an error if the tweets cannot be fetched, such as if the list does not exist or if there is a network issue.
-    pub async fn fetch_list_tweets(&mut self, list_id: &str, max_tweets: i32, cursor: Option<&str>) -> Result<Value> {
+    pub async fn fetch_list_tweets(&self, list_id: &str, max_tweets: i32, cursor: Option<&str>) -> Result<Value> {
         fetch_list_tweets(self, list_id, max_tweets, cursor).await
     }
 
-    ///! Creates a long tweet with optional media attachments.
+    /// Streams the tweets of a list, following the pagination cursor.
+    /// # Arguments
+    /// * `list_id` - The ID of the list whose tweets are to be fetched.
+    /// * `limit` - The maximum number of tweets to yield, or `None` to exhaust the timeline.
+    /// # Returns
+    /// * `impl Stream<Item = Result<Tweet>>` - A stream that requests further pages as it is polled.
+    /// # Errors
+    /// The stream yields an error and ends if a page cannot be fetched.
+    pub fn fetch_list_tweets_stream(
+        &self,
+        list_id: &str,
+        limit: Option<usize>,
+    ) -> impl Stream<Item = Result<Tweet>> + Send {
+        pagination::list_tweets(self.auth.clone(), list_id, limit)
+    }
+
+    /// Creates a long tweet with optional media attachments.
     /// # Arguments
     /// * `text` - The text content of the long tweet.
     /// * `reply_to` - An optional tweet ID to reply to.
     /// * `media_ids` - An optional vector of media IDs to attach to the long tweet.
     /// # Returns
-    /// * `Result<Value>` - A result containing the response from the long tweet creation if successful, or an error if not.
+    /// * `Result<PostedTweet>` - The new tweet with its full text, ID and permalink if successful, or an error if not.
     /// # Errors
     /// Returns an error if the long tweet cannot be created, such as if the text is too long, if the media IDs are invalid, or if there is a network issue.
     pub async fn create_long_tweet(
-        &mut self,
+        &self,
         text: &str,
         reply_to: Option<&str>,
         media_ids: Option<Vec<String>>,
-    ) -> Result<Value> {
+    ) -> Result<PostedTweet> {
         create_long_tweet(self, text, reply_to, media_ids).await
     }
 }
 
-///! Trend's API collection
+/// Trend's API collection
 impl Xplore {
-    ///! Fetches the current trending topics.
+    /// Fetches the current trending topics.
     ///
     /// Retrieves a list of current trending topics from the platform.
     ///
-    /// # Arguments
-    /// * `self` - The mutable reference to the Xplore instance (implicitly passed).
-    ///
     /// # Returns
     /// * `Result<Vec<String>>` - A result containing a vector of trend names if successful,
     ///   or an error if the trends cannot be fetched.
@@ -493,7 +1063,7 @@ impl Xplore {
     /// - The request to fetch trends fails (network or API error)
     /// - The response cannot be parsed into a list of strings
     /// - There is an authentication issue preventing access to trends
-    pub async fn get_trend(&mut self) -> Result<Vec<String>> {
-        get_trend(&mut self.auth).await
+    pub async fn get_trend(&self) -> Result<Vec<String>> {
+        get_trend(&self.auth).await
     }
 }
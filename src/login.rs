@@ -0,0 +1,305 @@
+//! The onboarding flow X uses to log in.
+//!
+//! A login is a sequence of subtasks: every request to `onboarding/task.json` is answered with a
+//! new flow token and the subtasks X wants completed next. [`LoginFlow`] exposes that sequence
+//! one step at a time, so a caller can see which subtask it is on and what X asked for.
+//! [`Xplore::login`](crate::Xplore::login) drives the same flow with fixed credentials and, for
+//! challenges those cannot answer, a [`LoginChallengeHandler`].
+//!
+//! ```no_run
+//! # use xplore::{login::{LoginInput, SubtaskType}, Xplore};
+//! # async fn run(xplore: &Xplore) -> xplore::Result<()> {
+//! let mut flow = xplore.start_login().await?;
+//!
+//! while let Some(subtask) = flow.current() {
+//!     let input = match subtask.subtask_type {
+//!         SubtaskType::LoginEnterUserIdentifier => LoginInput::UserIdentifier("user".into()),
+//!         SubtaskType::LoginEnterPassword => LoginInput::Password("secret".into()),
+//!         SubtaskType::LoginAcid => LoginInput::Acid(read_code_from_stdin()),
+//!         _ => LoginInput::Continue,
+//!     };
+//!     xplore.submit_login(&mut flow, input).await?;
+//! }
+//! # Ok(())
+//! # }
+//! # fn read_code_from_stdin() -> String { String::new() }
+//! ```
+
+use {
+    crate::{Result, XploreError},
+    async_trait::async_trait,
+    serde::{Deserialize, Serialize},
+    serde_json::{json, Value},
+    std::fmt,
+};
+
+/// The kind of a login subtask, from its `subtask_id`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubtaskType {
+    LoginJsInstrumentation,
+    LoginEnterUserIdentifier,
+    LoginEnterPassword,
+    LoginAcid,
+    AccountDuplicationCheck,
+    LoginTwoFactorAuthChallenge,
+    LoginEnterAlternateIdentifier,
+    LoginSuccess,
+    DenyLogin,
+    Unknown(String),
+}
+
+impl From<&str> for SubtaskType {
+    fn from(s: &str) -> Self {
+        match s {
+            "LoginJsInstrumentationSubtask" => Self::LoginJsInstrumentation,
+            "LoginEnterUserIdentifierSSO" => Self::LoginEnterUserIdentifier,
+            "LoginEnterPassword" => Self::LoginEnterPassword,
+            "LoginAcid" => Self::LoginAcid,
+            "AccountDuplicationCheck" => Self::AccountDuplicationCheck,
+            "LoginTwoFactorAuthChallenge" => Self::LoginTwoFactorAuthChallenge,
+            "LoginEnterAlternateIdentifierSubtask" => Self::LoginEnterAlternateIdentifier,
+            "LoginSuccessSubtask" => Self::LoginSuccess,
+            "DenyLoginSubtask" => Self::DenyLogin,
+            other => Self::Unknown(other.to_string()),
+        }
+    }
+}
+
+/// A subtask X asked the client to complete.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginSubtask {
+    pub subtask_type: SubtaskType,
+    /// The subtask object as sent by X.
+    pub raw: Value,
+}
+
+impl LoginSubtask {
+    fn from_raw(raw: Value) -> Self {
+        let subtask_type = SubtaskType::from(raw.get("subtask_id").and_then(Value::as_str).unwrap_or_default());
+        Self { subtask_type, raw }
+    }
+
+    /// The `subtask_id` X sent, e.g. `LoginAcid`.
+    pub fn id(&self) -> &str {
+        self.raw.get("subtask_id").and_then(Value::as_str).unwrap_or_default()
+    }
+
+    /// The text X shows the user for this subtask, such as "Check your email" or the reason a
+    /// login was denied.
+    pub fn prompt(&self) -> Option<&str> {
+        [
+            "/enter_text/header/primary_text/text",
+            "/enter_text/hint_text",
+            "/cta/secondary_text/text",
+            "/cta/primary_text/text",
+            "/open_account/header/primary_text/text",
+        ]
+        .iter()
+        .find_map(|pointer| self.raw.pointer(pointer).and_then(Value::as_str))
+    }
+}
+
+/// An answer to the current subtask of a [`LoginFlow`].
+#[derive(Clone)]
+pub enum LoginInput {
+    /// Completes subtasks that need nothing from the user: JS instrumentation, the account
+    /// duplication check and the final success subtask.
+    Continue,
+    UserIdentifier(String),
+    Password(String),
+    /// The email address, phone number or confirmation code `LoginAcid` asks for.
+    Acid(String),
+    AlternateIdentifier(String),
+    /// The current code of the account's authenticator app.
+    TwoFactorCode(String),
+    /// A subtask input object sent as is, for subtasks this crate does not know.
+    Raw(Value),
+}
+
+impl LoginInput {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Continue => "Continue",
+            Self::UserIdentifier(_) => "UserIdentifier",
+            Self::Password(_) => "Password",
+            Self::Acid(_) => "Acid",
+            Self::AlternateIdentifier(_) => "AlternateIdentifier",
+            Self::TwoFactorCode(_) => "TwoFactorCode",
+            Self::Raw(_) => "Raw",
+        }
+    }
+}
+
+// Inputs carry passwords and codes, so only the variant is printed.
+impl fmt::Debug for LoginInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LoginInput::{}", self.name())
+    }
+}
+
+/// Answers login challenges that fixed credentials cannot, typically by asking a human.
+///
+/// [`Xplore::login`](crate::Xplore::login) calls the handler configured in
+/// `XploreOptions::login_challenge_handler` for `LoginAcid`, `LoginEnterAlternateIdentifier`,
+/// `LoginTwoFactorAuthChallenge` and unknown subtasks, before falling back to the email or
+/// two-factor secret it was given.
+///
+/// ```no_run
+/// # use async_trait::async_trait;
+/// # use xplore::login::{LoginChallengeHandler, LoginInput, LoginSubtask, SubtaskType};
+/// struct Stdin;
+///
+/// #[async_trait]
+/// impl LoginChallengeHandler for Stdin {
+///     async fn on_challenge(&self, subtask: &LoginSubtask) -> xplore::Result<Option<LoginInput>> {
+///         if subtask.subtask_type != SubtaskType::LoginAcid {
+///             return Ok(None);
+///         }
+///         println!("{}", subtask.prompt().unwrap_or("Enter the code X sent you"));
+///         let mut code = String::new();
+///         std::io::stdin().read_line(&mut code)?;
+///         Ok(Some(LoginInput::Acid(code.trim().to_string())))
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait LoginChallengeHandler: Send + Sync {
+    /// Called when the flow reaches a challenge subtask.
+    ///
+    /// Returns the answer to send, or `None` to leave the subtask to the credentials passed to
+    /// `login`; if those cannot answer it either, the login fails with
+    /// `XploreError::LoginChallenge`.
+    async fn on_challenge(&self, subtask: &LoginSubtask) -> Result<Option<LoginInput>>;
+}
+
+/// A login in progress.
+///
+/// The flow only holds the token and the pending subtasks; the cookies X sets along the way go
+/// to the client's cookie jar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginFlow {
+    flow_token: String,
+    subtasks: Vec<LoginSubtask>,
+}
+
+impl LoginFlow {
+    pub(crate) fn from_response(response: FlowResponse) -> Self {
+        let subtasks = response.subtasks.unwrap_or_default().into_iter().map(LoginSubtask::from_raw).collect();
+        Self { flow_token: response.flow_token, subtasks }
+    }
+
+    /// The token identifying this flow on X's side.
+    pub fn flow_token(&self) -> &str {
+        &self.flow_token
+    }
+
+    /// The subtask to answer next, or `None` once the login is complete.
+    pub fn current(&self) -> Option<&LoginSubtask> {
+        self.subtasks.first()
+    }
+
+    /// Every subtask X sent with the last response.
+    pub fn subtasks(&self) -> &[LoginSubtask] {
+        &self.subtasks
+    }
+
+    /// Whether X has no further subtasks, i.e. the session cookies are set.
+    pub fn is_complete(&self) -> bool {
+        self.subtasks.is_empty()
+    }
+
+    /// Builds the request answering the current subtask with `input`.
+    pub(crate) fn answer(&self, input: LoginInput) -> Result<FlowTaskRequest> {
+        let subtask = self.current().ok_or_else(|| XploreError::Auth("The login flow is already complete".into()))?;
+        let id = subtask.id();
+
+        let subtask_input = match (&subtask.subtask_type, input) {
+            (_, LoginInput::Raw(value)) => Some(value),
+            (SubtaskType::DenyLogin, _) => {
+                return Err(XploreError::LoginDenied(subtask.prompt().unwrap_or("Login denied").to_string()))
+            }
+            (SubtaskType::LoginJsInstrumentation, LoginInput::Continue) => Some(json!({
+                "subtask_id": id,
+                "js_instrumentation": {
+                    "response": "{}",
+                    "link": "next_link"
+                }
+            })),
+            (SubtaskType::LoginEnterUserIdentifier, LoginInput::UserIdentifier(username)) => Some(json!({
+                "subtask_id": id,
+                "settings_list": {
+                    "setting_responses": [
+                        {
+                            "key": "user_identifier",
+                            "response_data": {
+                                "text_data": {
+                                    "result": username
+                                }
+                            }
+                        }
+                    ],
+                    "link": "next_link"
+                }
+            })),
+            (SubtaskType::LoginEnterPassword, LoginInput::Password(password)) => Some(json!({
+                "subtask_id": id,
+                "enter_password": {
+                    "password": password,
+                    "link": "next_link"
+                }
+            })),
+            (SubtaskType::AccountDuplicationCheck, LoginInput::Continue) => Some(json!({
+                "subtask_id": id,
+                "check_logged_in_account": {
+                    "link": "AccountDuplicationCheck_false"
+                }
+            })),
+            (SubtaskType::LoginAcid, LoginInput::Acid(text))
+            | (SubtaskType::LoginEnterAlternateIdentifier, LoginInput::AlternateIdentifier(text))
+            | (SubtaskType::LoginTwoFactorAuthChallenge, LoginInput::TwoFactorCode(text)) => Some(json!({
+                "subtask_id": id,
+                "enter_text": {
+                    "text": text,
+                    "link": "next_link"
+                }
+            })),
+            (SubtaskType::LoginSuccess, LoginInput::Continue) => None,
+            (_, input) => {
+                return Err(XploreError::LoginChallenge(format!(
+                    "subtask {} cannot be answered with LoginInput::{}",
+                    id,
+                    input.name()
+                )))
+            }
+        };
+
+        Ok(FlowTaskRequest { flow_token: self.flow_token.clone(), subtask_inputs: subtask_input.into_iter().collect() })
+    }
+}
+
+/// The outcome of [`Xplore::logout_everywhere`](crate::Xplore::logout_everywhere).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogoutReport {
+    /// X accepted the logout request. `false` when X had already invalidated the session.
+    pub server_confirmed: bool,
+    /// X revoked every other session of the account.
+    pub other_sessions_revoked: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct FlowInitRequest {
+    pub flow_name: String,
+    pub input_flow_data: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct FlowTaskRequest {
+    pub flow_token: String,
+    pub subtask_inputs: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct FlowResponse {
+    pub flow_token: String,
+    pub subtasks: Option<Vec<Value>>,
+}
@@ -0,0 +1,631 @@
+//! Registry of the GraphQL operations used by the crate.
+//!
+//! X identifies every GraphQL operation by a query ID that changes whenever its
+//! web client is redeployed, and rejects requests whose feature switches do not
+//! match what it expects. [`OperationRegistry`] keeps both per operation name,
+//! starting from built-in defaults. Entries can be replaced at runtime or patched
+//! from a JSON or TOML file, so a rotated ID does not have to wait for a release.
+//!
+//! A patch file maps operation names to the fields to change; features are merged
+//! into the existing ones:
+//!
+//! ```toml
+//! [UserByScreenName]
+//! query_id = "xc8f1g7BYqr6VTzTbvNlGw"
+//!
+//! [SearchTimeline.features]
+//! rweb_video_timestamps_enabled = true
+//! ```
+
+use {
+    crate::{discovery::DiscoveredOperation, endpoints::ApiEndpoint, Result, XploreError},
+    reqwest::Method,
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    std::{
+        collections::{BTreeMap, HashMap},
+        fs,
+        path::Path,
+        sync::{Arc, RwLock},
+    },
+};
+
+/// Whether an operation is sent as a `GET` query or a `POST` mutation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Query,
+    Mutation,
+}
+
+impl OperationKind {
+    pub fn method(self) -> Method {
+        match self {
+            OperationKind::Query => Method::GET,
+            OperationKind::Mutation => Method::POST,
+        }
+    }
+}
+
+/// Everything needed to call a GraphQL operation apart from its variables.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Operation {
+    pub query_id: String,
+    pub kind: OperationKind,
+    #[serde(default)]
+    pub features: BTreeMap<String, bool>,
+    #[serde(default)]
+    pub field_toggles: Option<BTreeMap<String, bool>>,
+}
+
+impl Operation {
+    fn builtin(
+        query_id: &str,
+        kind: OperationKind,
+        features: &[(&str, bool)],
+        field_toggles: Option<&[(&str, bool)]>,
+    ) -> Self {
+        let to_map = |flags: &[(&str, bool)]| flags.iter().map(|(name, on)| (name.to_string(), *on)).collect();
+
+        Self {
+            query_id: query_id.to_string(),
+            kind,
+            features: to_map(features),
+            field_toggles: field_toggles.map(to_map),
+        }
+    }
+}
+
+/// Changes to apply to an operation; unset fields are left as they are.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OperationPatch {
+    pub query_id: Option<String>,
+    pub kind: Option<OperationKind>,
+    /// Merged into the existing features.
+    #[serde(default)]
+    pub features: BTreeMap<String, bool>,
+    /// Replaces the existing field toggles.
+    pub field_toggles: Option<BTreeMap<String, bool>>,
+}
+
+/// A shared, updatable table of GraphQL operations keyed by operation name.
+///
+/// Clones share the same table, so an update made through
+/// [`Xplore::operations`](crate::Xplore::operations) is seen by every request
+/// made afterwards.
+#[derive(Debug, Clone)]
+pub struct OperationRegistry {
+    operations: Arc<RwLock<HashMap<String, Operation>>>,
+}
+
+impl Default for OperationRegistry {
+    /// A registry holding the operations built into this release.
+    fn default() -> Self {
+        Self { operations: Arc::new(RwLock::new(builtin_operations())) }
+    }
+}
+
+impl OperationRegistry {
+    /// A registry with no operations at all.
+    pub fn empty() -> Self {
+        Self { operations: Arc::new(RwLock::new(HashMap::new())) }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Operation> {
+        self.operations.read().unwrap().get(name).cloned()
+    }
+
+    /// Names of all registered operations, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = self.operations.read().unwrap().keys().cloned().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    /// Adds or replaces an operation.
+    pub fn insert(&self, name: &str, operation: Operation) {
+        self.operations.write().unwrap().insert(name.to_string(), operation);
+    }
+
+    /// Changes the query ID of a known operation.
+    pub fn set_query_id(&self, name: &str, query_id: &str) -> Result<()> {
+        self.patch(name, OperationPatch { query_id: Some(query_id.to_string()), ..Default::default() })
+    }
+
+    /// Turns a feature switch of a known operation on or off.
+    pub fn set_feature(&self, name: &str, feature: &str, enabled: bool) -> Result<()> {
+        let features = BTreeMap::from([(feature.to_string(), enabled)]);
+        self.patch(name, OperationPatch { features, ..Default::default() })
+    }
+
+    /// Applies `patch` to the operation called `name`.
+    ///
+    /// An unknown operation is added, provided the patch has a query ID.
+    pub fn patch(&self, name: &str, patch: OperationPatch) -> Result<()> {
+        let mut operations = self.operations.write().unwrap();
+
+        let operation = match operations.get_mut(name) {
+            Some(operation) => operation,
+            None => {
+                let query_id = patch
+                    .query_id
+                    .clone()
+                    .ok_or_else(|| XploreError::Config(format!("Unknown operation {} needs a query_id", name)))?;
+                let kind = patch.kind.unwrap_or(OperationKind::Query);
+                let operation = Operation { query_id, kind, features: BTreeMap::new(), field_toggles: None };
+                operations.entry(name.to_string()).or_insert(operation)
+            }
+        };
+
+        if let Some(query_id) = patch.query_id {
+            operation.query_id = query_id;
+        }
+        if let Some(kind) = patch.kind {
+            operation.kind = kind;
+        }
+        operation.features.extend(patch.features);
+        if let Some(field_toggles) = patch.field_toggles {
+            operation.field_toggles = Some(field_toggles);
+        }
+
+        Ok(())
+    }
+
+    /// Applies every patch in `patches`, or none of them if one names an
+    /// unknown operation without a query ID.
+    pub fn apply(&self, patches: HashMap<String, OperationPatch>) -> Result<()> {
+        let known = self.names();
+        if let Some((name, _)) = patches.iter().find(|(name, patch)| patch.query_id.is_none() && !known.contains(*name))
+        {
+            return Err(XploreError::Config(format!("Unknown operation {} needs a query_id", name)));
+        }
+
+        for (name, patch) in patches {
+            self.patch(&name, patch)?;
+        }
+        Ok(())
+    }
+
+    /// Applies patches from a JSON object keyed by operation name.
+    pub fn load_json(&self, json: &str) -> Result<()> {
+        let patches = serde_json::from_str(json).map_err(|e| XploreError::Config(e.to_string()))?;
+        self.apply(patches)
+    }
+
+    /// Applies patches from a TOML document with one table per operation.
+    pub fn load_toml(&self, toml: &str) -> Result<()> {
+        let patches = toml::from_str(toml).map_err(|e| XploreError::Config(e.to_string()))?;
+        self.apply(patches)
+    }
+
+    /// Applies patches from a `.toml` or `.json` file.
+    pub fn load_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => self.load_toml(&contents),
+            _ => self.load_json(&contents),
+        }
+    }
+
+    /// Updates the registered operations from a discovery run.
+    ///
+    /// Only operations already in the registry are touched: their query ID and
+    /// kind are replaced, and feature switches or field toggles they lack are
+    /// added, taking values from `feature_defaults` where known and `false`
+    /// otherwise. Returns the names of the operations that changed.
+    pub fn refresh(&self, discovered: &[DiscoveredOperation], feature_defaults: &HashMap<String, bool>) -> Vec<String> {
+        let mut operations = self.operations.write().unwrap();
+        let mut changed = Vec::new();
+
+        for found in discovered {
+            let Some(operation) = operations.get_mut(&found.operation_name) else {
+                continue;
+            };
+            let before = operation.clone();
+
+            operation.query_id = found.query_id.clone();
+            operation.kind = found.kind;
+            for feature in &found.feature_switches {
+                if !operation.features.contains_key(feature) {
+                    operation.features.insert(feature.clone(), feature_defaults.get(feature).copied().unwrap_or(false));
+                }
+            }
+            if !found.field_toggles.is_empty() {
+                let toggles = operation.field_toggles.get_or_insert_with(BTreeMap::new);
+                for toggle in &found.field_toggles {
+                    toggles.entry(toggle.clone()).or_insert(false);
+                }
+            }
+
+            if *operation != before {
+                changed.push(found.operation_name.clone());
+            }
+        }
+
+        changed.sort();
+        changed
+    }
+
+    /// The whole table as JSON, in the format accepted by [`OperationRegistry::load_json`].
+    pub fn to_json(&self) -> Result<String> {
+        let operations =
+            self.operations.read().unwrap().iter().map(|(k, v)| (k.clone(), v.clone())).collect::<BTreeMap<_, _>>();
+        Ok(serde_json::to_string_pretty(&operations)?)
+    }
+
+    /// Builds an endpoint for the operation called `name`, without variables.
+    pub(crate) fn endpoint(&self, name: &str) -> Result<ApiEndpoint> {
+        let operation =
+            self.get(name).ok_or_else(|| XploreError::Config(format!("Unknown GraphQL operation {}", name)))?;
+
+        let to_value = |flags: BTreeMap<String, bool>| {
+            Value::Object(flags.into_iter().map(|(k, v)| (k, Value::Bool(v))).collect())
+        };
+
+        Ok(ApiEndpoint {
+            path: format!("{}/{}", operation.query_id, name),
+            method: operation.kind.method(),
+            variables: None,
+            features: if operation.features.is_empty() { None } else { Some(to_value(operation.features)) },
+            field_toggles: operation.field_toggles.map(to_value),
+        })
+    }
+}
+
+fn builtin_operations() -> HashMap<String, Operation> {
+    use OperationKind::{Mutation, Query};
+
+    let operations = [
+        (
+            "UserByScreenName",
+            Operation::builtin(
+                "G3KGOASz96M-Qu0nwmGXNg",
+                Query,
+                USER_BY_SCREEN_NAME_FEATURES,
+                Some(&[("withAuxiliaryUserLabels", false)]),
+            ),
+        ),
+        (
+            "SearchTimeline",
+            Operation::builtin(
+                "gkjsKepM6gl_HmFWoWKfgg",
+                Query,
+                SEARCH_FEATURES,
+                Some(&[("withArticleRichContentState", false)]),
+            ),
+        ),
+        ("HomeTimeline", Operation::builtin("HJFjzBgCs16TqxewQOeLNg", Query, HOME_TIMELINE_FEATURES, None)),
+        ("Following", Operation::builtin("iSicc7LrzWGBgDPL0tM_TQ", Query, RELATIONSHIP_FEATURES, None)),
+        ("Followers", Operation::builtin("rRXFSG5vR6drKr5M37YOTw", Query, RELATIONSHIP_FEATURES, None)),
+        (
+            "TweetDetail",
+            Operation::builtin(
+                "xOhkmRac04YFZmOzU9PJHg",
+                Query,
+                TWEET_DETAIL_FEATURES,
+                Some(&[("withArticleRichContentState", false)]),
+            ),
+        ),
+        ("TweetResultByRestId", Operation::builtin("DJS3BdhUhcaEpZ7B7irJDg", Query, TWEET_RESULT_FEATURES, None)),
+        (
+            "UserTweets",
+            Operation::builtin(
+                "V7H0Ap3_Hh2FyS75OCDO3Q",
+                Query,
+                TIMELINE_FEATURES,
+                Some(&[("withArticlePlainText", false)]),
+            ),
+        ),
+        (
+            "UserTweetsAndReplies",
+            Operation::builtin(
+                "E4wA5vo2sjVyvpliUffSCw",
+                Query,
+                TIMELINE_FEATURES,
+                Some(&[("withArticlePlainText", false)]),
+            ),
+        ),
+        ("ListLatestTweetsTimeline", Operation::builtin("LFKj1wqHNTsEJ4Oq7TzaNA", Query, DEFAULT_FEATURES, None)),
+        ("Likes", Operation::builtin("YlkSUg4Czo2Zx7yRqpwDow", Query, DEFAULT_FEATURES, None)),
+        ("CreateTweet", Operation::builtin("a1p9RWpkYKBjWv_I3WzS-A", Mutation, CREATE_TWEET_FEATURES, Some(&[]))),
+        ("CreateNoteTweet", Operation::builtin("YNXM2DGuE2Sff6a2JD3Ztw", Mutation, CREATE_NOTE_TWEET_FEATURES, None)),
+        ("FavoriteTweet", Operation::builtin("lI07N6Otwv1PhnEgXILM7A", Mutation, &[], None)),
+        ("CreateRetweet", Operation::builtin("ojPdsZsimiJrUGLR1sjUtA", Mutation, &[], None)),
+        ("DeleteTweet", Operation::builtin("VaenaVgh5q5ih7kvyVjgtg", Mutation, &[], None)),
+        ("UnfavoriteTweet", Operation::builtin("ZYKSe-w7KEslx3JhSIk5LA", Mutation, &[], None)),
+        ("DeleteRetweet", Operation::builtin("iQtK4dl5hBmXewYZuEOKVw", Mutation, &[], None)),
+        ("CreateBookmark", Operation::builtin("aoDbu3RHznuiSkQ9aNM67Q", Mutation, &[], None)),
+        ("DeleteBookmark", Operation::builtin("Wlmlj2-xzyS1GN3a6cj-mQ", Mutation, &[], None)),
+    ];
+
+    operations.into_iter().map(|(name, operation)| (name.to_string(), operation)).collect()
+}
+
+/// Features sent with UserByScreenName.
+const USER_BY_SCREEN_NAME_FEATURES: &[(&str, bool)] = &[
+    ("hidden_profile_likes_enabled", false),
+    ("hidden_profile_subscriptions_enabled", false),
+    ("responsive_web_graphql_exclude_directive_enabled", true),
+    ("verified_phone_label_enabled", false),
+    ("subscriptions_verification_info_is_identity_verified_enabled", false),
+    ("subscriptions_verification_info_verified_since_enabled", true),
+    ("highlights_tweets_tab_ui_enabled", true),
+    ("creator_subscriptions_tweet_preview_api_enabled", true),
+    ("responsive_web_graphql_skip_user_profile_image_extensions_enabled", false),
+    ("responsive_web_graphql_timeline_navigation_enabled", true),
+];
+
+/// Features sent with SearchTimeline.
+const SEARCH_FEATURES: &[(&str, bool)] = &[
+    ("longform_notetweets_inline_media_enabled", true),
+    ("responsive_web_enhance_cards_enabled", false),
+    ("responsive_web_media_download_video_enabled", false),
+    ("responsive_web_twitter_article_tweet_consumption_enabled", false),
+    ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled", true),
+    ("interactive_text_enabled", false),
+    ("responsive_web_text_conversations_enabled", false),
+    ("vibe_api_enabled", false),
+    ("rweb_lists_timeline_redesign_enabled", true),
+    ("responsive_web_graphql_exclude_directive_enabled", true),
+    ("verified_phone_label_enabled", false),
+    ("creator_subscriptions_tweet_preview_api_enabled", true),
+    ("responsive_web_graphql_timeline_navigation_enabled", true),
+    ("responsive_web_graphql_skip_user_profile_image_extensions_enabled", false),
+    ("tweetypie_unmention_optimization_enabled", true),
+    ("responsive_web_edit_tweet_api_enabled", true),
+    ("graphql_is_translatable_rweb_tweet_is_translatable_enabled", true),
+    ("view_counts_everywhere_api_enabled", true),
+    ("longform_notetweets_consumption_enabled", true),
+    ("tweet_awards_web_tipping_enabled", false),
+    ("freedom_of_speech_not_reach_fetch_enabled", true),
+    ("standardized_nudges_misinfo", true),
+    ("longform_notetweets_rich_text_read_enabled", true),
+    ("subscriptions_verification_info_enabled", true),
+    ("subscriptions_verification_info_reason_enabled", true),
+    ("subscriptions_verification_info_verified_since_enabled", true),
+    ("super_follow_badge_privacy_enabled", false),
+    ("super_follow_exclusive_tweet_notifications_enabled", false),
+    ("super_follow_tweet_api_enabled", false),
+    ("super_follow_user_api_enabled", false),
+    ("android_graphql_skip_api_media_color_palette", false),
+    ("creator_subscriptions_subscription_count_enabled", false),
+    ("blue_business_profile_image_shape_enabled", false),
+    ("unified_cards_ad_metadata_container_dynamic_card_content_query_enabled", false),
+];
+
+/// Features sent with HomeTimeline: the user timeline ones without `tweetypie_unmention_optimization_enabled`.
+const HOME_TIMELINE_FEATURES: &[(&str, bool)] = &[
+    ("rweb_tipjar_consumption_enabled", true),
+    ("responsive_web_graphql_exclude_directive_enabled", true),
+    ("verified_phone_label_enabled", false),
+    ("creator_subscriptions_tweet_preview_api_enabled", true),
+    ("responsive_web_graphql_timeline_navigation_enabled", true),
+    ("responsive_web_graphql_skip_user_profile_image_extensions_enabled", false),
+    ("communities_web_enable_tweet_community_results_fetch", true),
+    ("c9s_tweet_anatomy_moderator_badge_enabled", true),
+    ("articles_preview_enabled", true),
+    ("responsive_web_edit_tweet_api_enabled", true),
+    ("graphql_is_translatable_rweb_tweet_is_translatable_enabled", true),
+    ("view_counts_everywhere_api_enabled", true),
+    ("longform_notetweets_consumption_enabled", true),
+    ("responsive_web_twitter_article_tweet_consumption_enabled", true),
+    ("tweet_awards_web_tipping_enabled", false),
+    ("creator_subscriptions_quote_tweet_preview_enabled", false),
+    ("freedom_of_speech_not_reach_fetch_enabled", true),
+    ("standardized_nudges_misinfo", true),
+    ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled", true),
+    ("rweb_video_timestamps_enabled", true),
+    ("longform_notetweets_rich_text_read_enabled", true),
+    ("longform_notetweets_inline_media_enabled", true),
+    ("responsive_web_enhance_cards_enabled", false),
+];
+
+/// Features sent with UserTweets and UserTweetsAndReplies.
+const TIMELINE_FEATURES: &[(&str, bool)] = &[
+    ("rweb_tipjar_consumption_enabled", true),
+    ("responsive_web_graphql_exclude_directive_enabled", true),
+    ("verified_phone_label_enabled", false),
+    ("creator_subscriptions_tweet_preview_api_enabled", true),
+    ("responsive_web_graphql_timeline_navigation_enabled", true),
+    ("responsive_web_graphql_skip_user_profile_image_extensions_enabled", false),
+    ("communities_web_enable_tweet_community_results_fetch", true),
+    ("c9s_tweet_anatomy_moderator_badge_enabled", true),
+    ("articles_preview_enabled", true),
+    ("tweetypie_unmention_optimization_enabled", true),
+    ("responsive_web_edit_tweet_api_enabled", true),
+    ("graphql_is_translatable_rweb_tweet_is_translatable_enabled", true),
+    ("view_counts_everywhere_api_enabled", true),
+    ("longform_notetweets_consumption_enabled", true),
+    ("responsive_web_twitter_article_tweet_consumption_enabled", true),
+    ("tweet_awards_web_tipping_enabled", false),
+    ("creator_subscriptions_quote_tweet_preview_enabled", false),
+    ("freedom_of_speech_not_reach_fetch_enabled", true),
+    ("standardized_nudges_misinfo", true),
+    ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled", true),
+    ("rweb_video_timestamps_enabled", true),
+    ("longform_notetweets_rich_text_read_enabled", true),
+    ("longform_notetweets_inline_media_enabled", true),
+    ("responsive_web_enhance_cards_enabled", false),
+];
+
+/// Features sent with Following and Followers.
+const RELATIONSHIP_FEATURES: &[(&str, bool)] = &[
+    ("responsive_web_twitter_article_tweet_consumption_enabled", false),
+    ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled", true),
+    ("longform_notetweets_inline_media_enabled", true),
+    ("responsive_web_media_download_video_enabled", false),
+    ("rweb_lists_timeline_redesign_enabled", true),
+    ("responsive_web_graphql_exclude_directive_enabled", true),
+    ("verified_phone_label_enabled", false),
+    ("creator_subscriptions_tweet_preview_api_enabled", true),
+    ("responsive_web_graphql_timeline_navigation_enabled", true),
+    ("responsive_web_graphql_skip_user_profile_image_extensions_enabled", false),
+    ("tweetypie_unmention_optimization_enabled", true),
+    ("responsive_web_edit_tweet_api_enabled", true),
+    ("graphql_is_translatable_rweb_tweet_is_translatable_enabled", true),
+    ("view_counts_everywhere_api_enabled", true),
+    ("longform_notetweets_consumption_enabled", true),
+    ("tweet_awards_web_tipping_enabled", false),
+    ("freedom_of_speech_not_reach_fetch_enabled", true),
+    ("standardized_nudges_misinfo", true),
+    ("longform_notetweets_rich_text_read_enabled", true),
+    ("responsive_web_enhance_cards_enabled", false),
+    ("subscriptions_verification_info_enabled", true),
+    ("subscriptions_verification_info_reason_enabled", true),
+    ("subscriptions_verification_info_verified_since_enabled", true),
+    ("super_follow_badge_privacy_enabled", false),
+    ("super_follow_exclusive_tweet_notifications_enabled", false),
+    ("super_follow_tweet_api_enabled", false),
+    ("super_follow_user_api_enabled", false),
+    ("android_graphql_skip_api_media_color_palette", false),
+    ("creator_subscriptions_subscription_count_enabled", false),
+    ("blue_business_profile_image_shape_enabled", false),
+    ("unified_cards_ad_metadata_container_dynamic_card_content_query_enabled", false),
+];
+
+/// Features sent with TweetDetail.
+const TWEET_DETAIL_FEATURES: &[(&str, bool)] = &[
+    ("responsive_web_graphql_exclude_directive_enabled", true),
+    ("verified_phone_label_enabled", false),
+    ("creator_subscriptions_tweet_preview_api_enabled", true),
+    ("responsive_web_graphql_timeline_navigation_enabled", true),
+    ("responsive_web_graphql_skip_user_profile_image_extensions_enabled", false),
+    ("tweetypie_unmention_optimization_enabled", true),
+    ("responsive_web_edit_tweet_api_enabled", true),
+    ("graphql_is_translatable_rweb_tweet_is_translatable_enabled", true),
+    ("view_counts_everywhere_api_enabled", true),
+    ("longform_notetweets_consumption_enabled", true),
+    ("tweet_awards_web_tipping_enabled", false),
+    ("freedom_of_speech_not_reach_fetch_enabled", true),
+    ("standardized_nudges_misinfo", true),
+    ("responsive_web_twitter_article_tweet_consumption_enabled", false),
+    ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled", true),
+    ("longform_notetweets_rich_text_read_enabled", true),
+    ("longform_notetweets_inline_media_enabled", true),
+    ("responsive_web_media_download_video_enabled", false),
+    ("responsive_web_enhance_cards_enabled", false),
+];
+
+/// Features sent with TweetResultByRestId.
+const TWEET_RESULT_FEATURES: &[(&str, bool)] = &[
+    ("creator_subscriptions_tweet_preview_api_enabled", true),
+    ("tweetypie_unmention_optimization_enabled", true),
+    ("responsive_web_edit_tweet_api_enabled", true),
+    ("graphql_is_translatable_rweb_tweet_is_translatable_enabled", true),
+    ("view_counts_everywhere_api_enabled", true),
+    ("longform_notetweets_consumption_enabled", true),
+    ("responsive_web_twitter_article_tweet_consumption_enabled", false),
+    ("tweet_awards_web_tipping_enabled", false),
+    ("freedom_of_speech_not_reach_fetch_enabled", true),
+    ("standardized_nudges_misinfo", true),
+];
+
+/// Features sent with ListLatestTweetsTimeline and Likes.
+const DEFAULT_FEATURES: &[(&str, bool)] = &[
+    ("interactive_text_enabled", true),
+    ("longform_notetweets_inline_media_enabled", false),
+    ("responsive_web_text_conversations_enabled", false),
+    ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled", false),
+    ("vibe_api_enabled", false),
+    ("rweb_lists_timeline_redesign_enabled", true),
+    ("responsive_web_graphql_exclude_directive_enabled", true),
+    ("verified_phone_label_enabled", false),
+    ("creator_subscriptions_tweet_preview_api_enabled", true),
+    ("responsive_web_graphql_timeline_navigation_enabled", true),
+    ("responsive_web_graphql_skip_user_profile_image_extensions_enabled", false),
+    ("tweetypie_unmention_optimization_enabled", true),
+    ("responsive_web_edit_tweet_api_enabled", true),
+    ("graphql_is_translatable_rweb_tweet_is_translatable_enabled", true),
+    ("view_counts_everywhere_api_enabled", true),
+    ("longform_notetweets_consumption_enabled", true),
+    ("tweet_awards_web_tipping_enabled", false),
+    ("freedom_of_speech_not_reach_fetch_enabled", true),
+    ("standardized_nudges_misinfo", true),
+    ("longform_notetweets_rich_text_read_enabled", true),
+    ("responsive_web_enhance_cards_enabled", false),
+    ("subscriptions_verification_info_enabled", true),
+    ("subscriptions_verification_info_reason_enabled", true),
+    ("subscriptions_verification_info_verified_since_enabled", true),
+    ("super_follow_badge_privacy_enabled", false),
+    ("super_follow_exclusive_tweet_notifications_enabled", false),
+    ("super_follow_tweet_api_enabled", false),
+    ("super_follow_user_api_enabled", false),
+    ("android_graphql_skip_api_media_color_palette", false),
+    ("creator_subscriptions_subscription_count_enabled", false),
+    ("blue_business_profile_image_shape_enabled", false),
+    ("unified_cards_ad_metadata_container_dynamic_card_content_query_enabled", false),
+    ("rweb_video_timestamps_enabled", true),
+    ("c9s_tweet_anatomy_moderator_badge_enabled", true),
+    ("responsive_web_twitter_article_tweet_consumption_enabled", false),
+    ("creator_subscriptions_quote_tweet_preview_enabled", false),
+    ("profile_label_improvements_pcf_label_in_post_enabled", false),
+    ("rweb_tipjar_consumption_enabled", true),
+    ("articles_preview_enabled", true),
+];
+
+/// Features sent with CreateTweet, quote tweets included.
+///
+/// Quote tweets used to have their own copy with `rweb_video_timestamps_enabled` and
+/// `c9s_tweet_anatomy_moderator_badge_enabled` on; they now send these as they are here.
+const CREATE_TWEET_FEATURES: &[(&str, bool)] = &[
+    ("interactive_text_enabled", true),
+    ("longform_notetweets_inline_media_enabled", false),
+    ("responsive_web_text_conversations_enabled", false),
+    ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled", false),
+    ("vibe_api_enabled", false),
+    ("rweb_lists_timeline_redesign_enabled", true),
+    ("responsive_web_graphql_exclude_directive_enabled", true),
+    ("verified_phone_label_enabled", false),
+    ("creator_subscriptions_tweet_preview_api_enabled", true),
+    ("responsive_web_graphql_timeline_navigation_enabled", true),
+    ("responsive_web_graphql_skip_user_profile_image_extensions_enabled", false),
+    ("tweetypie_unmention_optimization_enabled", true),
+    ("responsive_web_edit_tweet_api_enabled", true),
+    ("graphql_is_translatable_rweb_tweet_is_translatable_enabled", true),
+    ("view_counts_everywhere_api_enabled", true),
+    ("longform_notetweets_consumption_enabled", true),
+    ("tweet_awards_web_tipping_enabled", false),
+    ("freedom_of_speech_not_reach_fetch_enabled", true),
+    ("standardized_nudges_misinfo", true),
+    ("longform_notetweets_rich_text_read_enabled", true),
+    ("responsive_web_enhance_cards_enabled", false),
+    ("subscriptions_verification_info_enabled", true),
+    ("subscriptions_verification_info_reason_enabled", true),
+    ("subscriptions_verification_info_verified_since_enabled", true),
+    ("super_follow_badge_privacy_enabled", false),
+    ("super_follow_exclusive_tweet_notifications_enabled", false),
+    ("super_follow_tweet_api_enabled", false),
+    ("super_follow_user_api_enabled", false),
+    ("android_graphql_skip_api_media_color_palette", false),
+    ("creator_subscriptions_subscription_count_enabled", false),
+    ("blue_business_profile_image_shape_enabled", false),
+    ("unified_cards_ad_metadata_container_dynamic_card_content_query_enabled", false),
+    ("rweb_video_timestamps_enabled", false),
+    ("c9s_tweet_anatomy_moderator_badge_enabled", false),
+    ("responsive_web_twitter_article_tweet_consumption_enabled", false),
+];
+
+/// Features sent with CreateNoteTweet.
+const CREATE_NOTE_TWEET_FEATURES: &[(&str, bool)] = &[
+    ("premium_content_api_read_enabled", false),
+    ("communities_web_enable_tweet_community_results_fetch", true),
+    ("c9s_tweet_anatomy_moderator_badge_enabled", true),
+    ("responsive_web_grok_analyze_button_fetch_trends_enabled", true),
+    ("responsive_web_edit_tweet_api_enabled", true),
+    ("graphql_is_translatable_rweb_tweet_is_translatable_enabled", true),
+    ("view_counts_everywhere_api_enabled", true),
+    ("longform_notetweets_consumption_enabled", true),
+    ("responsive_web_twitter_article_tweet_consumption_enabled", true),
+    ("tweet_awards_web_tipping_enabled", false),
+    ("longform_notetweets_rich_text_read_enabled", true),
+    ("longform_notetweets_inline_media_enabled", true),
+    ("responsive_web_graphql_exclude_directive_enabled", true),
+    ("verified_phone_label_enabled", false),
+    ("freedom_of_speech_not_reach_fetch_enabled", true),
+    ("standardized_nudges_misinfo", true),
+    ("tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled", true),
+    ("responsive_web_graphql_timeline_navigation_enabled", true),
+    ("responsive_web_enhance_cards_enabled", false),
+];
@@ -0,0 +1,239 @@
+//! Automatic pagination of cursor-based timelines.
+//!
+//! Every function here returns a stream that requests pages lazily: nothing is sent until the
+//! stream is polled, and dropping it stops the harvest. [`paginate`] is public so that
+//! timelines this crate has no stream for can be paged the same way.
+
+use {
+    crate::{
+        api,
+        auth::UserAuth,
+        endpoints::Endpoints,
+        profile::{get_user_id, Profile},
+        rate_limit::{self, RateLimitEvent, RateLimitInfo, RateLimitStrategy},
+        relationship::{self, RelationshipTimeline},
+        search::{self, SearchMode, SearchTimeline},
+        timeline_v2::{parse_list_timeline_tweets, parse_timeline_tweets_v2, ListTimeline, TimelineV2},
+        transport::HttpResponse,
+        tweets::Tweet,
+        Result, XploreError,
+    },
+    futures::{stream, Stream, StreamExt, TryStreamExt},
+    reqwest::{header::HeaderMap, StatusCode},
+    std::{collections::HashSet, future::Future, sync::Arc},
+};
+
+const SEARCH_PAGE_SIZE: i32 = 50;
+const RELATIONSHIP_PAGE_SIZE: i32 = 50;
+const USER_TIMELINE_PAGE_SIZE: i32 = 40;
+const LIST_PAGE_SIZE: i32 = 100;
+
+/// One page of a cursor-based timeline.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The cursor of the following page, if X returned one.
+    pub next: Option<String>,
+    /// Headers of the response, used to honour the rate-limit window before the next page.
+    pub headers: HeaderMap,
+}
+
+struct Pager<F> {
+    strategy: Arc<dyn RateLimitStrategy>,
+    operation: &'static str,
+    fetch: F,
+    cursor: Option<String>,
+    requested: HashSet<String>,
+    previous_headers: Option<HeaderMap>,
+    done: bool,
+}
+
+/// Turns a page-fetching function into a stream of items.
+///
+/// `fetch` is called with `None` for the first page and with the `next` cursor of the previous
+/// page after that. The stream ends after a page without a `next` cursor, after a page whose
+/// `next` cursor has already been requested, on an empty page, once `limit` items have been
+/// yielded, or after the first error. Before each follow-up request the rate-limit headers of the
+/// previous page are checked; if the window is exhausted `strategy` decides whether to wait or to
+/// fail.
+pub fn paginate<T, F, Fut>(
+    strategy: Arc<dyn RateLimitStrategy>,
+    operation: &'static str,
+    limit: Option<usize>,
+    fetch: F,
+) -> impl Stream<Item = Result<T>>
+where
+    F: Fn(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Page<T>>>,
+{
+    let pager = Pager {
+        strategy,
+        operation,
+        fetch,
+        cursor: None,
+        requested: HashSet::new(),
+        previous_headers: None,
+        done: false,
+    };
+
+    stream::try_unfold(pager, |mut pager| async move {
+        if pager.done {
+            return Ok::<_, XploreError>(None);
+        }
+
+        if let Some(headers) = pager.previous_headers.take() {
+            wait_for_window(pager.strategy.as_ref(), pager.operation, headers).await?;
+        }
+
+        let page = (pager.fetch)(pager.cursor.take()).await?;
+        if page.items.is_empty() {
+            return Ok(None);
+        }
+
+        match page.next {
+            Some(next) if pager.requested.insert(next.clone()) => {
+                pager.cursor = Some(next);
+                pager.previous_headers = Some(page.headers);
+            }
+            _ => pager.done = true,
+        }
+
+        Ok(Some((page.items, pager)))
+    })
+    .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+    .try_flatten()
+    .take(limit.unwrap_or(usize::MAX))
+}
+
+/// Hands the previous page to the rate-limit strategy if it used up the current window.
+async fn wait_for_window(strategy: &dyn RateLimitStrategy, operation: &str, headers: HeaderMap) -> Result<()> {
+    let exhausted = RateLimitInfo::from_headers(&headers).is_some_and(|info| info.remaining == Some(0));
+    if !exhausted {
+        return Ok(());
+    }
+
+    // The next request would be answered with a 429, so present the strategy with one.
+    let event = RateLimitEvent {
+        fetch_parameters: operation.to_string(),
+        response: HttpResponse { status: StatusCode::TOO_MANY_REQUESTS, headers, body: String::new() },
+    };
+    rate_limit::on_rate_limit(strategy, event).await
+}
+
+pub(crate) fn search_tweets(
+    auth: UserAuth,
+    query: &str,
+    search_mode: SearchMode,
+    limit: Option<usize>,
+) -> impl Stream<Item = Result<Tweet>> {
+    let query = query.to_string();
+
+    paginate(auth.rate_limit_strategy.clone(), "SearchTimeline", limit, move |cursor| {
+        let auth = auth.clone();
+        let query = query.clone();
+        async move {
+            let endpoint =
+                search::search_endpoint(&auth.operations, &query, SEARCH_PAGE_SIZE, search_mode, cursor.as_deref())?;
+            let (timeline, headers) = api::request_endpoint::<SearchTimeline>(&auth, &endpoint).await?;
+
+            let response = search::parse_search_timeline_tweets(&timeline, auth.urls.origin());
+            Ok(Page { items: response.tweets, next: response.next, headers })
+        }
+    })
+}
+
+pub(crate) fn search_profiles(
+    auth: UserAuth,
+    query: &str,
+    limit: Option<usize>,
+) -> impl Stream<Item = Result<Profile>> {
+    let query = query.to_string();
+
+    paginate(auth.rate_limit_strategy.clone(), "SearchTimeline", limit, move |cursor| {
+        let auth = auth.clone();
+        let query = query.clone();
+        async move {
+            let endpoint = search::search_endpoint(
+                &auth.operations,
+                &query,
+                SEARCH_PAGE_SIZE,
+                SearchMode::Users,
+                cursor.as_deref(),
+            )?;
+            let (timeline, headers) = api::request_endpoint::<SearchTimeline>(&auth, &endpoint).await?;
+
+            let response = search::parse_search_timeline_users(&timeline);
+            Ok(Page { items: response.profiles, next: response.next, headers })
+        }
+    })
+}
+
+/// Streams the accounts of a `Following` or `Followers` timeline.
+pub(crate) fn relationships(
+    auth: UserAuth,
+    operation: &'static str,
+    user_id: &str,
+    limit: Option<usize>,
+) -> impl Stream<Item = Result<Profile>> {
+    let user_id = user_id.to_string();
+
+    paginate(auth.rate_limit_strategy.clone(), operation, limit, move |cursor| {
+        let auth = auth.clone();
+        let user_id = user_id.clone();
+        async move {
+            let endpoint = relationship::relationship_endpoint(
+                &auth.operations,
+                operation,
+                &user_id,
+                RELATIONSHIP_PAGE_SIZE,
+                cursor.as_deref(),
+            )?;
+            let (timeline, headers) = api::request_endpoint::<RelationshipTimeline>(&auth, &endpoint).await?;
+
+            let response = relationship::parse_relationship_timeline(&timeline);
+            Ok(Page { items: response.profiles, next: response.next, headers })
+        }
+    })
+}
+
+pub(crate) fn tweets_and_replies(
+    auth: UserAuth,
+    username: &str,
+    limit: Option<usize>,
+) -> impl Stream<Item = Result<Tweet>> {
+    let username = username.to_string();
+
+    paginate(auth.rate_limit_strategy.clone(), "UserTweetsAndReplies", limit, move |cursor| {
+        let auth = auth.clone();
+        let username = username.clone();
+        async move {
+            let user_id = get_user_id(&auth, &username).await?;
+            let endpoint = Endpoints::user_tweets_and_replies(
+                &auth.operations,
+                &user_id,
+                USER_TIMELINE_PAGE_SIZE,
+                cursor.as_deref(),
+            )?;
+            let (timeline, headers) = api::request_endpoint::<TimelineV2>(&auth, &endpoint).await?;
+
+            let response = parse_timeline_tweets_v2(&timeline, auth.urls.origin());
+            Ok(Page { items: response.tweets, next: response.next, headers })
+        }
+    })
+}
+
+pub(crate) fn list_tweets(auth: UserAuth, list_id: &str, limit: Option<usize>) -> impl Stream<Item = Result<Tweet>> {
+    let list_id = list_id.to_string();
+
+    paginate(auth.rate_limit_strategy.clone(), "ListLatestTweetsTimeline", limit, move |cursor| {
+        let auth = auth.clone();
+        let list_id = list_id.clone();
+        async move {
+            let endpoint =
+                Endpoints::list_latest_tweets(&auth.operations, &list_id, LIST_PAGE_SIZE, cursor.as_deref())?;
+            let (timeline, headers) = api::request_endpoint::<ListTimeline>(&auth, &endpoint).await?;
+
+            let response = parse_list_timeline_tweets(&timeline, auth.urls.origin());
+            Ok(Page { items: response.tweets, next: response.next, headers })
+        }
+    })
+}
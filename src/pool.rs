@@ -0,0 +1,292 @@
+//! Spreading requests over several accounts.
+//!
+//! An [`XplorePool`] holds one logged-in [`Xplore`] client per account and hands each request to
+//! an account that is currently usable. Accounts whose rate-limit window is used up cool down
+//! until `x-rate-limit-reset`; accounts whose session X rejects are quarantined until
+//! [`XplorePool::release`] is called, typically after logging them in again.
+//!
+//! Inside [`XplorePool::run`] a 429 always reaches the pool, whatever `RateLimitStrategy` the
+//! client was created with, so the request moves to another account instead of waiting out the
+//! window:
+//!
+//! ```no_run
+//! # use xplore::{pool::XplorePool, Xplore};
+//! # async fn run(cookies: Vec<(String, String)>) -> xplore::Result<()> {
+//! let mut pool = XplorePool::new();
+//! for (name, cookie) in cookies {
+//!     let xplore = Xplore::new(None).await?;
+//!     xplore.set_cookie(&cookie).await?;
+//!     pool.add(name, xplore);
+//! }
+//!
+//! let profile = pool.run(|xplore| async move { xplore.get_profile("jack").await }).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use {
+    crate::{rate_limit, ApiErrorDetails, Result, Xplore, XploreError},
+    chrono::{DateTime, Duration, Utc},
+    serde::{Deserialize, Serialize},
+    std::{
+        future::Future,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    },
+};
+
+/// How long an account cools down when X does not say when its window resets.
+const DEFAULT_COOLDOWN: Duration = Duration::minutes(15);
+
+/// Whether an account can currently take requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AccountHealth {
+    Healthy,
+    /// The rate-limit window is used up until `until`.
+    CoolingDown {
+        until: DateTime<Utc>,
+    },
+    /// X rejected the session; the account stays out of rotation until released.
+    Quarantined {
+        reason: String,
+    },
+}
+
+/// Counters for one account of the pool.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AccountStats {
+    /// Requests dispatched to the account.
+    pub requests: u64,
+    pub successes: u64,
+    /// Failed requests, including rate limits and authentication errors.
+    pub failures: u64,
+    /// Times the account was put into cooldown.
+    pub rate_limited: u64,
+    /// Times the account was quarantined.
+    pub auth_failures: u64,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// The state of one account, as returned by [`XplorePool::accounts`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountReport {
+    pub name: String,
+    pub health: AccountHealth,
+    pub stats: AccountStats,
+}
+
+struct AccountState {
+    health: AccountHealth,
+    stats: AccountStats,
+}
+
+struct PoolAccount {
+    name: String,
+    xplore: Xplore,
+    state: Mutex<AccountState>,
+}
+
+impl PoolAccount {
+    /// Whether the account can take a request now, lifting an expired cooldown.
+    fn is_available(&self, now: DateTime<Utc>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.health {
+            AccountHealth::Healthy => true,
+            AccountHealth::CoolingDown { until } if until <= now => {
+                state.health = AccountHealth::Healthy;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn cool_down(&self, until: DateTime<Utc>) {
+        tracing::info!(account = %self.name, %until, "Account is cooling down");
+        let mut state = self.state.lock().unwrap();
+        state.health = AccountHealth::CoolingDown { until };
+        state.stats.rate_limited += 1;
+    }
+
+    fn quarantine(&self, error: &XploreError) {
+        tracing::warn!(account = %self.name, %error, "Account quarantined");
+        let mut state = self.state.lock().unwrap();
+        state.health = AccountHealth::Quarantined { reason: error.to_string() };
+        state.stats.auth_failures += 1;
+    }
+}
+
+/// A set of accounts that requests are rotated over.
+///
+/// Accounts are tried round-robin. The pool is not `Clone`; wrap it in an `Arc` to share it
+/// between tasks.
+#[derive(Default)]
+pub struct XplorePool {
+    accounts: Vec<PoolAccount>,
+    next: AtomicUsize,
+}
+
+impl XplorePool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a logged-in client under `name`, which identifies the account in logs and statistics.
+    pub fn add(&mut self, name: impl Into<String>, xplore: Xplore) -> &mut Self {
+        self.accounts.push(PoolAccount {
+            name: name.into(),
+            xplore,
+            state: Mutex::new(AccountState { health: AccountHealth::Healthy, stats: AccountStats::default() }),
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Runs `request` with the client of a usable account.
+    ///
+    /// When the account is rate limited it cools down and the request is retried on the next
+    /// usable account; when X rejects its session (`Unauthorized`, `AccountLocked`,
+    /// `AccountSuspended`, `InvalidCredentials` or `Auth`) it is quarantined and the request
+    /// retried likewise. Any other error is returned as is. A successful request that used up
+    /// the window of any endpoint it called also starts a cooldown, until the latest reset among
+    /// them. Only the responses to this request count, not those of other requests running on
+    /// the same account at the same time.
+    ///
+    /// # Errors
+    /// - `XploreError::RateLimited` with the earliest `reset_at` when every usable account is cooling down
+    /// - `XploreError::Auth` when the pool is empty or every account is quarantined
+    pub async fn run<T, F, Fut>(&self, request: F) -> Result<T>
+    where
+        F: Fn(Xplore) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_error = None;
+
+        for _ in 0..self.accounts.len() {
+            let Some(account) = self.pick() else {
+                break;
+            };
+
+            {
+                let mut state = account.state.lock().unwrap();
+                state.stats.requests += 1;
+                state.stats.last_used = Some(Utc::now());
+            }
+
+            let (result, rate_limits) = rate_limit::pooled(request(account.xplore.clone())).await;
+
+            match result {
+                Ok(value) => {
+                    account.state.lock().unwrap().stats.successes += 1;
+                    let exhausted = rate_limits.values().filter(|info| info.remaining == Some(0));
+                    if let Some(until) =
+                        exhausted.map(|info| info.reset_at.unwrap_or_else(|| Utc::now() + DEFAULT_COOLDOWN)).max()
+                    {
+                        account.cool_down(until);
+                    }
+                    return Ok(value);
+                }
+                Err(error) => {
+                    account.state.lock().unwrap().stats.failures += 1;
+                    match &error {
+                        XploreError::RateLimited { reset_at, .. } => {
+                            account.cool_down(reset_at.unwrap_or_else(|| Utc::now() + DEFAULT_COOLDOWN));
+                        }
+                        XploreError::Unauthorized(_)
+                        | XploreError::AccountLocked(_)
+                        | XploreError::AccountSuspended(_)
+                        | XploreError::InvalidCredentials(_)
+                        | XploreError::Auth(_) => account.quarantine(&error),
+                        _ => return Err(error),
+                    }
+                    last_error = Some(error);
+                }
+            }
+        }
+
+        Err(self.unavailable_error(last_error))
+    }
+
+    /// The client of the account called `name`, e.g. to log it in again.
+    pub fn get(&self, name: &str) -> Option<&Xplore> {
+        self.account(name).map(|account| &account.xplore)
+    }
+
+    /// Puts a quarantined or cooling down account back into rotation.
+    ///
+    /// Returns `false` if there is no account called `name`.
+    pub fn release(&self, name: &str) -> bool {
+        match self.account(name) {
+            Some(account) => {
+                account.state.lock().unwrap().health = AccountHealth::Healthy;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The health and statistics of every account, in the order they were added.
+    pub fn accounts(&self) -> Vec<AccountReport> {
+        let now = Utc::now();
+        self.accounts
+            .iter()
+            .map(|account| {
+                account.is_available(now);
+                let state = account.state.lock().unwrap();
+                AccountReport { name: account.name.clone(), health: state.health.clone(), stats: state.stats.clone() }
+            })
+            .collect()
+    }
+
+    fn account(&self, name: &str) -> Option<&PoolAccount> {
+        self.accounts.iter().find(|account| account.name == name)
+    }
+
+    /// The next usable account in round-robin order.
+    fn pick(&self) -> Option<&PoolAccount> {
+        let now = Utc::now();
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+
+        (0..self.accounts.len())
+            .map(|offset| &self.accounts[(start + offset) % self.accounts.len()])
+            .find(|account| account.is_available(now))
+    }
+
+    /// The error returned when no account could take a request.
+    fn unavailable_error(&self, last_error: Option<XploreError>) -> XploreError {
+        let earliest_reset = self
+            .accounts
+            .iter()
+            .filter_map(|account| match account.state.lock().unwrap().health {
+                AccountHealth::CoolingDown { until } => Some(until),
+                _ => None,
+            })
+            .min();
+
+        match (earliest_reset, last_error) {
+            (Some(reset_at), Some(XploreError::RateLimited { details, .. })) => {
+                XploreError::RateLimited { reset_at: Some(reset_at), details }
+            }
+            (Some(reset_at), _) => XploreError::RateLimited {
+                reset_at: Some(reset_at),
+                details: ApiErrorDetails {
+                    message: "Every account in the pool is cooling down".to_string(),
+                    ..Default::default()
+                },
+            },
+            (None, _) if self.accounts.is_empty() => XploreError::Auth("The pool has no accounts".into()),
+            (None, Some(error)) => {
+                XploreError::Auth(format!("Every account in the pool is quarantined, last: {}", error))
+            }
+            (None, None) => XploreError::Auth("Every account in the pool is quarantined".into()),
+        }
+    }
+}
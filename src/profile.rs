@@ -1,5 +1,5 @@
 use {
-    crate::{api, auth::UserAuth, Result, XploreError},
+    crate::{api, auth::UserAuth, ApiErrorDetails, Result, XploreError},
     chrono::{DateTime, Utc},
     lazy_static::lazy_static,
     reqwest::Method,
@@ -86,18 +86,10 @@ impl From<(&LegacyUserRaw, Option<bool>)> for Profile {
                 .unwrap_or_else(Utc::now),
             profile_image_url: user.profile_image_url_https.as_ref().map(|url| url.replace("_normal", "")),
             profile_banner_url: user.profile_banner_url.clone(),
-            pinned_tweet_id: user.pinned_tweet_ids_str.as_ref().and_then(|ids| Some(ids.clone())),
+            pinned_tweet_id: user.pinned_tweet_ids_str.clone(),
         };
 
-        // Set website URL from entities using functional chaining
-        user.entities
-            .as_ref()
-            .and_then(|entities| entities.url.as_ref())
-            .and_then(|url_entity| url_entity.urls.as_ref())
-            .and_then(|urls| urls.first())
-            .and_then(|first_url| first_url.expanded_url.as_ref())
-            .map(|expanded_url| profile.url = Some(expanded_url.clone()));
-
+        // Set website URL from entities
         if let Some(expanded_url) = user
             .entities
             .as_ref()
@@ -139,6 +131,7 @@ pub struct UserResults {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "__typename")]
+#[allow(clippy::large_enum_variant)]
 pub enum UserResult {
     User(UserData),
     UserUnavailable(UserUnavailable),
@@ -172,7 +165,7 @@ pub struct UserRaw {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserRawData {
-    pub user: UserRawUser,
+    pub user: Option<UserRawUser>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -182,9 +175,13 @@ pub struct UserRawUser {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserRawResult {
+    #[serde(rename = "__typename")]
+    pub typename: Option<String>,
     pub rest_id: Option<String>,
     pub is_blue_verified: Option<bool>,
-    pub legacy: LegacyUserRaw,
+    pub legacy: Option<LegacyUserRaw>,
+    /// Set when `__typename` is `UserUnavailable`, e.g. `"Suspended"`.
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -193,68 +190,92 @@ pub struct TwitterApiErrorRaw {
     pub code: i32,
 }
 
-pub async fn get_profile(auth: &mut UserAuth, screen_name: &str) -> Result<Profile> {
-    let body = json!({
-        "variables": json!({
-            "screen_name": screen_name,
-            "withSafetyModeUserFields": true
-        }),
-        "features": json!({
-            "hidden_profile_likes_enabled": false,
-            "hidden_profile_subscriptions_enabled": false,
-            "responsive_web_graphql_exclude_directive_enabled": true,
-            "verified_phone_label_enabled": false,
-            "subscriptions_verification_info_is_identity_verified_enabled": false,
-            "subscriptions_verification_info_verified_since_enabled": true,
-            "highlights_tweets_tab_ui_enabled": true,
-            "creator_subscriptions_tweet_preview_api_enabled": true,
-            "responsive_web_graphql_skip_user_profile_image_extensions_enabled": false,
-            "responsive_web_graphql_timeline_navigation_enabled": true
-        }),
-        "fieldToggles": json!({
-            "withAuxiliaryUserLabels": false
+pub async fn get_profile(auth: &UserAuth, screen_name: &str) -> Result<Profile> {
+    let endpoint = auth.operations.endpoint("UserByScreenName")?.with_variables(json!({
+        "screen_name": screen_name,
+        "withSafetyModeUserFields": true
+    }));
+    let (user_raw, _) = api::request_endpoint::<UserRaw>(auth, &endpoint).await?;
+
+    let not_found = || {
+        XploreError::NotFound(ApiErrorDetails {
+            message: format!("User {} does not exist", screen_name),
+            ..Default::default()
         })
-    });
-    let (user_raw, _) = api::send_request::<UserRaw>(
-        auth,
-        "https://twitter.com/i/api/graphql/G3KGOASz96M-Qu0nwmGXNg/UserByScreenName",
-        Method::GET,
-        Some(body),
-    )
-    .await?;
-
-    if let Some(errors) = user_raw.errors {
-        if !errors.is_empty() {
-            return Err(XploreError::Api(errors[0].message.clone()));
-        }
-    }
+    };
 
-    let user_legacy = &user_raw.data.user.result.legacy;
-    let rest_id = user_raw.data.user.result.rest_id.clone();
-    let is_blue_verified = user_raw.data.user.result.is_blue_verified;
+    let result = user_raw.data.user.ok_or_else(not_found)?.result;
+
+    if let Some(reason) = &result.reason {
+        let details = ApiErrorDetails {
+            message: format!("User {} is unavailable: {}", screen_name, reason),
+            ..Default::default()
+        };
+        return Err(match reason.as_str() {
+            "Suspended" => XploreError::Suspended(details),
+            "Protected" => XploreError::Protected(details),
+            _ => XploreError::NotFound(details),
+        });
+    }
 
-    let mut legacy = user_legacy.clone();
-    legacy.user_id = rest_id;
+    let mut legacy = result.legacy.ok_or_else(not_found)?;
+    legacy.user_id = result.rest_id;
 
     match legacy.screen_name.as_deref() {
-        Some(name) if !name.is_empty() => Ok((&legacy, is_blue_verified).into()),
-        _ => Err(XploreError::Api(format!("Either {} does not exist or is private.", screen_name))),
+        Some(name) if !name.is_empty() => Ok((&legacy, result.is_blue_verified).into()),
+        _ => Err(not_found()),
     }
 }
 
-pub async fn get_user_id(auth: &mut UserAuth, screen_name: &str) -> Result<String> {
-    let cache = ID_CACHE.clone();
+/// Settings of the logged-in account, from `account/settings.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSettings {
+    pub screen_name: String,
+    #[serde(default)]
+    pub protected: bool,
+    /// Interface language, e.g. `en`.
+    pub language: Option<String>,
+    /// Who may send direct messages: `all`, `following` or `verified`.
+    pub allow_dms_from: Option<String>,
+    /// Who may add the account to group conversations: `all` or `following`.
+    pub allow_dm_groups_from: Option<String>,
+    pub discoverable_by_email: Option<bool>,
+    pub discoverable_by_mobile_phone: Option<bool>,
+    pub display_sensitive_media: Option<bool>,
+    pub geo_enabled: Option<bool>,
+}
+
+/// The logged-in account, as returned by [`Xplore::me`](crate::Xplore::me).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrentAccount {
+    pub profile: Profile,
+    pub settings: AccountSettings,
+}
+
+pub async fn get_current_account(auth: &UserAuth) -> Result<CurrentAccount> {
+    if !auth.has_session().await {
+        return Err(XploreError::LoginRequired("verify_credentials".into()));
+    }
+
+    let user = auth.verify_credentials().await?;
+
+    let url = auth.urls.api_url("1.1/account/settings.json");
+    let (settings, _) = api::send_request::<AccountSettings>(auth, &url, Method::GET, None).await?;
+
+    Ok(CurrentAccount { profile: (&user, None).into(), settings })
+}
 
-    let mut cache = cache.lock().await;
-    if let Some(cached_id) = cache.get(screen_name) {
+pub async fn get_user_id(auth: &UserAuth, screen_name: &str) -> Result<String> {
+    if let Some(cached_id) = ID_CACHE.lock().await.get(screen_name) {
         return Ok(cached_id.clone());
     }
 
+    // The cache is not locked during the lookup so that concurrent callers are not serialized.
     let profile = get_profile(auth, screen_name).await?;
 
     let user_id = profile.id;
 
-    cache.insert(screen_name.to_string(), user_id.clone());
+    ID_CACHE.lock().await.insert(screen_name.to_string(), user_id.clone());
 
     Ok(user_id)
 }
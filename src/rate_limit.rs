@@ -1,30 +1,66 @@
 #![allow(dead_code)]
 
 use {
-    crate::{Result, XploreError},
+    crate::{transport::HttpResponse, ApiErrorDetails, Result, XploreError},
     async_trait::async_trait,
+    chrono::{DateTime, TimeZone, Utc},
     log::info,
-    reqwest::Response,
-    std::time::{Duration, SystemTime, UNIX_EPOCH},
+    reqwest::header::HeaderMap,
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::HashMap,
+        future::Future,
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
 };
 
+/// The `x-rate-limit-*` headers of a response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimitInfo {
+    /// Maximum number of requests in the current window (`x-rate-limit-limit`).
+    pub limit: Option<u32>,
+    /// Requests left in the current window (`x-rate-limit-remaining`).
+    pub remaining: Option<u32>,
+    /// When the current window ends (`x-rate-limit-reset`).
+    pub reset_at: Option<DateTime<Utc>>,
+}
+
+impl RateLimitInfo {
+    /// Reads the rate-limit headers, returning `None` if none of them are present.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let number = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).and_then(|v| v.parse::<i64>().ok());
+
+        let info = Self {
+            limit: number("x-rate-limit-limit").and_then(|v| u32::try_from(v).ok()),
+            remaining: number("x-rate-limit-remaining").and_then(|v| u32::try_from(v).ok()),
+            reset_at: number("x-rate-limit-reset").and_then(|v| Utc.timestamp_opt(v, 0).single()),
+        };
+
+        if info.limit.is_none() && info.remaining.is_none() && info.reset_at.is_none() {
+            None
+        } else {
+            Some(info)
+        }
+    }
+}
+
 /// Information about a rate-limiting event. Both the request and response
 /// information are provided.
 pub struct RateLimitEvent {
     /// The complete arguments that were passed to the fetch function.
     pub fetch_parameters: String,
     /// The failing HTTP response.
-    pub response: Response,
+    pub response: HttpResponse,
 }
 
 /// The public interface for all rate-limiting strategies. Library consumers are
-/// welcome to provide their own implementations of this trait in the Scraper
-/// constructor options.
+/// welcome to provide their own implementations of this trait in the
+/// `XploreOptions` passed to `Xplore::new`.
 ///
 /// The `RateLimitEvent` object contains both the request and response
 /// information associated with the event.
 #[async_trait]
-pub trait RateLimitStrategy {
+pub trait RateLimitStrategy: Send + Sync {
     /// Called when the scraper is rate limited.
     ///
     /// # Arguments
@@ -45,18 +81,18 @@ impl RateLimitStrategy for WaitingRateLimitStrategy {
           - x-rate-limit-reset: UNIX timestamp when the current rate limit will be reset.
           - x-rate-limit-remaining: Number of requests remaining in current time period?
         */
-        let x_rate_limit_limit = match event.response.headers().get("x-rate-limit-limit") {
-            Some(header) => header.to_str().map_err(|_e| XploreError::RateLimit)?.to_owned(),
+        let x_rate_limit_limit = match event.response.headers.get("x-rate-limit-limit") {
+            Some(header) => header.to_str().map_err(|e| XploreError::InvalidResponse(e.to_string()))?.to_owned(),
             None => return Err(XploreError::InvalidResponse("Missing x-rate-limit-limit header".into())),
         };
 
-        let x_rate_limit_remaining = match event.response.headers().get("x-rate-limit-remaining") {
-            Some(header) => header.to_str().map_err(|_e| XploreError::RateLimit)?.to_owned(),
+        let x_rate_limit_remaining = match event.response.headers.get("x-rate-limit-remaining") {
+            Some(header) => header.to_str().map_err(|e| XploreError::InvalidResponse(e.to_string()))?.to_owned(),
             None => return Err(XploreError::InvalidResponse("Missing x-rate-limit-remaining header".into())),
         };
 
-        let x_rate_limit_reset = match event.response.headers().get("x-rate-limit-reset") {
-            Some(header) => header.to_str().map_err(|_e| XploreError::RateLimit)?.to_owned(),
+        let x_rate_limit_reset = match event.response.headers.get("x-rate-limit-reset") {
+            Some(header) => header.to_str().map_err(|e| XploreError::InvalidResponse(e.to_string()))?.to_owned(),
             None => return Err(XploreError::InvalidResponse("Missing x-rate-limit-reset header".into())),
         };
 
@@ -69,9 +105,11 @@ impl RateLimitStrategy for WaitingRateLimitStrategy {
             let reset_time = x_rate_limit_reset
                 .parse::<u64>()
                 .map_err(|e| XploreError::InvalidResponse(format!("Failed to parse x-rate-limit-reset: {}", e)))?;
-            let current_time =
-                SystemTime::now().duration_since(UNIX_EPOCH).map_err(|_e| XploreError::RateLimit)?.as_secs();
-            let time_delta_ms = (reset_time - current_time) * 1000;
+            let current_time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|e| XploreError::InvalidResponse(e.to_string()))?
+                .as_secs();
+            let time_delta_ms = reset_time.saturating_sub(current_time) * 1000;
 
             // I have seen this block for 800s (~13 *minutes*)
             tokio::time::sleep(Duration::from_millis(time_delta_ms)).await;
@@ -81,16 +119,56 @@ impl RateLimitStrategy for WaitingRateLimitStrategy {
     }
 }
 
-/// A rate-limiting strategy that throws an `ApiError` when a rate limiting event occurs.
+/// A rate-limiting strategy that returns `XploreError::RateLimited` when a rate limiting event occurs.
 pub struct ErrorRateLimitStrategy;
 
 #[async_trait]
 impl RateLimitStrategy for ErrorRateLimitStrategy {
-    async fn on_rate_limit(&self, _event: RateLimitEvent) -> Result<()> {
-        Err(XploreError::RateLimit)
+    async fn on_rate_limit(&self, event: RateLimitEvent) -> Result<()> {
+        let details = ApiErrorDetails::from_response(&event.response);
+        Err(XploreError::RateLimited { reset_at: details.rate_limit.as_ref().and_then(|r| r.reset_at), details })
+    }
+}
+
+/// The rate-limit headers seen while running one pooled request, by endpoint path.
+#[derive(Default)]
+struct PooledRequest {
+    rate_limits: std::sync::Mutex<HashMap<String, RateLimitInfo>>,
+}
+
+tokio::task_local! {
+    static POOLED_REQUEST: PooledRequest;
+}
+
+/// Runs `future` as a request of an `XplorePool` and returns its output together with the
+/// rate-limit headers of the responses it received, by endpoint path.
+///
+/// Within it, a 429 always fails with `XploreError::RateLimited`, whatever strategy the client
+/// was configured with, so that the pool can move the request to another account. Work spawned
+/// onto other tasks is not covered.
+pub(crate) async fn pooled<F: Future>(future: F) -> (F::Output, HashMap<String, RateLimitInfo>) {
+    POOLED_REQUEST
+        .scope(PooledRequest::default(), async {
+            let output = future.await;
+            let rate_limits = POOLED_REQUEST.with(|request| std::mem::take(&mut *request.rate_limits.lock().unwrap()));
+            (output, rate_limits)
+        })
+        .await
+}
+
+/// Records the rate-limit headers of a response to `url` for the pooled request running it, if any.
+pub(crate) fn record(url: &str, info: &RateLimitInfo) {
+    let _ = POOLED_REQUEST.try_with(|request| {
+        let endpoint = url.split('?').next().unwrap_or(url);
+        request.rate_limits.lock().unwrap().insert(endpoint.to_string(), info.clone());
+    });
+}
 
-        // let status = event.response.status();
-        // let body = event.response.text().await.map_err(|e| XploreError::Network(e.into()))?;
-        // Err(XploreError::Api(format!("Rate limit exceeded. Status: {}, Body: {}", status, body)))
+/// Hands a rate-limit event to `strategy`, or fails right away inside [`pooled`].
+pub(crate) async fn on_rate_limit(strategy: &dyn RateLimitStrategy, event: RateLimitEvent) -> Result<()> {
+    if POOLED_REQUEST.try_with(|_| ()).is_ok() {
+        ErrorRateLimitStrategy.on_rate_limit(event).await
+    } else {
+        strategy.on_rate_limit(event).await
     }
 }
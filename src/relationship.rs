@@ -1,7 +1,9 @@
 use {
-    crate::{api, timeline_v1::QueryProfilesResponse, Profile, Result, Xplore, XploreError},
+    crate::{
+        api, endpoints::ApiEndpoint, operations::OperationRegistry, timeline_v1::QueryProfilesResponse, Profile,
+        Result, Xplore, XploreError,
+    },
     chrono::{DateTime, Utc},
-    reqwest::Method,
     serde::Deserialize,
     serde_json::{json, Value},
 };
@@ -362,6 +364,7 @@ pub struct InnerTimeline {
 
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type")]
+#[allow(clippy::large_enum_variant)]
 pub enum Instruction {
     #[serde(rename = "TimelineAddEntries")]
     AddEntries { entries: Vec<RelationshipTimelineEntry> },
@@ -386,12 +389,12 @@ pub struct RelationshipTimelineWrapper {
     pub timeline: InnerTimeline,
 }
 
-pub async fn get_home_timeline(xplore: &mut Xplore, count: i32, seen_tweet_ids: Vec<String>) -> Result<Vec<Value>> {
+pub async fn get_home_timeline(xplore: &Xplore, count: i32, seen_tweet_ids: Vec<String>) -> Result<Vec<Value>> {
     fetch_home_timeline(xplore, count, seen_tweet_ids).await
 }
 
 pub async fn get_following(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     count: i32,
     cursor: Option<String>,
@@ -401,7 +404,7 @@ pub async fn get_following(
 }
 
 pub async fn get_followers(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     count: i32,
     cursor: Option<String>,
@@ -410,11 +413,11 @@ pub async fn get_followers(
     Ok((response.profiles, response.next))
 }
 
-///! TODO: error handling
-pub async fn follow(xplore: &mut Xplore, username: &str) -> Result<()> {
+// TODO: error handling
+pub async fn follow(xplore: &Xplore, username: &str) -> Result<()> {
     let user_id = xplore.get_user_id(username).await?;
 
-    let url = "https://api.twitter.com/1.1/friendships/create.json";
+    let url = xplore.auth.urls.api_url("1.1/friendships/create.json");
 
     let form = vec![
         ("include_profile_interstitial_type".to_string(), "1".to_string()),
@@ -422,16 +425,16 @@ pub async fn follow(xplore: &mut Xplore, username: &str) -> Result<()> {
         ("user_id".to_string(), user_id),
     ];
 
-    let _ = api::request_form::<Value>(&mut xplore.auth, url, username, form).await?;
+    let _ = api::request_form::<Value>(&xplore.auth, &url, &format!("/{}", username), form).await?;
 
     Ok(())
 }
 
-///! TODO: error handling
-pub async fn unfollow(xplore: &mut Xplore, username: &str) -> Result<()> {
+// TODO: error handling
+pub async fn unfollow(xplore: &Xplore, username: &str) -> Result<()> {
     let user_id = xplore.get_user_id(username).await?;
 
-    let url = "https://api.twitter.com/1.1/friendships/destroy.json";
+    let url = xplore.auth.urls.api_url("1.1/friendships/destroy.json");
 
     let form = vec![
         ("include_profile_interstitial_type".to_string(), "1".to_string()),
@@ -439,14 +442,14 @@ pub async fn unfollow(xplore: &mut Xplore, username: &str) -> Result<()> {
         ("user_id".to_string(), user_id),
     ];
 
-    let (_, _) = api::request_form::<Value>(&mut xplore.auth, url, username, form).await?;
+    let (_, _) = api::request_form::<Value>(&xplore.auth, &url, &format!("/{}", username), form).await?;
 
     Ok(())
 }
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
 pub async fn fetch_profile_following(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     max_profiles: i32,
     cursor: Option<String>,
@@ -456,7 +459,7 @@ pub async fn fetch_profile_following(
 }
 
 pub async fn fetch_profile_followers(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     max_profiles: i32,
     cursor: Option<String>,
@@ -465,7 +468,7 @@ pub async fn fetch_profile_followers(
     Ok(parse_relationship_timeline(&timeline))
 }
 
-fn parse_relationship_timeline(timeline: &RelationshipTimeline) -> QueryProfilesResponse {
+pub(crate) fn parse_relationship_timeline(timeline: &RelationshipTimeline) -> QueryProfilesResponse {
     let mut profiles = Vec::new();
     let mut bottom_cursor = None;
     let mut top_cursor = None;
@@ -532,17 +535,15 @@ fn parse_relationship_timeline(timeline: &RelationshipTimeline) -> QueryProfiles
                                         profiles.push(profile);
                                     }
                                 }
-                            } else if let Some(cursor_type) =
-                                &entry.content.as_ref().and_then(|c| c.cursor_type.as_ref())
-                            {
-                                if cursor_type.to_string() == "Bottom" {
-                                    if let Some(value) = &entry.content.as_ref().and_then(|c| c.value.as_ref()) {
-                                        bottom_cursor = Some(value.to_string());
-                                    }
-                                } else if cursor_type.to_string() == "Top" {
-                                    if let Some(value) = &entry.content.as_ref().and_then(|c| c.value.as_ref()) {
-                                        top_cursor = Some(value.to_string());
-                                    }
+                            }
+                        } else if let Some(cursor_type) = &entry.content.as_ref().and_then(|c| c.cursor_type.as_ref()) {
+                            if *cursor_type == "Bottom" {
+                                if let Some(value) = &entry.content.as_ref().and_then(|c| c.value.as_ref()) {
+                                    bottom_cursor = Some(value.to_string());
+                                }
+                            } else if *cursor_type == "Top" {
+                                if let Some(value) = &entry.content.as_ref().and_then(|c| c.value.as_ref()) {
+                                    top_cursor = Some(value.to_string());
                                 }
                             }
                         }
@@ -555,7 +556,7 @@ fn parse_relationship_timeline(timeline: &RelationshipTimeline) -> QueryProfiles
     QueryProfilesResponse { profiles, next: bottom_cursor, previous: top_cursor }
 }
 
-pub async fn fetch_home_timeline(xplore: &mut Xplore, count: i32, seen_tweet_ids: Vec<String>) -> Result<Vec<Value>> {
+pub async fn fetch_home_timeline(xplore: &Xplore, count: i32, seen_tweet_ids: Vec<String>) -> Result<Vec<Value>> {
     let variables = serde_json::json!({
         "count": count,
         "includePromotedContent": true,
@@ -565,39 +566,9 @@ pub async fn fetch_home_timeline(xplore: &mut Xplore, count: i32, seen_tweet_ids
         "seenTweetIds": seen_tweet_ids,
     });
 
-    let features = serde_json::json!({
-        "rweb_tipjar_consumption_enabled": true,
-        "responsive_web_graphql_exclude_directive_enabled": true,
-        "verified_phone_label_enabled": false,
-        "creator_subscriptions_tweet_preview_api_enabled": true,
-        "responsive_web_graphql_timeline_navigation_enabled": true,
-        "responsive_web_graphql_skip_user_profile_image_extensions_enabled": false,
-        "communities_web_enable_tweet_community_results_fetch": true,
-        "c9s_tweet_anatomy_moderator_badge_enabled": true,
-        "articles_preview_enabled": true,
-        "responsive_web_edit_tweet_api_enabled": true,
-        "graphql_is_translatable_rweb_tweet_is_translatable_enabled": true,
-        "view_counts_everywhere_api_enabled": true,
-        "longform_notetweets_consumption_enabled": true,
-        "responsive_web_twitter_article_tweet_consumption_enabled": true,
-        "tweet_awards_web_tipping_enabled": false,
-        "creator_subscriptions_quote_tweet_preview_enabled": false,
-        "freedom_of_speech_not_reach_fetch_enabled": true,
-        "standardized_nudges_misinfo": true,
-        "tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled": true,
-        "rweb_video_timestamps_enabled": true,
-        "longform_notetweets_rich_text_read_enabled": true,
-        "longform_notetweets_inline_media_enabled": true,
-        "responsive_web_enhance_cards_enabled": false,
-    });
+    let endpoint = xplore.auth.operations.endpoint("HomeTimeline")?.with_variables(variables);
 
-    let url = format!(
-        "https://x.com/i/api/graphql/HJFjzBgCs16TqxewQOeLNg/HomeTimeline?variables={}&features={}",
-        urlencoding::encode(&variables.to_string()),
-        urlencoding::encode(&features.to_string())
-    );
-
-    let (response, _) = api::send_request::<HomeTimelineResponse>(&mut xplore.auth, &url, Method::GET, None).await?;
+    let (response, _) = api::request_endpoint::<HomeTimelineResponse>(&xplore.auth, &endpoint).await?;
 
     let home = response.data.map(|data| data.home.home_timeline.instructions);
 
@@ -625,77 +596,39 @@ pub async fn fetch_home_timeline(xplore: &mut Xplore, count: i32, seen_tweet_ids
 }
 
 pub async fn get_following_timeline(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     max_items: i32,
     cursor: Option<String>,
 ) -> Result<RelationshipTimeline> {
-    let max_items = if max_items > 50 { 50 } else { max_items };
-
-    let mut variables = json!({
-        "userId": user_id,
-        "count": max_items,
-        "includePromotedContent": false,
-    });
-
-    let features = json!({
-        "responsive_web_twitter_article_tweet_consumption_enabled": false,
-        "tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled": true,
-        "longform_notetweets_inline_media_enabled": true,
-        "responsive_web_media_download_video_enabled": false,
-
-        "rweb_lists_timeline_redesign_enabled": true,
-        "responsive_web_graphql_exclude_directive_enabled": true,
-        "verified_phone_label_enabled": false,
-        "creator_subscriptions_tweet_preview_api_enabled": true,
-        "responsive_web_graphql_timeline_navigation_enabled": true,
-        "responsive_web_graphql_skip_user_profile_image_extensions_enabled": false,
-        "tweetypie_unmention_optimization_enabled": true,
-        "responsive_web_edit_tweet_api_enabled": true,
-        "graphql_is_translatable_rweb_tweet_is_translatable_enabled": true,
-        "view_counts_everywhere_api_enabled": true,
-        "longform_notetweets_consumption_enabled": true,
-        "tweet_awards_web_tipping_enabled": false,
-        "freedom_of_speech_not_reach_fetch_enabled": true,
-        "standardized_nudges_misinfo": true,
-        "longform_notetweets_rich_text_read_enabled": true,
-        "responsive_web_enhance_cards_enabled": false,
-        "subscriptions_verification_info_enabled": true,
-        "subscriptions_verification_info_reason_enabled": true,
-        "subscriptions_verification_info_verified_since_enabled": true,
-        "super_follow_badge_privacy_enabled": false,
-        "super_follow_exclusive_tweet_notifications_enabled": false,
-        "super_follow_tweet_api_enabled": false,
-        "super_follow_user_api_enabled": false,
-        "android_graphql_skip_api_media_color_palette": false,
-        "creator_subscriptions_subscription_count_enabled": false,
-        "blue_business_profile_image_shape_enabled": false,
-        "unified_cards_ad_metadata_container_dynamic_card_content_query_enabled": false
-    });
-
-    if let Some(cursor_val) = cursor {
-        if !cursor_val.is_empty() {
-            variables["cursor"] = json!(cursor_val);
-        }
-    }
-
-    let url = format!(
-        "https://x.com/i/api/graphql/iSicc7LrzWGBgDPL0tM_TQ/Following?variables={}&features={}",
-        urlencoding::encode(&variables.to_string()),
-        urlencoding::encode(&features.to_string())
-    );
+    let endpoint = relationship_endpoint(&xplore.auth.operations, "Following", user_id, max_items, cursor.as_deref())?;
 
-    let (data, _) = api::send_request::<RelationshipTimeline>(&mut xplore.auth, &url, Method::GET, None).await?;
+    let (data, _) = api::request_endpoint::<RelationshipTimeline>(&xplore.auth, &endpoint).await?;
 
     Ok(data)
 }
 
 pub async fn get_follower_timeline(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     max_items: i32,
     cursor: Option<String>,
 ) -> Result<RelationshipTimeline> {
+    let endpoint = relationship_endpoint(&xplore.auth.operations, "Followers", user_id, max_items, cursor.as_deref())?;
+
+    let (data, _) = api::request_endpoint::<RelationshipTimeline>(&xplore.auth, &endpoint).await?;
+
+    Ok(data)
+}
+
+/// Builds a `Following` or `Followers` request; `operation` selects which.
+pub(crate) fn relationship_endpoint(
+    operations: &OperationRegistry,
+    operation: &str,
+    user_id: &str,
+    max_items: i32,
+    cursor: Option<&str>,
+) -> Result<ApiEndpoint> {
     let max_items = if max_items > 50 { 50 } else { max_items };
 
     let mut variables = json!({
@@ -704,54 +637,11 @@ pub async fn get_follower_timeline(
         "includePromotedContent": false,
     });
 
-    let features = json!({
-        "responsive_web_twitter_article_tweet_consumption_enabled": false,
-        "tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled": true,
-        "longform_notetweets_inline_media_enabled": true,
-        "responsive_web_media_download_video_enabled": false,
-
-        "rweb_lists_timeline_redesign_enabled": true,
-        "responsive_web_graphql_exclude_directive_enabled": true,
-        "verified_phone_label_enabled": false,
-        "creator_subscriptions_tweet_preview_api_enabled": true,
-        "responsive_web_graphql_timeline_navigation_enabled": true,
-        "responsive_web_graphql_skip_user_profile_image_extensions_enabled": false,
-        "tweetypie_unmention_optimization_enabled": true,
-        "responsive_web_edit_tweet_api_enabled": true,
-        "graphql_is_translatable_rweb_tweet_is_translatable_enabled": true,
-        "view_counts_everywhere_api_enabled": true,
-        "longform_notetweets_consumption_enabled": true,
-        "tweet_awards_web_tipping_enabled": false,
-        "freedom_of_speech_not_reach_fetch_enabled": true,
-        "standardized_nudges_misinfo": true,
-        "longform_notetweets_rich_text_read_enabled": true,
-        "responsive_web_enhance_cards_enabled": false,
-        "subscriptions_verification_info_enabled": true,
-        "subscriptions_verification_info_reason_enabled": true,
-        "subscriptions_verification_info_verified_since_enabled": true,
-        "super_follow_badge_privacy_enabled": false,
-        "super_follow_exclusive_tweet_notifications_enabled": false,
-        "super_follow_tweet_api_enabled": false,
-        "super_follow_user_api_enabled": false,
-        "android_graphql_skip_api_media_color_palette": false,
-        "creator_subscriptions_subscription_count_enabled": false,
-        "blue_business_profile_image_shape_enabled": false,
-        "unified_cards_ad_metadata_container_dynamic_card_content_query_enabled": false
-    });
-
     if let Some(cursor_val) = cursor {
         if !cursor_val.is_empty() {
             variables["cursor"] = json!(cursor_val);
         }
     }
 
-    let url = format!(
-        "https://x.com/i/api/graphql/rRXFSG5vR6drKr5M37YOTw/Followers?variables={}&features={}",
-        urlencoding::encode(&variables.to_string()),
-        urlencoding::encode(&features.to_string())
-    );
-
-    let (data, _) = api::send_request::<RelationshipTimeline>(&mut xplore.auth, &url, Method::GET, None).await?;
-
-    Ok(data)
+    Ok(operations.endpoint(operation)?.with_variables(variables))
 }
@@ -2,17 +2,24 @@ use {
     crate::{
         api,
         auth::UserAuth,
+        endpoints::ApiEndpoint,
+        fs_util,
+        operations::OperationRegistry,
         timeline_v1::{QueryProfilesResponse, QueryTweetsResponse},
         timeline_v2::{parse_legacy_tweet, SearchEntryRaw},
-        Profile, Result,
+        tweets::Tweet,
+        Profile, Result, XploreError,
     },
     lazy_static::lazy_static,
-    reqwest::Method,
-    serde::Deserialize,
+    serde::{Deserialize, Serialize},
     serde_json::json,
+    std::{
+        fs,
+        path::{Path, PathBuf},
+    },
 };
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SearchMode {
     Top,
     Latest,
@@ -22,7 +29,7 @@ pub enum SearchMode {
 }
 
 pub async fn search_tweets(
-    auth: &mut UserAuth,
+    auth: &UserAuth,
     query: &str,
     max_tweets: i32,
     search_mode: SearchMode,
@@ -30,11 +37,11 @@ pub async fn search_tweets(
 ) -> Result<QueryTweetsResponse> {
     let timeline = get_search_timeline(auth, query, max_tweets, search_mode, cursor).await?;
 
-    Ok(parse_search_timeline_tweets(&timeline))
+    Ok(parse_search_timeline_tweets(&timeline, auth.urls.origin()))
 }
 
 pub async fn search_profiles(
-    auth: &mut UserAuth,
+    auth: &UserAuth,
     query: &str,
     max_profiles: i32,
     cursor: Option<String>,
@@ -44,6 +51,117 @@ pub async fn search_profiles(
     Ok(parse_search_timeline_users(&timeline))
 }
 
+/// A search harvest that can be resumed after a restart.
+///
+/// The session remembers the query, the mode and the cursor of the last page fetched. When it
+/// was opened with [`SearchSession::open`], it is written back to its file after every page, so
+/// a new process can pick up where the previous one stopped.
+///
+/// The file is written as soon as a page has been fetched, before the page is returned: a
+/// process that dies while handling a page resumes after that page. To save only once a page
+/// has been handled, keep a session from [`SearchSession::new`] (or one deserialized from the
+/// file, which has no path either) and call [`SearchSession::save`] yourself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSession {
+    pub query: String,
+    pub search_mode: SearchMode,
+    /// Cursor of the next page, `None` before the first page.
+    pub cursor: Option<String>,
+    /// Set once the timeline has no further pages.
+    pub exhausted: bool,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl SearchSession {
+    /// Starts a session that is only kept in memory.
+    pub fn new(query: &str, search_mode: SearchMode) -> Self {
+        Self { query: query.to_string(), search_mode, cursor: None, exhausted: false, path: None }
+    }
+
+    /// Resumes the session saved at `path`, or starts a new one there if the file does not exist.
+    ///
+    /// Returns `XploreError::Config` if the file belongs to a different query or mode.
+    pub fn open(path: impl AsRef<Path>, query: &str, search_mode: SearchMode) -> Result<Self> {
+        let path = path.as_ref();
+
+        let mut session = if path.exists() {
+            let session: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+            if session.query != query || session.search_mode != search_mode {
+                return Err(XploreError::Config(format!(
+                    "{} holds a session for {:?} ({:?}), not {:?} ({:?})",
+                    path.display(),
+                    session.query,
+                    session.search_mode,
+                    query,
+                    search_mode
+                )));
+            }
+            session
+        } else {
+            Self::new(query, search_mode)
+        };
+
+        session.path = Some(path.to_path_buf());
+        Ok(session)
+    }
+
+    /// Writes the session to `path`, replacing the file atomically.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs_util::write_atomic(path.as_ref(), serde_json::to_string_pretty(self)?.as_bytes(), false)
+    }
+
+    /// Records the cursor returned with a page and persists the session if it has a file.
+    fn advance(&mut self, next: Option<String>, page_is_empty: bool) -> Result<()> {
+        match next {
+            Some(next) if !page_is_empty && self.cursor.as_deref() != Some(next.as_str()) => self.cursor = Some(next),
+            _ => self.exhausted = true,
+        }
+
+        match &self.path {
+            Some(path) => self.save(path),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Fetches the next page of tweets of `session`, or nothing if it is exhausted.
+pub async fn next_session_tweets(auth: &UserAuth, session: &mut SearchSession, max_tweets: i32) -> Result<Vec<Tweet>> {
+    if session.search_mode == SearchMode::Users {
+        return Err(XploreError::Config("A tweet search cannot use SearchMode::Users".into()));
+    }
+    if session.exhausted {
+        return Ok(Vec::new());
+    }
+
+    let response = search_tweets(auth, &session.query, max_tweets, session.search_mode, session.cursor.clone()).await?;
+    session.advance(response.next, response.tweets.is_empty())?;
+
+    Ok(response.tweets)
+}
+
+/// Fetches the next page of profiles of `session`, or nothing if it is exhausted.
+pub async fn next_session_profiles(
+    auth: &UserAuth,
+    session: &mut SearchSession,
+    max_profiles: i32,
+) -> Result<Vec<Profile>> {
+    if session.search_mode != SearchMode::Users {
+        return Err(XploreError::Config(format!(
+            "A profile search needs SearchMode::Users, not {:?}",
+            session.search_mode
+        )));
+    }
+    if session.exhausted {
+        return Ok(Vec::new());
+    }
+
+    let response = search_profiles(auth, &session.query, max_profiles, session.cursor.clone()).await?;
+    session.advance(response.next, response.profiles.is_empty())?;
+
+    Ok(response.profiles)
+}
+
 lazy_static! {
     static ref EMPTY_INSTRUCTIONS: Vec<SearchInstruction> = Vec::new();
     static ref EMPTY_ENTRIES: Vec<SearchEntryRaw> = Vec::new();
@@ -83,12 +201,26 @@ pub struct SearchInstruction {
 }
 
 pub(crate) async fn get_search_timeline(
-    auth: &mut UserAuth,
+    auth: &UserAuth,
     query: &str,
     max_items: i32,
     search_mode: SearchMode,
-    _cursor: Option<String>,
+    cursor: Option<String>,
 ) -> Result<SearchTimeline> {
+    let endpoint = search_endpoint(&auth.operations, query, max_items, search_mode, cursor.as_deref())?;
+
+    let (res, _) = api::request_endpoint::<SearchTimeline>(auth, &endpoint).await?;
+
+    Ok(res)
+}
+
+pub(crate) fn search_endpoint(
+    operations: &OperationRegistry,
+    query: &str,
+    max_items: i32,
+    search_mode: SearchMode,
+    cursor: Option<&str>,
+) -> Result<ApiEndpoint> {
     let max_items = if max_items > 50 { 50 } else { max_items };
 
     let mut variables = json!({
@@ -115,65 +247,16 @@ pub(crate) async fn get_search_timeline(
         _ => {}
     }
 
-    let features = json!({
-        "longform_notetweets_inline_media_enabled": true,
-        "responsive_web_enhance_cards_enabled": false,
-        "responsive_web_media_download_video_enabled": false,
-        "responsive_web_twitter_article_tweet_consumption_enabled": false,
-        "tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled": true,
-        "interactive_text_enabled": false,
-        "responsive_web_text_conversations_enabled": false,
-        "vibe_api_enabled": false,
-        "rweb_lists_timeline_redesign_enabled": true,
-        "responsive_web_graphql_exclude_directive_enabled": true,
-        "verified_phone_label_enabled": false,
-        "creator_subscriptions_tweet_preview_api_enabled": true,
-        "responsive_web_graphql_timeline_navigation_enabled": true,
-        "responsive_web_graphql_skip_user_profile_image_extensions_enabled": false,
-        "tweetypie_unmention_optimization_enabled": true,
-        "responsive_web_edit_tweet_api_enabled": true,
-        "graphql_is_translatable_rweb_tweet_is_translatable_enabled": true,
-        "view_counts_everywhere_api_enabled": true,
-        "longform_notetweets_consumption_enabled": true,
-        "tweet_awards_web_tipping_enabled": false,
-        "freedom_of_speech_not_reach_fetch_enabled": true,
-        "standardized_nudges_misinfo": true,
-        "longform_notetweets_rich_text_read_enabled": true,
-        "responsive_web_enhance_cards_enabled": false,
-        "subscriptions_verification_info_enabled": true,
-        "subscriptions_verification_info_reason_enabled": true,
-        "subscriptions_verification_info_verified_since_enabled": true,
-        "super_follow_badge_privacy_enabled": false,
-        "super_follow_exclusive_tweet_notifications_enabled": false,
-        "super_follow_tweet_api_enabled": false,
-        "super_follow_user_api_enabled": false,
-        "android_graphql_skip_api_media_color_palette": false,
-        "creator_subscriptions_subscription_count_enabled": false,
-        "blue_business_profile_image_shape_enabled": false,
-        "unified_cards_ad_metadata_container_dynamic_card_content_query_enabled": false
-    });
-
-    let field_toggles = json!({
-        "withArticleRichContentState": false
-    });
-
-    let params = [
-        ("variables", serde_json::to_string(&variables)?),
-        ("features", serde_json::to_string(&features)?),
-        ("fieldToggles", serde_json::to_string(&field_toggles)?),
-    ];
-
-    let query_string =
-        params.iter().map(|(k, v)| format!("{}={}", k, urlencoding::encode(v))).collect::<Vec<_>>().join("&");
-
-    let url = format!("https://api.twitter.com/graphql/gkjsKepM6gl_HmFWoWKfgg/SearchTimeline?{}", query_string);
-
-    let (res, _) = api::send_request::<SearchTimeline>(auth, &url, Method::GET, None).await?;
+    if let Some(cursor_val) = cursor {
+        if !cursor_val.is_empty() {
+            variables["cursor"] = json!(cursor_val);
+        }
+    }
 
-    Ok(res)
+    Ok(operations.endpoint("SearchTimeline")?.with_variables(variables))
 }
 
-pub fn parse_search_timeline_tweets(timeline: &SearchTimeline) -> QueryTweetsResponse {
+pub fn parse_search_timeline_tweets(timeline: &SearchTimeline, origin: &str) -> QueryTweetsResponse {
     let mut bottom_cursor = None;
     let mut top_cursor = None;
     let mut tweets = Vec::new();
@@ -222,7 +305,7 @@ pub fn parse_search_timeline_tweets(timeline: &SearchTimeline) -> QueryTweetsRes
                                             .and_then(|result| result.legacy.as_ref());
 
                                         if let Ok(tweet_result) =
-                                            parse_legacy_tweet(user_legacy, result.legacy.as_deref())
+                                            parse_legacy_tweet(user_legacy, result.legacy.as_deref(), origin)
                                         {
                                             if tweet_result.views.is_none() {
                                                 if let Some(views) = &result.views {
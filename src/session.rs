@@ -0,0 +1,195 @@
+//! Persisted login sessions.
+//!
+//! A [`Session`] is everything needed to resume a client without logging in again: the cookies
+//! with their attributes, the guest token, the bearer token and the account they belong to. It
+//! is stored as versioned JSON; files written by `save_cookies_to_file`, which only hold
+//! `(name, value)` pairs, are still accepted.
+
+use {
+    crate::{fs_util, Result, XploreError},
+    chrono::{DateTime, TimeZone, Utc},
+    cookie::{time::OffsetDateTime, Cookie, SameSite},
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+    std::{fmt, fs, path::Path},
+};
+
+/// Version of the session format written by this release.
+pub const SESSION_FORMAT_VERSION: u32 = 1;
+
+/// A snapshot of a client's authentication state.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Session {
+    /// Format version, see [`SESSION_FORMAT_VERSION`].
+    pub version: u32,
+    pub cookies: Vec<StoredCookie>,
+    pub guest_token: Option<String>,
+    /// When the guest token was activated.
+    pub guest_token_created_at: Option<DateTime<Utc>>,
+    pub bearer_token: String,
+    /// Rest ID of the logged-in account, if known.
+    pub user_id: Option<String>,
+    /// Screen name of the logged-in account, if known.
+    pub screen_name: Option<String>,
+    pub saved_at: DateTime<Utc>,
+}
+
+/// A cookie with the attributes X set it with.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredCookie {
+    pub name: String,
+    pub value: String,
+    pub domain: Option<String>,
+    pub path: Option<String>,
+    /// `None` for session cookies.
+    pub expires: Option<DateTime<Utc>>,
+    pub secure: bool,
+    pub http_only: bool,
+    /// `Strict`, `Lax` or `None`.
+    pub same_site: Option<String>,
+}
+
+impl StoredCookie {
+    pub fn from_cookie(cookie: &Cookie<'_>) -> Self {
+        Self {
+            name: cookie.name().to_string(),
+            value: cookie.value().to_string(),
+            domain: cookie.domain().map(str::to_string),
+            path: cookie.path().map(str::to_string),
+            expires: cookie
+                .expires_datetime()
+                .and_then(|expires| Utc.timestamp_opt(expires.unix_timestamp(), 0).single()),
+            secure: cookie.secure().unwrap_or_default(),
+            http_only: cookie.http_only().unwrap_or_default(),
+            same_site: cookie.same_site().map(|same_site| same_site.to_string()),
+        }
+    }
+
+    pub fn to_cookie(&self) -> Cookie<'static> {
+        let mut builder = Cookie::build(self.name.clone(), self.value.clone())
+            .path(self.path.clone().unwrap_or_else(|| "/".to_string()))
+            .secure(self.secure)
+            .http_only(self.http_only);
+
+        if let Some(domain) = &self.domain {
+            builder = builder.domain(domain.clone());
+        }
+        if let Some(expires) =
+            self.expires.and_then(|expires| OffsetDateTime::from_unix_timestamp(expires.timestamp()).ok())
+        {
+            builder = builder.expires(expires);
+        }
+        match self.same_site.as_deref() {
+            Some("Strict") => builder = builder.same_site(SameSite::Strict),
+            Some("Lax") => builder = builder.same_site(SameSite::Lax),
+            Some("None") => builder = builder.same_site(SameSite::None),
+            _ => {}
+        }
+
+        builder.finish()
+    }
+
+    /// Whether the cookie has an expiry date in the past.
+    pub fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= Utc::now())
+    }
+}
+
+// Cookie values and tokens log the account in, so they are never printed.
+impl fmt::Debug for StoredCookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StoredCookie")
+            .field("name", &self.name)
+            .field("value", &REDACTED)
+            .field("domain", &self.domain)
+            .field("path", &self.path)
+            .field("expires", &self.expires)
+            .field("secure", &self.secure)
+            .field("http_only", &self.http_only)
+            .field("same_site", &self.same_site)
+            .finish()
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("version", &self.version)
+            .field("cookies", &self.cookies)
+            .field("guest_token", &self.guest_token.as_ref().map(|_| REDACTED))
+            .field("guest_token_created_at", &self.guest_token_created_at)
+            .field("bearer_token", &REDACTED)
+            .field("user_id", &self.user_id)
+            .field("screen_name", &self.screen_name)
+            .field("saved_at", &self.saved_at)
+            .finish()
+    }
+}
+
+const REDACTED: &str = "<redacted>";
+
+impl Session {
+    /// Parses a session, upgrading the legacy `[[name, value], ...]` cookie format.
+    ///
+    /// Returns `XploreError::Config` for sessions written by a newer release.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)?;
+
+        if value.is_array() {
+            let pairs: Vec<(String, String)> = serde_json::from_value(value)?;
+            return Ok(Self::from_legacy_cookies(pairs));
+        }
+
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or_default();
+        if version > u64::from(SESSION_FORMAT_VERSION) {
+            return Err(XploreError::Config(format!(
+                "Session format version {} is newer than the supported version {}",
+                version, SESSION_FORMAT_VERSION
+            )));
+        }
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Reads a session file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Writes the session to `path`, replacing the file atomically. On Unix the file is readable
+    /// by its owner only.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs_util::write_atomic(path.as_ref(), self.to_json()?.as_bytes(), true)
+    }
+
+    fn from_legacy_cookies(pairs: Vec<(String, String)>) -> Self {
+        let cookies = pairs
+            .into_iter()
+            .map(|(name, value)| StoredCookie {
+                name,
+                value,
+                domain: Some("twitter.com".to_string()),
+                path: Some("/".to_string()),
+                expires: None,
+                secure: true,
+                http_only: true,
+                same_site: None,
+            })
+            .collect();
+
+        Self {
+            version: SESSION_FORMAT_VERSION,
+            cookies,
+            guest_token: None,
+            guest_token_created_at: None,
+            bearer_token: crate::api::BEARER_TOKEN.to_string(),
+            user_id: None,
+            screen_name: None,
+            saved_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Where a client keeps its [`Session`] between runs.
+//!
+//! The session holds `auth_token`, which grants full access to the account, so the file backend
+//! encrypts it at rest. A store configured in `XploreOptions::session_store` is read when the
+//! client is created and written every time X sets cookies.
+//!
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use xplore::{session_store::EncryptedFileStore, Xplore, XploreOptions};
+//! # async fn run() -> xplore::Result<()> {
+//! let store = EncryptedFileStore::with_passphrase("session.enc", std::env::var("SESSION_PASSPHRASE").unwrap());
+//! let options = XploreOptions { session_store: Some(Arc::new(store)), ..Default::default() };
+//! let xplore = Xplore::new(Some(options)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use {
+    crate::{fs_util, session::Session, Result, XploreError},
+    aes_gcm::{
+        aead::{rand_core::RngCore, Aead, KeyInit, OsRng},
+        Aes256Gcm, Nonce,
+    },
+    argon2::Argon2,
+    async_trait::async_trait,
+    base64::{engine::general_purpose::STANDARD, Engine},
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    std::{
+        fs,
+        path::{Path, PathBuf},
+        sync::{Arc, Mutex},
+    },
+};
+
+/// Persists sessions. Implementations must be safe to call from several tasks at once.
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    /// Returns the stored session, or `None` if nothing has been stored yet.
+    async fn load(&self) -> Result<Option<Session>>;
+
+    /// Replaces the stored session.
+    async fn save(&self, session: &Session) -> Result<()>;
+
+    /// Removes the stored session.
+    async fn clear(&self) -> Result<()>;
+}
+
+/// Keeps the session in memory; useful in tests.
+#[derive(Default)]
+pub struct MemorySessionStore {
+    session: Mutex<Option<Session>>,
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the last session saved.
+    pub fn get(&self) -> Option<Session> {
+        self.session.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemorySessionStore {
+    async fn load(&self) -> Result<Option<Session>> {
+        Ok(self.get())
+    }
+
+    async fn save(&self, session: &Session) -> Result<()> {
+        *self.session.lock().unwrap() = Some(session.clone());
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<()> {
+        *self.session.lock().unwrap() = None;
+        Ok(())
+    }
+}
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Minimum size of a key file, in bytes.
+const MIN_KEY_FILE_LEN: usize = 32;
+
+/// How the file store derives its encryption key.
+enum KeySource {
+    /// Argon2id over the passphrase with a per-file salt. The last derived key is cached, since
+    /// derivation is deliberately slow and cookies are saved often.
+    Passphrase { passphrase: String, cached: Mutex<Option<([u8; SALT_LEN], [u8; 32])>> },
+    /// SHA-256 of the key file contents.
+    KeyFile([u8; 32]),
+}
+
+/// The on-disk layout of an encrypted session.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    version: u32,
+    /// `argon2id` or `key-file`.
+    kdf: String,
+    salt: Option<String>,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Stores the session in a file encrypted with AES-256-GCM.
+///
+/// Key derivation and file access run on tokio's blocking pool, so a slow disk or the Argon2
+/// derivation does not stall other requests. Clones share the derived key.
+#[derive(Clone)]
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    key: Arc<KeySource>,
+}
+
+impl EncryptedFileStore {
+    /// Encrypts with a key derived from `passphrase`.
+    pub fn with_passphrase(path: impl Into<PathBuf>, passphrase: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            key: Arc::new(KeySource::Passphrase { passphrase: passphrase.into(), cached: Mutex::new(None) }),
+        }
+    }
+
+    /// Encrypts with a key derived from the contents of `key_file`, which must hold at least 32
+    /// bytes, e.g. from `head -c 32 /dev/urandom`.
+    pub fn with_key_file(path: impl Into<PathBuf>, key_file: impl AsRef<Path>) -> Result<Self> {
+        let key_file = key_file.as_ref();
+        let contents = fs::read(key_file)?;
+        if contents.len() < MIN_KEY_FILE_LEN {
+            return Err(XploreError::Config(format!(
+                "Key file {} holds {} bytes, at least {} are required",
+                key_file.display(),
+                contents.len(),
+                MIN_KEY_FILE_LEN
+            )));
+        }
+
+        Ok(Self { path: path.into(), key: Arc::new(KeySource::KeyFile(Sha256::digest(&contents).into())) })
+    }
+
+    fn kdf(&self) -> &'static str {
+        match *self.key {
+            KeySource::Passphrase { .. } => "argon2id",
+            KeySource::KeyFile(_) => "key-file",
+        }
+    }
+
+    /// Returns the key for `salt`, or a fresh salt and its key when `salt` is `None`.
+    fn key(&self, salt: Option<[u8; SALT_LEN]>) -> Result<(Option<[u8; SALT_LEN]>, [u8; 32])> {
+        let (passphrase, cached) = match &*self.key {
+            KeySource::KeyFile(key) => return Ok((None, *key)),
+            KeySource::Passphrase { passphrase, cached } => (passphrase, cached),
+        };
+
+        let mut cached = cached.lock().unwrap();
+        if let Some((cached_salt, key)) = *cached {
+            if salt.is_none() || salt == Some(cached_salt) {
+                return Ok((Some(cached_salt), key));
+            }
+        }
+
+        let salt = salt.unwrap_or_else(|| {
+            let mut salt = [0u8; SALT_LEN];
+            OsRng.fill_bytes(&mut salt);
+            salt
+        });
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| XploreError::Config(format!("Failed to derive the session key: {}", e)))?;
+
+        *cached = Some((salt, key));
+        Ok((Some(salt), key))
+    }
+
+    fn encrypt(&self, plaintext: &[u8]) -> Result<Envelope> {
+        let (salt, key) = self.key(None)?;
+        let mut nonce = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let ciphertext = Aes256Gcm::new(&key.into())
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| XploreError::Config("Failed to encrypt the session".into()))?;
+
+        Ok(Envelope {
+            version: 1,
+            kdf: self.kdf().to_string(),
+            salt: salt.map(|salt| STANDARD.encode(salt)),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    fn decrypt(&self, envelope: &Envelope) -> Result<Vec<u8>> {
+        if envelope.kdf != self.kdf() {
+            return Err(XploreError::Config(format!(
+                "{} was encrypted with {}, but the store uses {}",
+                self.path.display(),
+                envelope.kdf,
+                self.kdf()
+            )));
+        }
+
+        let decode = |field: &str, value: &str| {
+            STANDARD
+                .decode(value)
+                .map_err(|e| XploreError::Config(format!("Invalid {} in {}: {}", field, self.path.display(), e)))
+        };
+        let salt = match &envelope.salt {
+            Some(salt) => Some(
+                <[u8; SALT_LEN]>::try_from(decode("salt", salt)?)
+                    .map_err(|_| XploreError::Config(format!("Invalid salt in {}", self.path.display())))?,
+            ),
+            None => None,
+        };
+        let nonce = decode("nonce", &envelope.nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(XploreError::Config(format!("Invalid nonce in {}", self.path.display())));
+        }
+
+        let (_, key) = self.key(salt)?;
+        Aes256Gcm::new(&key.into())
+            .decrypt(Nonce::from_slice(&nonce), decode("ciphertext", &envelope.ciphertext)?.as_slice())
+            .map_err(|_| {
+                XploreError::Config(format!("Failed to decrypt {}: wrong passphrase or key file", self.path.display()))
+            })
+    }
+
+    fn load_blocking(&self) -> Result<Option<Session>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        // A damaged file is reported as `Config`, like a wrong key, so that the client ignores it.
+        let envelope: Envelope = serde_json::from_str(&fs::read_to_string(&self.path)?)
+            .map_err(|e| XploreError::Config(format!("{} is not a session store: {}", self.path.display(), e)))?;
+        let plaintext = self.decrypt(&envelope)?;
+        let json = String::from_utf8(plaintext)
+            .map_err(|_| XploreError::Config(format!("{} does not hold a session", self.path.display())))?;
+
+        match Session::from_json(&json) {
+            Ok(session) => Ok(Some(session)),
+            Err(XploreError::Json(e)) => {
+                Err(XploreError::Config(format!("{} does not hold a session: {}", self.path.display(), e)))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save_blocking(&self, json: &str) -> Result<()> {
+        let envelope = self.encrypt(json.as_bytes())?;
+        fs_util::write_atomic(&self.path, serde_json::to_string_pretty(&envelope)?.as_bytes(), true)
+    }
+
+    fn clear_blocking(&self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Runs `f` with a clone of the store on the blocking pool.
+    async fn blocking<T: Send + 'static>(&self, f: impl FnOnce(Self) -> Result<T> + Send + 'static) -> Result<T> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || f(store))
+            .await
+            .map_err(|e| XploreError::Config(format!("Session store task failed: {}", e)))?
+    }
+}
+
+#[async_trait]
+impl SessionStore for EncryptedFileStore {
+    async fn load(&self) -> Result<Option<Session>> {
+        self.blocking(|store| store.load_blocking()).await
+    }
+
+    async fn save(&self, session: &Session) -> Result<()> {
+        let json = session.to_json()?;
+        self.blocking(move |store| store.save_blocking(&json)).await
+    }
+
+    async fn clear(&self) -> Result<()> {
+        self.blocking(|store| store.clear_blocking()).await
+    }
+}
@@ -0,0 +1,147 @@
+//! Tweet length as X counts it.
+//!
+//! X does not count characters but weights them, following the twitter-text v3 rules:
+//!
+//! - text is NFC-normalized first,
+//! - code points in the Latin, Greek, Cyrillic and other ranges below U+1100, plus a few
+//!   punctuation ranges, weigh 1; everything else, CJK included, weighs 2,
+//! - an emoji weighs 2 however many code points it is made of, so a flag, a skin tone or a
+//!   family joined with ZWJ counts the same as a single emoji,
+//! - every URL weighs 23, the length of the t.co link that replaces it.
+//!
+//! @mentions, #hashtags and $cashtags are not shortened and count character by character.
+//!
+//! ```
+//! use xplore::text::weighted_length;
+//!
+//! assert_eq!(weighted_length("hello"), 5);
+//! assert_eq!(weighted_length("こんにちは"), 10);
+//! assert_eq!(weighted_length("👨‍👩‍👧 https://example.com/a/very/long/path"), 26);
+//! ```
+
+use {lazy_static::lazy_static, regex::Regex, unicode_normalization::UnicodeNormalization};
+
+/// Weight of one code point outside `LIGHT_RANGES`, and of one emoji, in hundredths.
+const DEFAULT_WEIGHT: usize = 200;
+
+/// Weight of one code point inside `LIGHT_RANGES`, in hundredths.
+const LIGHT_WEIGHT: usize = 100;
+
+/// Weights are counted in hundredths of a character.
+const SCALE: usize = 100;
+
+/// Length of a t.co link, which every URL counts as.
+pub const URL_LENGTH: usize = 23;
+
+/// Code points that weigh 1.
+const LIGHT_RANGES: &[(u32, u32)] = &[(0x0000, 0x10FF), (0x2000, 0x200D), (0x2010, 0x201F), (0x2032, 0x2037)];
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+lazy_static! {
+    /// URLs with a scheme, bare domains with a common top-level domain, and bare domains with
+    /// any country-code domain when followed by a path, as twitter-text links them.
+    static ref URL: Regex = Regex::new(
+        r"(?i)\b(?:https?://[^\s/$.?#][^\s]*|(?:[a-z0-9](?:[a-z0-9-]*[a-z0-9])?\.)+(?:(?:com|net|org|edu|gov|mil|int|info|biz|app|dev|xyz|io|co|me|tv|ai|ly|gg)\b(?:[/?#][^\s]*)?|[a-z]{2}/[^\s]*))"
+    )
+    .unwrap();
+}
+
+/// The length X gives `text`, which must not exceed [`MAX_TWEET_LENGTH`](crate::tweets::MAX_TWEET_LENGTH)
+/// for a regular tweet.
+pub fn weighted_length(text: &str) -> usize {
+    let text: String = text.nfc().collect();
+
+    let mut weight = 0;
+    let mut last = 0;
+    for url in find_urls(&text) {
+        weight += text_weight(&text[last..url.0]) + URL_LENGTH * SCALE;
+        last = url.1;
+    }
+    weight += text_weight(&text[last..]);
+
+    weight / SCALE
+}
+
+/// Byte ranges of the URLs in `text`, leaving out trailing punctuation and e-mail addresses.
+fn find_urls(text: &str) -> Vec<(usize, usize)> {
+    URL.find_iter(text)
+        .filter(|m| !matches!(text[..m.start()].chars().next_back(), Some('@' | '.' | '/')))
+        .map(|m| {
+            let mut url = m.as_str();
+            loop {
+                let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '"']);
+                let trimmed = match trimmed.strip_suffix(')') {
+                    Some(rest) if trimmed.matches('(').count() < trimmed.matches(')').count() => rest,
+                    _ => trimmed,
+                };
+                if trimmed.len() == url.len() {
+                    break;
+                }
+                url = trimmed;
+            }
+            (m.start(), m.start() + url.len())
+        })
+        .collect()
+}
+
+/// Weight of text without URLs, in hundredths.
+fn text_weight(text: &str) -> usize {
+    let mut weight = 0;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if is_emoji_start(c, chars.peek().copied()) {
+            skip_emoji_rest(c, &mut chars);
+            weight += DEFAULT_WEIGHT;
+        } else if LIGHT_RANGES.iter().any(|(start, end)| (*start..=*end).contains(&(c as u32))) {
+            weight += LIGHT_WEIGHT;
+        } else {
+            weight += DEFAULT_WEIGHT;
+        }
+    }
+
+    weight
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x1F000..=0x1FAFF | 0x2300..=0x23FF | 0x2600..=0x27BF | 0x2B00..=0x2BFF)
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// Whether `c` starts an emoji; a keycap such as `1️⃣` starts with a plain digit.
+fn is_emoji_start(c: char, next: Option<char>) -> bool {
+    is_emoji(c) || (matches!(c, '0'..='9' | '#' | '*') && matches!(next, Some('\u{FE0F}' | '\u{20E3}')))
+}
+
+/// Consumes the code points that belong to the emoji started by `first`: a second regional
+/// indicator, variation selectors, skin tones, keycaps, tags and ZWJ-joined emoji.
+fn skip_emoji_rest(first: char, chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    if is_regional_indicator(first) {
+        if chars.peek().copied().is_some_and(is_regional_indicator) {
+            chars.next();
+        }
+        return;
+    }
+
+    while let Some(&c) = chars.peek() {
+        match c as u32 {
+            0xFE0F | 0x20E3 | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F => {
+                chars.next();
+            }
+            _ if c == ZERO_WIDTH_JOINER => {
+                let mut ahead = chars.clone();
+                ahead.next();
+                if !ahead.peek().copied().is_some_and(is_emoji) {
+                    break;
+                }
+                chars.next();
+                chars.next();
+            }
+            _ => break,
+        }
+    }
+}
@@ -128,6 +128,33 @@ pub struct TimelineResultRaw {
     pub quoted_status_result: Option<Box<TimelineQuotedStatus>>,
     pub legacy: Option<Box<LegacyTweetRaw>>,
     pub tweet: Option<Box<TimelineResultRaw>>,
+    pub card: Option<TimelineCard>,
+}
+
+/// A card attached to a tweet, such as a poll.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimelineCard {
+    pub rest_id: Option<String>,
+    pub legacy: Option<TimelineCardLegacy>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TimelineCardLegacy {
+    /// The card type, e.g. `poll2choice_text_only`.
+    pub name: Option<String>,
+    pub binding_values: Option<Vec<CardBindingValue>>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CardBindingValue {
+    pub key: String,
+    pub value: CardValue,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CardValue {
+    pub string_value: Option<String>,
+    pub boolean_value: Option<bool>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -313,6 +340,7 @@ pub struct TimelineV1 {
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum ParseTweetResult {
     Success { tweet: Tweet },
     Error { err: String },
@@ -372,7 +400,7 @@ pub struct UserMention {
     pub name: String,
 }
 
-pub fn parse_timeline_tweet(timeline: &TimelineV1, id: &str) -> ParseTweetResult {
+pub fn parse_timeline_tweet(timeline: &TimelineV1, id: &str, origin: &str) -> ParseTweetResult {
     let empty_tweets = HashMap::new();
     let tweets = match &timeline.global_objects {
         Some(go) => go.tweets.as_ref().unwrap_or(&empty_tweets),
@@ -446,7 +474,8 @@ pub fn parse_timeline_tweet(timeline: &TimelineV1, id: &str) -> ParseTweetResult
         mentions,
         name: user.name.clone(),
         permanent_url: Some(format!(
-            "https://twitter.com/{}/status/{}",
+            "{}/{}/status/{}",
+            origin,
             user.screen_name.as_ref().unwrap_or(&String::new()),
             id
         )),
@@ -502,7 +531,7 @@ pub fn parse_timeline_tweet(timeline: &TimelineV1, id: &str) -> ParseTweetResult
         tweet_obj.is_quoted = Some(true);
         tweet_obj.quoted_status_id = Some(quoted_id.clone());
 
-        if let ParseTweetResult::Success { tweet: quoted_tweet } = parse_timeline_tweet(timeline, quoted_id) {
+        if let ParseTweetResult::Success { tweet: quoted_tweet } = parse_timeline_tweet(timeline, quoted_id, origin) {
             tweet_obj.quoted_status = Some(Box::new(quoted_tweet));
         }
     }
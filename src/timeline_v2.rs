@@ -4,9 +4,9 @@ use {
     crate::{
         api_utils::parse_media_groups,
         profile::LegacyUserRaw,
-        timeline_v1::{LegacyTweetRaw, TimelineResultRaw},
+        timeline_v1::{LegacyTweetRaw, TimelineCard, TimelineResultRaw},
         tweets::Mention,
-        tweets::Tweet,
+        tweets::{PollOption, PollV2, Tweet},
         Result, XploreError,
     },
     chrono::Utc,
@@ -100,6 +100,21 @@ pub struct TimelineV2 {
     pub timeline: Option<TimelineItems>,
 }
 
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListTimeline {
+    pub data: Option<ListTimelineData>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListTimelineData {
+    pub list: Option<ListTimelineList>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ListTimelineList {
+    pub tweets_timeline: Option<Timeline>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ThreadedConversation {
     pub data: Option<ThreadedConversationData>,
@@ -202,7 +217,8 @@ pub struct SearchEntryItemInnerRaw {
     pub content: Option<TimelineEntryItemContentRaw>,
 }
 
-pub fn parse_legacy_tweet(user: Option<&LegacyUserRaw>, tweet: Option<&LegacyTweetRaw>) -> Result<Tweet> {
+/// Builds a [`Tweet`] from its legacy fields; `origin`, e.g. `https://x.com`, prefixes its permalink.
+pub fn parse_legacy_tweet(user: Option<&LegacyUserRaw>, tweet: Option<&LegacyTweetRaw>, origin: &str) -> Result<Tweet> {
     let tweet = tweet.ok_or(XploreError::Api("Tweet was not found in the timeline object".into()))?;
     let user = user.ok_or(XploreError::Api("User was not found in the timeline object".into()))?;
 
@@ -226,12 +242,10 @@ pub fn parse_legacy_tweet(user: Option<&LegacyUserRaw>, tweet: Option<&LegacyTwe
         .map(|mentions| {
             mentions
                 .iter()
-                .filter_map(|m| {
-                    Some(Mention {
-                        id: m.id_str.clone().unwrap_or_default(),
-                        name: m.name.clone(),
-                        username: m.screen_name.clone(),
-                    })
+                .map(|m| Mention {
+                    id: m.id_str.clone().unwrap_or_default(),
+                    name: m.name.clone(),
+                    username: m.screen_name.clone(),
                 })
                 .collect()
         })
@@ -256,7 +270,8 @@ pub fn parse_legacy_tweet(user: Option<&LegacyUserRaw>, tweet: Option<&LegacyTwe
         mentions,
         name: user.name.clone(),
         permanent_url: Some(format!(
-            "https://twitter.com/{}/status/{}",
+            "{}/{}/status/{}",
+            origin,
             user.screen_name.as_ref().unwrap_or(&String::new()),
             id_str
         )),
@@ -322,10 +337,11 @@ pub fn parse_timeline_entry_item_content_raw(
     content: &TimelineEntryItemContent,
     _entry_id: &str,
     is_conversation: bool,
+    origin: &str,
 ) -> Option<Tweet> {
     let result = content.tweet_results.as_ref().or(content.tweet_result.as_ref()).and_then(|r| r.result.as_ref())?;
 
-    let tweet_result = parse_result(result);
+    let tweet_result = parse_result(result, origin);
     if tweet_result.success {
         let mut tweet = tweet_result.tweet?;
 
@@ -344,13 +360,14 @@ pub fn parse_and_push(
     content: &TimelineEntryItemContent,
     entry_id: String,
     is_conversation: bool,
+    origin: &str,
 ) {
-    if let Some(tweet) = parse_timeline_entry_item_content_raw(content, &entry_id, is_conversation) {
+    if let Some(tweet) = parse_timeline_entry_item_content_raw(content, &entry_id, is_conversation, origin) {
         tweets.push(tweet);
     }
 }
 
-pub fn parse_result(result: &TimelineResultRaw) -> ParseTweetResult {
+pub fn parse_result(result: &TimelineResultRaw, origin: &str) -> ParseTweetResult {
     let tweet_result = parse_legacy_tweet(
         result
             .core
@@ -359,6 +376,7 @@ pub fn parse_result(result: &TimelineResultRaw) -> ParseTweetResult {
             .and_then(|u| u.result.as_ref())
             .and_then(|r| r.legacy.as_ref()),
         result.legacy.as_deref(),
+        origin,
     );
 
     let mut tweet = match tweet_result {
@@ -372,9 +390,13 @@ pub fn parse_result(result: &TimelineResultRaw) -> ParseTweetResult {
         }
     }
 
+    if let Some(card) = result.card.as_ref() {
+        tweet.poll = parse_poll_card(card);
+    }
+
     if let Some(quoted) = result.quoted_status_result.as_ref() {
         if let Some(quoted_result) = quoted.result.as_ref() {
-            let quoted_tweet_result = parse_result(quoted_result);
+            let quoted_tweet_result = parse_result(quoted_result, origin);
             if quoted_tweet_result.success {
                 tweet.quoted_status = quoted_tweet_result.tweet.map(Box::new);
             }
@@ -384,6 +406,35 @@ pub fn parse_result(result: &TimelineResultRaw) -> ParseTweetResult {
     ParseTweetResult { success: true, tweet: Some(tweet), err: None }
 }
 
+/// Reads a poll card's choices and counts; `None` for cards that are not polls.
+pub fn parse_poll_card(card: &TimelineCard) -> Option<PollV2> {
+    let legacy = card.legacy.as_ref()?;
+    if !legacy.name.as_deref()?.starts_with("poll") {
+        return None;
+    }
+
+    let values = legacy.binding_values.as_deref().unwrap_or_default();
+    let string = |key: &str| values.iter().find(|v| v.key == key).and_then(|v| v.value.string_value.clone());
+    let counts_are_final = values.iter().find(|v| v.key == "counts_are_final").and_then(|v| v.value.boolean_value);
+
+    let options = (1..=4)
+        .filter_map(|position| {
+            Some(PollOption {
+                position: Some(position),
+                label: string(&format!("choice{}_label", position))?,
+                votes: string(&format!("choice{}_count", position)).and_then(|count| count.parse().ok()),
+            })
+        })
+        .collect();
+
+    Some(PollV2 {
+        id: card.rest_id.clone(),
+        end_datetime: string("end_datetime_utc"),
+        voting_status: counts_are_final.map(|done| if done { "closed" } else { "open" }.to_string()),
+        options,
+    })
+}
+
 pub struct ParseTweetResult {
     pub success: bool,
     pub tweet: Option<Tweet>,
@@ -397,11 +448,7 @@ pub struct QueryTweetsResponse {
     pub previous: Option<String>,
 }
 
-pub fn parse_timeline_tweets_v2(timeline: &TimelineV2) -> QueryTweetsResponse {
-    let mut tweets = Vec::new();
-    let mut bottom_cursor = None;
-    let mut top_cursor = None;
-
+pub fn parse_timeline_tweets_v2(timeline: &TimelineV2, origin: &str) -> QueryTweetsResponse {
     let instructions = timeline
         .data
         .as_ref()
@@ -412,13 +459,32 @@ pub fn parse_timeline_tweets_v2(timeline: &TimelineV2) -> QueryTweetsResponse {
         .and_then(|timeline| timeline.instructions.as_ref())
         .unwrap_or(&EMPTY_INSTRUCTIONS);
 
-    let expected_entry_types = ["tweet-", "profile-conversation-"];
+    parse_timeline_instructions(instructions, origin)
+}
+
+/// Parses the tweets of a `ListLatestTweetsTimeline` response.
+pub fn parse_list_timeline_tweets(timeline: &ListTimeline, origin: &str) -> QueryTweetsResponse {
+    let instructions = timeline
+        .data
+        .as_ref()
+        .and_then(|data| data.list.as_ref())
+        .and_then(|list| list.tweets_timeline.as_ref())
+        .and_then(|timeline| timeline.timeline.as_ref())
+        .and_then(|timeline| timeline.instructions.as_ref())
+        .unwrap_or(&EMPTY_INSTRUCTIONS);
+
+    parse_timeline_instructions(instructions, origin)
+}
+
+fn parse_timeline_instructions(instructions: &[TimelineInstruction], origin: &str) -> QueryTweetsResponse {
+    let mut tweets = Vec::new();
+    let mut bottom_cursor = None;
+    let mut top_cursor = None;
+
+    let expected_entry_types = ["tweet-", "profile-conversation-", "list-conversation-"];
 
     for instruction in instructions {
-        let entries = instruction
-            .entries
-            .as_deref()
-            .unwrap_or_else(|| instruction.entry.as_ref().map(std::slice::from_ref).unwrap_or_default());
+        let entries = instruction.entries.as_deref().unwrap_or(instruction.entry.as_slice());
 
         for entry in entries {
             let content = match &entry.content {
@@ -449,14 +515,14 @@ pub fn parse_timeline_tweets_v2(timeline: &TimelineV2) -> QueryTweetsResponse {
             }
 
             if let Some(ref item_content) = content.item_content {
-                parse_and_push(&mut tweets, item_content, entry_id.clone(), false);
+                parse_and_push(&mut tweets, item_content, entry_id.clone(), false, origin);
             }
 
             if let Some(items) = &content.items {
                 for item in items {
                     if let Some(item) = &item.item {
                         if let Some(item_content) = &item.item_content {
-                            parse_and_push(&mut tweets, item_content, entry_id.clone(), false);
+                            parse_and_push(&mut tweets, item_content, entry_id.clone(), false, origin);
                         }
                     }
                 }
@@ -467,7 +533,7 @@ pub fn parse_timeline_tweets_v2(timeline: &TimelineV2) -> QueryTweetsResponse {
     QueryTweetsResponse { tweets, next: bottom_cursor, previous: top_cursor }
 }
 
-pub fn parse_threaded_conversation(conversation: &ThreadedConversation) -> Option<Tweet> {
+pub fn parse_threaded_conversation(conversation: &ThreadedConversation, origin: &str) -> Option<Tweet> {
     let mut main_tweet: Option<Tweet> = None;
     let mut replies: Vec<Tweet> = Vec::new();
 
@@ -488,6 +554,7 @@ pub fn parse_threaded_conversation(conversation: &ThreadedConversation) -> Optio
                         item_content,
                         entry.entry_id.as_deref().unwrap_or_default(),
                         true,
+                        origin,
                     ) {
                         if main_tweet.is_none() {
                             main_tweet = Some(tweet);
@@ -505,6 +572,7 @@ pub fn parse_threaded_conversation(conversation: &ThreadedConversation) -> Optio
                                     item_content,
                                     entry.entry_id.as_deref().unwrap_or_default(),
                                     true,
+                                    origin,
                                 ) {
                                     replies.push(tweet);
                                 }
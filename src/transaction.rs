@@ -0,0 +1,279 @@
+//! Generation of the `x-client-transaction-id` header.
+//!
+//! The web client derives a per-request ID from three things it loads with the home page:
+//!
+//! - the `twitter-site-verification` meta tag, a base64 key,
+//! - the `loading-x-anim-*` SVGs, four animation frames drawn as cubic curves,
+//! - the `ondemand.s.<hash>a.js` script, which says which key bytes pick the frame row and time.
+//!
+//! The key and an "animation key" computed from the selected frame are hashed together with the
+//! request method, path and time, then masked with a random byte.
+//!
+//! [`TransactionKey::from_pages`] is pure so it can be run against saved copies; the client
+//! fetches the pages itself when `ClientProfile::transaction_id` turns the header on.
+
+use {
+    crate::{auth::UserAuth, discovery, Result, XploreError},
+    aes_gcm::aead::{rand_core::RngCore, OsRng},
+    base64::{engine::general_purpose::STANDARD, Engine},
+    lazy_static::lazy_static,
+    regex::Regex,
+    sha2::{Digest, Sha256},
+    std::time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Seconds since the epoch that the web client counts time from.
+const EPOCH_OFFSET: u64 = 1_682_924_400;
+
+/// Fixed salt of the hashed string.
+const KEYWORD: &str = "obfiowerehiring";
+
+/// Fixed last byte of the ID.
+const ADDITIONAL_BYTE: u8 = 3;
+
+/// Length of the animation, in the web client's time units.
+const TOTAL_TIME: f64 = 4096.0;
+
+lazy_static! {
+    static ref ONDEMAND_HASH: Regex = Regex::new(r#"['"]ondemand\.s['"]:\s*['"](\w*)['"]"#).unwrap();
+    static ref KEY_BYTE_INDEX: Regex = Regex::new(r"\(\w\[(\d{1,2})\],\s*16\)").unwrap();
+    static ref VERIFICATION_META: Regex =
+        Regex::new(r#"<meta[^>]*name=["']twitter-site-verification["'][^>]*>"#).unwrap();
+    static ref CONTENT_ATTR: Regex = Regex::new(r#"content=["']([^"']+)["']"#).unwrap();
+    static ref ANIMATION_SVG: Regex =
+        Regex::new(r#"(?s)<svg[^>]*id=["']loading-x-anim[^"']*["'][^>]*>(.*?)</svg>"#).unwrap();
+    static ref PATH_D: Regex = Regex::new(r#"<path[^>]*\sd=["']([^"']+)["']"#).unwrap();
+    static ref NUMBER: Regex = Regex::new(r"\d+").unwrap();
+}
+
+/// What is needed to generate transaction IDs, derived once from the home page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionKey {
+    key_bytes: Vec<u8>,
+    animation_key: String,
+}
+
+/// Finds the URL of the `ondemand.s` script in the x.com home page.
+pub fn find_ondemand_url(html: &str) -> Option<String> {
+    ONDEMAND_HASH
+        .captures(html)
+        .map(|c| format!("https://abs.twimg.com/responsive-web/client-web/ondemand.s.{}a.js", &c[1]))
+}
+
+impl TransactionKey {
+    /// Builds the key from the home page and the `ondemand.s` script it references.
+    pub fn from_pages(home_html: &str, ondemand_js: &str) -> Result<Self> {
+        let invalid =
+            |what: &str| XploreError::InvalidResponse(format!("No {} found for x-client-transaction-id", what));
+
+        let key = VERIFICATION_META
+            .find(home_html)
+            .and_then(|meta| CONTENT_ATTR.captures(meta.as_str()))
+            .map(|c| c[1].to_string())
+            .ok_or_else(|| invalid("twitter-site-verification key"))?;
+        let key_bytes = STANDARD
+            .decode(key)
+            .map_err(|e| XploreError::InvalidResponse(format!("Invalid twitter-site-verification key: {}", e)))?;
+        if key_bytes.len() < 6 {
+            return Err(invalid("usable twitter-site-verification key"));
+        }
+
+        let indices: Vec<usize> = KEY_BYTE_INDEX.captures_iter(ondemand_js).filter_map(|c| c[1].parse().ok()).collect();
+        let (row_index, frame_indices) = indices.split_first().ok_or_else(|| invalid("key byte indices"))?;
+
+        let frames = parse_frames(home_html);
+        if frames.len() < 4 {
+            return Err(invalid("loading-x-anim frames"));
+        }
+
+        let byte = |index: usize| key_bytes.get(index).copied().ok_or_else(|| invalid(&format!("key byte {}", index)));
+
+        let row = (byte(*row_index)? % 16) as usize;
+        let mut frame_time = 1.0;
+        for index in frame_indices {
+            frame_time *= f64::from(byte(*index)? % 16);
+        }
+        let frame_time = js_round(frame_time / 10.0) * 10.0;
+
+        let frame = &frames[(key_bytes[5] % 4) as usize];
+        let values =
+            frame.get(row).filter(|values| values.len() >= 11).ok_or_else(|| invalid("animation frame row"))?;
+
+        Ok(Self { animation_key: animate(values, frame_time / TOTAL_TIME), key_bytes })
+    }
+
+    /// Generates the ID for a request, e.g. `generate("GET", "/i/api/graphql/…/UserByScreenName")`.
+    pub fn generate(&self, method: &str, path: &str) -> String {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        let time = now.saturating_sub(EPOCH_OFFSET) as u32;
+
+        self.generate_at(method, path, time, OsRng.next_u32() as u8)
+    }
+
+    /// Generates the ID for a fixed time, counted from `EPOCH_OFFSET`, and mask byte.
+    pub fn generate_at(&self, method: &str, path: &str, time: u32, mask: u8) -> String {
+        let hash = Sha256::digest(format!("{}!{}!{}{}{}", method, path, time, KEYWORD, self.animation_key));
+
+        let mut bytes = Vec::with_capacity(self.key_bytes.len() + 22);
+        bytes.push(mask);
+        bytes.extend(
+            self.key_bytes
+                .iter()
+                .chain(time.to_le_bytes().iter())
+                .chain(hash[..16].iter())
+                .chain(std::iter::once(&ADDITIONAL_BYTE))
+                .map(|byte| byte ^ mask),
+        );
+
+        STANDARD.encode(bytes).trim_end_matches('=').to_string()
+    }
+}
+
+/// Fetches the home page and `ondemand.s` script and derives the key from them.
+pub(crate) async fn fetch_transaction_key(auth: &UserAuth) -> Result<TransactionKey> {
+    let html = discovery::fetch_text(auth, &auth.urls.web_url("")).await?;
+    let ondemand_url = find_ondemand_url(&html)
+        .ok_or_else(|| XploreError::InvalidResponse("No ondemand.s script found in the x.com home page".into()))?;
+    let ondemand = discovery::fetch_text(auth, &ondemand_url).await?;
+
+    TransactionKey::from_pages(&html, &ondemand)
+}
+
+/// The numbers of the second path of every `loading-x-anim` SVG, one row per curve segment.
+fn parse_frames(html: &str) -> Vec<Vec<Vec<u32>>> {
+    ANIMATION_SVG
+        .captures_iter(html)
+        .filter_map(|svg| {
+            let d = PATH_D.captures_iter(&svg[1]).nth(1)?[1].to_string();
+            let segments = d.get(9..)?;
+            Some(
+                segments
+                    .split('C')
+                    .map(|segment| NUMBER.find_iter(segment).filter_map(|n| n.as_str().parse().ok()).collect())
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Plays the frame's colour and rotation animation to `time` and encodes the result as hex.
+fn animate(values: &[u32], time: f64) -> String {
+    let value = |index: usize| f64::from(values[index]);
+
+    let from_color = [value(0), value(1), value(2)];
+    let to_color = [value(3), value(4), value(5)];
+    let to_rotation = scale(value(6), 60.0, 360.0).floor();
+
+    let curves: Vec<f64> = values[7..]
+        .iter()
+        .enumerate()
+        .map(|(i, v)| round2(scale(f64::from(*v), if i % 2 == 1 { -1.0 } else { 0.0 }, 1.0)))
+        .collect();
+    let progress = cubic_value(&curves, time);
+
+    let mut parts: Vec<String> = from_color
+        .iter()
+        .zip(to_color.iter())
+        .map(|(from, to)| format!("{:x}", interpolate(*from, *to, progress).max(0.0).round_ties_even() as i64))
+        .collect();
+
+    let rotation = interpolate(0.0, to_rotation, progress).to_radians();
+    for entry in [rotation.cos(), -rotation.sin(), rotation.sin(), rotation.cos()] {
+        let hex = float_to_hex(round2(entry).abs());
+        parts.push(match hex.as_str() {
+            "" => "0".to_string(),
+            h if h.starts_with('.') => format!("0{}", h).to_lowercase(),
+            h => h.to_string(),
+        });
+    }
+    parts.extend(["0".to_string(), "0".to_string()]);
+
+    parts.concat().replace(['.', '-'], "")
+}
+
+fn scale(value: f64, min: f64, max: f64) -> f64 {
+    value * (max - min) / 255.0 + min
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn interpolate(from: f64, to: f64, progress: f64) -> f64 {
+    from * (1.0 - progress) + to * progress
+}
+
+/// Rounds like JavaScript's `Math.round`: halves go up.
+fn js_round(value: f64) -> f64 {
+    (value + 0.5).floor()
+}
+
+/// The y value of the cubic Bézier easing curve `curves` at x = `time`.
+fn cubic_value(curves: &[f64], time: f64) -> f64 {
+    let [x1, y1, x2, y2] = [curves[0], curves[1], curves[2], curves[3]];
+    let bezier = |a: f64, b: f64, m: f64| 3.0 * a * (1.0 - m) * (1.0 - m) * m + 3.0 * b * (1.0 - m) * m * m + m * m * m;
+
+    if time <= 0.0 {
+        let gradient = if x1 > 0.0 {
+            y1 / x1
+        } else if y1 == 0.0 && x2 > 0.0 {
+            y2 / x2
+        } else {
+            0.0
+        };
+        return gradient * time;
+    }
+    if time >= 1.0 {
+        let gradient = if x2 < 1.0 {
+            (y2 - 1.0) / (x2 - 1.0)
+        } else if x2 == 1.0 && x1 < 1.0 {
+            (y1 - 1.0) / (x1 - 1.0)
+        } else {
+            0.0
+        };
+        return 1.0 + gradient * (time - 1.0);
+    }
+
+    // Bisect for the curve parameter whose x is `time`; 64 halvings exhaust f64 precision.
+    let (mut start, mut end, mut mid) = (0.0, 1.0, 0.0);
+    for _ in 0..64 {
+        mid = (start + end) / 2.0;
+        let estimate = bezier(x1, x2, mid);
+        if (time - estimate).abs() < 0.00001 {
+            break;
+        }
+        if estimate < time {
+            start = mid;
+        } else {
+            end = mid;
+        }
+    }
+    bezier(y1, y2, mid)
+}
+
+/// Formats a non-negative number in hexadecimal with its fraction, e.g. `0.5` as `.8`.
+fn float_to_hex(mut value: f64) -> String {
+    let mut digits = String::new();
+    let mut quotient = value.trunc();
+    let mut fraction = value - quotient;
+
+    while quotient > 0.0 {
+        quotient = (value / 16.0).trunc();
+        let remainder = (value - quotient * 16.0) as u32;
+        digits.insert(0, char::from_digit(remainder, 16).unwrap_or('0').to_ascii_uppercase());
+        value = quotient;
+    }
+
+    if fraction == 0.0 {
+        return digits;
+    }
+
+    digits.push('.');
+    while fraction > 0.0 {
+        fraction *= 16.0;
+        let integer = fraction.trunc();
+        fraction -= integer;
+        digits.push(char::from_digit(integer as u32, 16).unwrap_or('0').to_ascii_uppercase());
+    }
+    digits
+}
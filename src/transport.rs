@@ -0,0 +1,306 @@
+//! HTTP transport abstraction.
+//!
+//! Every request made by the crate goes through a [`Transport`]. The default
+//! [`ReqwestTransport`] talks to the network; [`MockTransport`] serves canned
+//! responses from memory so that code built on xplore can be tested without
+//! live credentials. Host names are taken from [`BaseUrls`], which can point the
+//! crate at a local stub server instead of X.
+
+use {
+    crate::{Result, XploreError},
+    async_trait::async_trait,
+    reqwest::{
+        header::HeaderMap,
+        multipart::{Form, Part},
+        Client, Method, StatusCode,
+    },
+    serde_json::Value,
+    std::sync::Mutex,
+};
+
+/// Base URLs for each of the hosts the crate talks to.
+#[derive(Debug, Clone)]
+pub struct BaseUrls {
+    /// REST endpoints (`/1.1/...`, `/2/...`).
+    ///
+    /// Default: `https://api.x.com`
+    pub api: String,
+
+    /// Media upload endpoints.
+    ///
+    /// Default: `https://upload.x.com`
+    pub upload: String,
+
+    /// GraphQL operations, `SearchTimeline` included.
+    ///
+    /// Default: `https://x.com/i/api/graphql`
+    pub graphql: String,
+
+    /// The web client, used to discover current GraphQL query IDs.
+    ///
+    /// Default: `https://x.com`
+    pub web: String,
+
+    /// Card creation, used to attach polls to tweets.
+    ///
+    /// Default: `https://caps.x.com`
+    pub caps: String,
+}
+
+impl Default for BaseUrls {
+    fn default() -> Self {
+        Self::for_domain("x.com")
+    }
+}
+
+impl BaseUrls {
+    /// The hosts X serves on `domain`, e.g. `twitter.com`.
+    pub fn for_domain(domain: &str) -> Self {
+        Self {
+            api: format!("https://api.{}", domain),
+            upload: format!("https://upload.{}", domain),
+            graphql: format!("https://{}/i/api/graphql", domain),
+            web: format!("https://{}", domain),
+            caps: format!("https://caps.{}", domain),
+        }
+    }
+
+    /// Uses `base` for every host, e.g. `http://127.0.0.1:8080`.
+    pub fn single(base: &str) -> Self {
+        let base = base.trim_end_matches('/');
+        Self {
+            api: base.to_string(),
+            upload: base.to_string(),
+            graphql: format!("{}/i/api/graphql", base),
+            web: base.to_string(),
+            caps: base.to_string(),
+        }
+    }
+
+    /// The web client's origin, e.g. `https://x.com`, as sent in `Referer` and used in permalinks.
+    pub fn origin(&self) -> &str {
+        self.web.trim_end_matches('/')
+    }
+
+    pub fn api_url(&self, path: &str) -> String {
+        join(&self.api, path)
+    }
+
+    pub fn upload_url(&self, path: &str) -> String {
+        join(&self.upload, path)
+    }
+
+    pub fn graphql_url(&self, path: &str) -> String {
+        join(&self.graphql, path)
+    }
+
+    pub fn web_url(&self, path: &str) -> String {
+        join(&self.web, path)
+    }
+
+    pub fn caps_url(&self, path: &str) -> String {
+        join(&self.caps, path)
+    }
+}
+
+fn join(base: &str, path: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), path.trim_start_matches('/'))
+}
+
+/// The body of an outgoing request.
+#[derive(Debug, Clone)]
+pub enum RequestBody {
+    Empty,
+    Json(Value),
+    Form(Vec<(String, String)>),
+    Multipart(Vec<MultipartField>),
+}
+
+#[derive(Debug, Clone)]
+pub struct MultipartField {
+    pub name: String,
+    pub value: MultipartValue,
+}
+
+#[derive(Debug, Clone)]
+pub enum MultipartValue {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl MultipartField {
+    pub fn text(name: &str, value: impl Into<String>) -> Self {
+        Self { name: name.to_string(), value: MultipartValue::Text(value.into()) }
+    }
+
+    pub fn bytes(name: &str, value: Vec<u8>) -> Self {
+        Self { name: name.to_string(), value: MultipartValue::Bytes(value) }
+    }
+}
+
+/// A fully prepared request, headers included.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: RequestBody,
+}
+
+/// A response as seen by the rest of the crate.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+/// Sends requests on behalf of the client.
+///
+/// Implementations should only return an error when no HTTP response could be
+/// obtained at all; non-2xx responses are returned as `Ok` and interpreted by the caller.
+#[async_trait]
+pub trait Transport: Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse>;
+}
+
+/// The default transport, backed by `reqwest`.
+pub struct ReqwestTransport {
+    client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl Transport for ReqwestTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let mut builder = self.client.request(request.method, &request.url).headers(request.headers);
+
+        builder = match request.body {
+            RequestBody::Empty => builder,
+            RequestBody::Json(json) => builder.json(&json),
+            RequestBody::Form(form) => builder.form(&form),
+            RequestBody::Multipart(fields) => {
+                let form = fields.into_iter().fold(Form::new(), |form, field| match field.value {
+                    MultipartValue::Text(text) => form.text(field.name, text),
+                    MultipartValue::Bytes(bytes) => form.part(field.name, Part::bytes(bytes)),
+                });
+                builder.multipart(form)
+            }
+        };
+
+        let response = builder.send().await?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await?;
+
+        Ok(HttpResponse { status, headers, body })
+    }
+}
+
+struct MockRoute {
+    method: Method,
+    path: String,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: String,
+    once: bool,
+}
+
+/// An in-memory transport that serves canned responses.
+///
+/// Routes are matched on the HTTP method and on the end of the URL path, so
+/// `"/TweetDetail"` matches any query ID. The first matching route in registration
+/// order answers; a route added with [`MockTransport::with_response_once`] answers a
+/// single time and then lets later routes answer. Every request received is kept and
+/// can be inspected with [`MockTransport::requests`].
+///
+/// ```no_run
+/// # use std::sync::Arc;
+/// # use reqwest::Method;
+/// # use serde_json::json;
+/// # use xplore::{transport::MockTransport, Xplore, XploreOptions};
+/// # async fn run() -> xplore::Result<()> {
+/// let transport = MockTransport::new().with_json(Method::GET, "/UserByScreenName", json!({ "data": {} }));
+/// let options = XploreOptions { transport: Some(Arc::new(transport)), ..Default::default() };
+/// let xplore = Xplore::new(Some(options)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct MockTransport {
+    routes: Mutex<Vec<MockRoute>>,
+    requests: Mutex<Vec<HttpRequest>>,
+}
+
+impl MockTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves `body` with status 200 for requests matching `method` and `path`.
+    pub fn with_json(self, method: Method, path: &str, body: Value) -> Self {
+        self.with_response(method, path, StatusCode::OK, body.to_string())
+    }
+
+    /// Serves `body` with the given status for requests matching `method` and `path`.
+    pub fn with_response(self, method: Method, path: &str, status: StatusCode, body: impl Into<String>) -> Self {
+        self.route(method, path, status, body.into(), false)
+    }
+
+    /// Serves `body` with the given status for the next request matching `method` and `path` only,
+    /// e.g. a 503 before a successful retry.
+    pub fn with_response_once(self, method: Method, path: &str, status: StatusCode, body: impl Into<String>) -> Self {
+        self.route(method, path, status, body.into(), true)
+    }
+
+    /// Adds a header to the response of the route registered last, e.g. `x-rate-limit-remaining`.
+    pub fn with_header(self, name: &'static str, value: &str) -> Self {
+        if let Some(route) = self.routes.lock().unwrap().last_mut() {
+            route.headers.insert(name, value.parse().expect("invalid mock header value"));
+        }
+        self
+    }
+
+    fn route(self, method: Method, path: &str, status: StatusCode, body: String, once: bool) -> Self {
+        let route = MockRoute { method, path: path.to_string(), status, headers: HeaderMap::new(), body, once };
+        self.routes.lock().unwrap().push(route);
+        self
+    }
+
+    /// Returns the requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Transport for MockTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let path = url::Url::parse(&request.url)
+            .map(|url| url.path().to_string())
+            .map_err(|e| XploreError::Api(format!("Invalid URL {}: {}", request.url, e)))?;
+
+        let response = {
+            let mut routes = self.routes.lock().unwrap();
+            routes.iter().position(|route| route.method == request.method && path.ends_with(&route.path)).map(|index| {
+                let route = &routes[index];
+                let response =
+                    HttpResponse { status: route.status, headers: route.headers.clone(), body: route.body.clone() };
+                if route.once {
+                    routes.remove(index);
+                }
+                response
+            })
+        };
+
+        let method = request.method.clone();
+        self.requests.lock().unwrap().push(request);
+
+        response.ok_or_else(|| XploreError::Api(format!("No mock response registered for {} {}", method, path)))
+    }
+}
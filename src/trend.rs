@@ -10,7 +10,7 @@ use {
 };
 
 // TODO: debug???
-pub async fn get_trend(auth: &mut UserAuth) -> Result<Vec<String>> {
+pub async fn get_trend(auth: &UserAuth) -> Result<Vec<String>> {
     let params = json!(
         {
             "count": "20",
@@ -19,7 +19,7 @@ pub async fn get_trend(auth: &mut UserAuth) -> Result<Vec<String>> {
             "entity_tokens": "false",
         }
     );
-    let url = format!("https://api.x.com/2/guide.json?{}", urlencoding::encode(&params.to_string()));
+    let url = format!("{}?{}", auth.urls.api_url("2/guide.json"), urlencoding::encode(&params.to_string()));
 
     let (response, _) = api::send_request::<TimelineV1>(auth, &url, Method::GET, None).await?;
 
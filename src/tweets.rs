@@ -2,17 +2,33 @@ use {
     crate::{
         api,
         endpoints::Endpoints,
+        text::weighted_length,
         timeline_v2::{
-            parse_threaded_conversation, parse_timeline_tweets_v2, QueryTweetsResponse, ThreadedConversation,
+            parse_poll_card, parse_result, parse_threaded_conversation, parse_timeline_tweets_v2, QueryTweetsResponse,
+            ThreadedConversation, TweetResult,
         },
+        transport::MultipartField,
         Result, Xplore, XploreError,
     },
     chrono::{DateTime, Utc},
     reqwest::Method,
-    serde::{Deserialize, Serialize},
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
     serde_json::{json, Value},
 };
 
+/// Longest weighted length `CreateTweet` accepts; longer text is posted with `CreateNoteTweet`.
+pub const MAX_TWEET_LENGTH: usize = 280;
+
+/// Longest weighted length `CreateNoteTweet` accepts, for accounts with X Premium.
+pub const MAX_LONG_TWEET_LENGTH: usize = 25_000;
+
+/// Longest label of a poll choice.
+pub const MAX_POLL_CHOICE_LENGTH: usize = 25;
+
+/// Shortest and longest time a poll can stay open, in minutes.
+pub const MIN_POLL_MINUTES: u32 = 5;
+pub const MAX_POLL_MINUTES: u32 = 7 * 24 * 60;
+
 pub const DEFAULT_EXPANSIONS: &[&str] = &[
     "attachments.poll_ids",
     "attachments.media_keys",
@@ -110,6 +126,44 @@ pub struct BoundingBox {
     pub coordinates: Option<Vec<Vec<Vec<f64>>>>,
 }
 
+/// A poll to attach to a new tweet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewPoll {
+    /// Two to four choices of at most `MAX_POLL_CHOICE_LENGTH` characters each.
+    pub choices: Vec<String>,
+    /// How long the poll stays open, from 5 minutes to 7 days.
+    pub duration_minutes: u32,
+}
+
+impl NewPoll {
+    pub fn new<S: Into<String>>(choices: impl IntoIterator<Item = S>, duration_minutes: u32) -> Self {
+        Self { choices: choices.into_iter().map(Into::into).collect(), duration_minutes }
+    }
+
+    /// Checks the poll against X's limits, so a bad poll fails before anything is sent.
+    pub fn validate(&self) -> Result<()> {
+        if !(2..=4).contains(&self.choices.len()) {
+            return Err(XploreError::Config(format!("A poll needs 2 to 4 choices, got {}", self.choices.len())));
+        }
+        for choice in &self.choices {
+            let length = choice.chars().count();
+            if length == 0 || length > MAX_POLL_CHOICE_LENGTH {
+                return Err(XploreError::Config(format!(
+                    "Poll choice {:?} must be 1 to {} characters long",
+                    choice, MAX_POLL_CHOICE_LENGTH
+                )));
+            }
+        }
+        if !(MIN_POLL_MINUTES..=MAX_POLL_MINUTES).contains(&self.duration_minutes) {
+            return Err(XploreError::Config(format!(
+                "A poll must last {} to {} minutes, got {}",
+                MIN_POLL_MINUTES, MAX_POLL_MINUTES, self.duration_minutes
+            )));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PollV2 {
     pub id: Option<String>,
@@ -125,144 +179,73 @@ pub struct PollOption {
     pub votes: Option<i32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetResponse {
-    data: TweetData,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetData {
-    create_tweet: TweetCreateResult,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetCreateResult {
-    tweet_results: TweetResultWrapper,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetResultWrapper {
-    result: TweetResult,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetResult {
-    core: TweetCore,
-    edit_control: TweetEditControl,
-    is_translatable: bool,
-    legacy: TweetLegacy,
-    rest_id: String,
-    source: String,
-    unmention_data: serde_json::Value,
-    unmention_info: serde_json::Value,
-    views: TweetViews,
+/// The tweet X created, as returned by `CreateTweet` and `CreateNoteTweet`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostedTweet {
+    pub id: String,
+    /// Link to the tweet on the client's host, e.g. `https://x.com/jack/status/20`.
+    pub permalink: String,
+    pub tweet: Tweet,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetCore {
-    user_results: UserResultWrapper,
+/// One tweet of a thread posted with `post_thread`.
+#[derive(Debug, Clone, Default)]
+pub struct ThreadPart {
+    pub text: String,
+    /// Media files and their MIME types, as for `post_tweet`.
+    pub media: Option<Vec<(Vec<u8>, String)>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct UserResultWrapper {
-    result: UserResult,
-}
+impl ThreadPart {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), media: None }
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct UserResult {
-    __typename: String,
-    affiliates_highlighted_label: serde_json::Value,
-    has_graduated_access: bool,
-    id: String,
-    is_blue_verified: bool,
-    legacy: UserLegacy,
-    profile_image_shape: String,
-    rest_id: String,
+    pub fn with_media(mut self, data: Vec<u8>, media_type: &str) -> Self {
+        self.media.get_or_insert_with(Vec::new).push((data, media_type.to_string()));
+        self
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct UserLegacy {
-    can_dm: bool,
-    can_media_tag: bool,
-    created_at: String,
-    default_profile: bool,
-    default_profile_image: bool,
-    description: String,
-    entities: UserEntities,
-    fast_followers_count: u64,
-    favourites_count: u64,
-    followers_count: u64,
-    friends_count: u64,
-    has_custom_timelines: bool,
-    is_translator: bool,
-    listed_count: u64,
-    location: String,
-    media_count: u64,
-    name: String,
-    needs_phone_verification: bool,
-    normal_followers_count: u64,
-    pinned_tweet_ids_str: Vec<String>,
-    possibly_sensitive: bool,
-    profile_image_url_https: String,
-    profile_interstitial_type: String,
-    screen_name: String,
-    statuses_count: u64,
-    translator_type: String,
-    verified: bool,
-    want_retweets: bool,
-    withheld_in_countries: Vec<String>,
+impl From<&str> for ThreadPart {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct UserEntities {
-    description: UserDescriptionEntities,
+impl From<String> for ThreadPart {
+    fn from(text: String) -> Self {
+        Self::new(text)
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct UserDescriptionEntities {
-    urls: Vec<serde_json::Value>,
+/// TweetResultByRestId
+#[derive(Debug, Deserialize)]
+struct TweetResultByRestIdResponse {
+    data: TweetResultByRestIdData,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetEditControl {
-    edit_tweet_ids: Vec<String>,
-    editable_until_msecs: String,
-    edits_remaining: String,
-    is_edit_eligible: bool,
+#[derive(Debug, Deserialize)]
+struct TweetResultByRestIdData {
+    #[serde(rename = "tweetResult")]
+    tweet_result: TweetResult,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetLegacy {
-    bookmark_count: u64,
-    bookmarked: bool,
-    conversation_id_str: String,
-    created_at: String,
-    display_text_range: Vec<u64>,
-    entities: TweetEntities,
-    favorite_count: u64,
-    favorited: bool,
-    full_text: String,
-    id_str: String,
-    is_quote_status: bool,
-    lang: String,
-    quote_count: u64,
-    reply_count: u64,
-    retweet_count: u64,
-    retweeted: bool,
-    user_id_str: String,
+/// CreateTweet / CreateNoteTweet
+#[derive(Debug, Deserialize)]
+struct CreateTweetResponse {
+    data: CreateTweetData,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetEntities {
-    hashtags: Vec<serde_json::Value>,
-    symbols: Vec<serde_json::Value>,
-    urls: Vec<serde_json::Value>,
-    user_mentions: Vec<serde_json::Value>,
+#[derive(Debug, Deserialize)]
+struct CreateTweetData {
+    #[serde(alias = "notetweet_create")]
+    create_tweet: CreateTweetResult,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct TweetViews {
-    state: String,
+#[derive(Debug, Deserialize)]
+struct CreateTweetResult {
+    tweet_results: TweetResult,
 }
 
 /// Retweet
@@ -297,53 +280,147 @@ pub struct TweetRetweetLegacy {
     pub full_text: String,
 }
 
+/// Unretweet
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TweetUnretweetResponse {
+    pub data: TweetUnretweetData,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TweetUnretweetData {
+    pub unretweet: TweetUnretweetResult,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TweetUnretweetResult {
+    /// The retweeted tweet; X leaves `result` out when there was no retweet to undo.
+    pub source_tweet_results: TweetUnretweetSourceWrapper,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TweetUnretweetSourceWrapper {
+    pub result: Option<TweetRetweetResult>,
+}
+
+/// Whether a like, retweet, delete, unlike, unretweet, bookmark or pin changed anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TweetActionOutcome {
+    /// X made the change.
+    Applied,
+    /// The tweet was already in the requested state, e.g. unliking a tweet that was not liked.
+    AlreadyApplied,
+}
+
 pub async fn post_tweet(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     text: &str,
     reply_to: Option<&str>,
     media_data: Option<Vec<(Vec<u8>, String)>>,
-) -> Result<Value> {
-    create_tweet_request(xplore, text, reply_to, media_data).await
+) -> Result<PostedTweet> {
+    publish(xplore, text, reply_to, None, None, media_data).await
 }
 
-pub async fn read_tweet(xplore: &mut Xplore, tweet_id: &str) -> Result<Tweet> {
+pub async fn read_tweet(xplore: &Xplore, tweet_id: &str) -> Result<Tweet> {
     get_tweet(xplore, tweet_id).await
 }
 
-pub async fn retweet(xplore: &mut Xplore, tweet_id: &str) -> Result<TweetRetweetResponse> {
-    let value = retweet_(xplore, tweet_id).await?;
-    let res = serde_json::from_value(value)?;
+/// Retweets a tweet. Retweeting a tweet the account has already retweeted succeeds.
+pub async fn retweet(xplore: &Xplore, tweet_id: &str) -> Result<TweetActionOutcome> {
+    let variables = json!({ "tweet_id": tweet_id, "dark_request": false });
+    let response: Option<TweetRetweetResponse> =
+        tweet_mutation(xplore, "CreateRetweet", variables, is_already_applied).await?;
 
-    Ok(res)
+    Ok(outcome(response.is_some()))
 }
 
-pub async fn like_tweet(xplore: &mut Xplore, tweet_id: &str) -> Result<Value> {
-    let value = like_tweet_(xplore, tweet_id).await?;
-    Ok(value)
+/// Likes a tweet. Liking a tweet the account already likes succeeds.
+pub async fn like_tweet(xplore: &Xplore, tweet_id: &str) -> Result<TweetActionOutcome> {
+    let value: Option<Value> =
+        tweet_mutation(xplore, "FavoriteTweet", json!({ "tweet_id": tweet_id }), is_already_applied).await?;
+
+    Ok(outcome(value.is_some()))
 }
 
-pub async fn get_user_tweets(xplore: &mut Xplore, user_id: &str, limit: usize) -> Result<Vec<Tweet>> {
-    let url = format!("https://api.twitter.com/2/users/{}/tweets", user_id);
-    let body = json!({
-        "max_results": limit,
-        "tweet.fields": "created_at,author_id,conversation_id,public_metrics"
-    });
+/// Deletes one of the account's tweets. A tweet that no longer exists counts as already deleted,
+/// so a mistyped ID is reported as `AlreadyApplied` too.
+pub async fn delete_tweet(xplore: &Xplore, tweet_id: &str) -> Result<TweetActionOutcome> {
+    let variables = json!({ "tweet_id": tweet_id, "dark_request": false });
+    let value: Option<Value> = tweet_mutation(xplore, "DeleteTweet", variables, is_not_found).await?;
+
+    Ok(outcome(value.is_some()))
+}
+
+/// Unlikes a tweet. A tweet that does not exist is `XploreError::NotFound`, not a no-op.
+pub async fn unlike_tweet(xplore: &Xplore, tweet_id: &str) -> Result<TweetActionOutcome> {
+    let value: Option<Value> =
+        tweet_mutation(xplore, "UnfavoriteTweet", json!({ "tweet_id": tweet_id }), is_already_applied).await?;
+
+    Ok(outcome(value.is_some()))
+}
+
+/// Undoes a retweet. X answers without the source tweet when there was no retweet to undo.
+pub async fn unretweet(xplore: &Xplore, tweet_id: &str) -> Result<TweetActionOutcome> {
+    let variables = json!({ "source_tweet_id": tweet_id, "dark_request": false });
+    let response: Option<TweetUnretweetResponse> =
+        tweet_mutation(xplore, "DeleteRetweet", variables, is_already_applied).await?;
+
+    Ok(outcome(response.is_some_and(|r| r.data.unretweet.source_tweet_results.result.is_some())))
+}
+
+pub async fn bookmark_tweet(xplore: &Xplore, tweet_id: &str) -> Result<TweetActionOutcome> {
+    let value: Option<Value> =
+        tweet_mutation(xplore, "CreateBookmark", json!({ "tweet_id": tweet_id }), is_already_applied).await?;
+
+    Ok(outcome(value.is_some()))
+}
+
+pub async fn unbookmark_tweet(xplore: &Xplore, tweet_id: &str) -> Result<TweetActionOutcome> {
+    let value: Option<Value> =
+        tweet_mutation(xplore, "DeleteBookmark", json!({ "tweet_id": tweet_id }), is_already_applied).await?;
+
+    Ok(outcome(value.is_some()))
+}
+
+/// Pins a tweet to the account's profile, replacing any pinned tweet. Pinning the pinned tweet
+/// again succeeds.
+pub async fn pin_tweet(xplore: &Xplore, tweet_id: &str) -> Result<TweetActionOutcome> {
+    set_pinned(xplore, "1.1/account/pin_tweet.json", tweet_id).await?;
+    Ok(TweetActionOutcome::Applied)
+}
+
+/// Unpins a tweet from the account's profile. A tweet that does not exist is
+/// `XploreError::NotFound`.
+pub async fn unpin_tweet(xplore: &Xplore, tweet_id: &str) -> Result<TweetActionOutcome> {
+    match set_pinned(xplore, "1.1/account/unpin_tweet.json", tweet_id).await {
+        Ok(()) => Ok(TweetActionOutcome::Applied),
+        Err(e) if is_already_applied(&e) => Ok(TweetActionOutcome::AlreadyApplied),
+        Err(e) => Err(e),
+    }
+}
 
-    let (v, _) = api::send_request::<Vec<Tweet>>(&mut xplore.auth, &url, Method::GET, Some(body)).await?;
+pub async fn get_user_tweets(xplore: &Xplore, user_id: &str, limit: usize) -> Result<Vec<Tweet>> {
+    let url = format!(
+        "{}?max_results={}&tweet.fields={}",
+        xplore.auth.urls.api_url(&format!("2/users/{}/tweets", user_id)),
+        limit,
+        urlencoding::encode("created_at,author_id,conversation_id,public_metrics")
+    );
+
+    let (v, _) = api::send_request::<Vec<Tweet>>(&xplore.auth, &url, Method::GET, None).await?;
     Ok(v)
 }
 
 pub async fn send_quote_tweet(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     text: &str,
     quoted_tweet_id: &str,
     media_data: Option<Vec<(Vec<u8>, String)>>,
-) -> Result<Value> {
-    create_quote_tweet(xplore, text, quoted_tweet_id, media_data).await
+) -> Result<PostedTweet> {
+    publish(xplore, text, None, Some(quoted_tweet_id), None, media_data).await
 }
 
 pub async fn fetch_tweets_and_replies(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     username: &str,
     max_tweets: i32,
     cursor: Option<&str>,
@@ -352,7 +429,7 @@ pub async fn fetch_tweets_and_replies(
 }
 
 pub async fn fetch_tweets_and_replies_by_user_id(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     max_tweets: i32,
     cursor: Option<&str>,
@@ -360,28 +437,73 @@ pub async fn fetch_tweets_and_replies_by_user_id(
     fetch_tweets_and_replies_by_user_id_(xplore, user_id, max_tweets, cursor).await
 }
 
-pub async fn fetch_list_tweets(
-    xplore: &mut Xplore,
-    list_id: &str,
-    max_tweets: i32,
-    cursor: Option<&str>,
-) -> Result<Value> {
+pub async fn fetch_list_tweets(xplore: &Xplore, list_id: &str, max_tweets: i32, cursor: Option<&str>) -> Result<Value> {
     fetch_list_tweets_(xplore, list_id, max_tweets, cursor).await
 }
 
 pub async fn create_long_tweet(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     text: &str,
     reply_to: Option<&str>,
     media_ids: Option<Vec<String>>,
-) -> Result<Value> {
-    create_long_tweet_(xplore, text, reply_to, media_ids).await
+) -> Result<PostedTweet> {
+    check_length(text, MAX_LONG_TWEET_LENGTH)?;
+
+    let value = create_long_tweet_(xplore, text, reply_to, media_ids).await?;
+    parse_posted_tweet(xplore, value)
 }
 
-////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+/// Posts `parts` as a thread, each replying to the one before.
 ///
+/// Parts longer than `MAX_TWEET_LENGTH` are posted as long tweets; a part over
+/// `MAX_LONG_TWEET_LENGTH` fails the thread before anything is posted. If a part fails, the error
+/// is `XploreError::ThreadFailed` with the IDs posted so far; with `rollback` they are deleted again.
+pub async fn post_thread(xplore: &Xplore, parts: Vec<ThreadPart>, rollback: bool) -> Result<Vec<PostedTweet>> {
+    if parts.is_empty() {
+        return Err(XploreError::Config("A thread needs at least one part".into()));
+    }
+    for part in &parts {
+        check_length(&part.text, MAX_LONG_TWEET_LENGTH)?;
+    }
 
-pub async fn fetch_tweets(xplore: &mut Xplore, user_id: &str, max_tweets: i32, cursor: Option<&str>) -> Result<Value> {
+    let mut posted: Vec<PostedTweet> = Vec::with_capacity(parts.len());
+    for part in parts {
+        let reply_to = posted.last().map(|tweet| tweet.id.clone());
+
+        match post_thread_part(xplore, part, reply_to.as_deref()).await {
+            Ok(tweet) => posted.push(tweet),
+            Err(e) => {
+                let posted: Vec<String> = posted.into_iter().map(|tweet| tweet.id).collect();
+                let deleted = if rollback { delete_thread(xplore, &posted).await } else { Vec::new() };
+                return Err(XploreError::ThreadFailed { posted, deleted, source: Box::new(e) });
+            }
+        }
+    }
+
+    Ok(posted)
+}
+
+async fn post_thread_part(xplore: &Xplore, part: ThreadPart, reply_to: Option<&str>) -> Result<PostedTweet> {
+    publish(xplore, &part.text, reply_to, None, None, part.media).await
+}
+
+/// Deletes the tweets of a failed thread, newest first so no reply is left without its parent,
+/// returning the IDs that are gone in posted order.
+async fn delete_thread(xplore: &Xplore, ids: &[String]) -> Vec<String> {
+    let mut deleted = Vec::with_capacity(ids.len());
+    for id in ids.iter().rev() {
+        match delete_tweet(xplore, id).await {
+            Ok(_) => deleted.push(id.clone()),
+            Err(e) => tracing::warn!(tweet_id = %id, error = %e, "Failed to delete a tweet of a failed thread"),
+        }
+    }
+    deleted.reverse();
+    deleted
+}
+
+////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////////
+
+pub async fn fetch_tweets(xplore: &Xplore, user_id: &str, max_tweets: i32, cursor: Option<&str>) -> Result<Value> {
     let mut variables = json!({
         "userId": user_id,
         "count": max_tweets.min(200),
@@ -392,184 +514,173 @@ pub async fn fetch_tweets(xplore: &mut Xplore, user_id: &str, max_tweets: i32, c
         variables["cursor"] = json!(cursor_val);
     }
 
-    let url = "https://twitter.com/i/api/graphql/YNXM2DGuE2Sff6a2JD3Ztw/UserTweets";
-    let method = Method::GET;
-    let body = Some(json!({
-        "variables": variables,
-        "features": get_default_features()
-    }));
-    let (value, _) = api::send_request(&mut xplore.auth, url, method, body).await?;
+    let endpoint = xplore.auth.operations.endpoint("UserTweets")?.with_variables(variables);
+    let (value, _) = api::request_endpoint(&xplore.auth, &endpoint).await?;
 
     Ok(value)
 }
 
 pub async fn fetch_tweets_and_replies_(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     username: &str,
     max_tweets: i32,
     cursor: Option<&str>,
 ) -> Result<QueryTweetsResponse> {
     let user_id = xplore.get_user_id(username).await?;
 
-    let endpoint = Endpoints::user_tweets_and_replies(&user_id, max_tweets.min(40), cursor);
-    let url = &endpoint.to_request_url();
-    let (value, _) = api::send_request(&mut xplore.auth, url, Method::GET, None).await?;
+    let endpoint = Endpoints::user_tweets_and_replies(&xplore.auth.operations, &user_id, max_tweets.min(40), cursor)?;
+    let (value, _) = api::request_endpoint(&xplore.auth, &endpoint).await?;
 
-    let parsed_response = parse_timeline_tweets_v2(&value);
+    let parsed_response = parse_timeline_tweets_v2(&value, xplore.auth.urls.origin());
     Ok(parsed_response)
 }
 
 pub async fn fetch_tweets_and_replies_by_user_id_(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     max_tweets: i32,
     cursor: Option<&str>,
 ) -> Result<QueryTweetsResponse> {
-    let endpoint = Endpoints::user_tweets_and_replies(user_id, max_tweets.min(40), cursor);
-    let url = &endpoint.to_request_url();
-    let method = Method::GET;
+    let endpoint = Endpoints::user_tweets_and_replies(&xplore.auth.operations, user_id, max_tweets.min(40), cursor)?;
 
-    let (value, _headers) = api::send_request(&mut xplore.auth, url, method, None).await?;
+    let (value, _headers) = api::request_endpoint(&xplore.auth, &endpoint).await?;
 
-    let parsed_response = parse_timeline_tweets_v2(&value);
+    let parsed_response = parse_timeline_tweets_v2(&value, xplore.auth.urls.origin());
     Ok(parsed_response)
 }
 
 pub async fn fetch_list_tweets_(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     list_id: &str,
     max_tweets: i32,
     cursor: Option<&str>,
 ) -> Result<Value> {
-    let mut variables = json!({
-        "listId": list_id,
-        "count": max_tweets.min(200)
-    });
-
-    if let Some(cursor_val) = cursor {
-        variables["cursor"] = json!(cursor_val);
-    }
-
-    let url = "https://twitter.com/i/api/graphql/LFKj1wqHNTsEJ4Oq7TzaNA/ListLatestTweetsTimeline";
-    let body = Some(json!({
-        "variables": variables,
-        "features": get_default_features()
-    }));
-
-    let (value, _) = api::send_request(&mut xplore.auth, url, Method::GET, body).await?;
+    let endpoint = Endpoints::list_latest_tweets(&xplore.auth.operations, list_id, max_tweets.min(200), cursor)?;
+    let (value, _) = api::request_endpoint(&xplore.auth, &endpoint).await?;
 
     Ok(value)
 }
 
 pub async fn create_quote_tweet(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     text: &str,
     quoted_tweet_id: &str,
     media_data: Option<Vec<(Vec<u8>, String)>>,
 ) -> Result<Value> {
-    let mut variables = json!({
-        "tweet_text": text,
-        "dark_request": false,
-        "attachment_url": format!("https://twitter.com/twitter/status/{}", quoted_tweet_id),
-        "media": {
-            "media_entities": [],
-            "possibly_sensitive": false
-        },
-        "semantic_annotation_ids": []
-    });
+    let media_ids = upload_all(xplore, media_data).await?;
+    let variables = tweet_variables(xplore.auth.urls.origin(), text, None, Some(quoted_tweet_id), &media_ids);
 
-    if let Some(media_files) = media_data {
-        let mut media_entities = Vec::new();
+    let endpoint = xplore.auth.operations.endpoint("CreateTweet")?.with_variables(variables);
+    let (v, _) = api::request_endpoint(&xplore.auth, &endpoint).await?;
 
-        for (file_data, media_type) in media_files {
-            let media_id = upload_media(xplore, file_data, &media_type).await?;
-            media_entities.push(json!({
-                "media_id": media_id,
-                "tagged_users": []
-            }));
+    Ok(v)
+}
+
+/// Parses the tweet returned by `CreateTweet` or `CreateNoteTweet` like any timeline tweet.
+fn parse_posted_tweet(xplore: &Xplore, value: Value) -> Result<PostedTweet> {
+    let response: CreateTweetResponse = serde_json::from_value(value)?;
+    let result = response
+        .data
+        .create_tweet
+        .tweet_results
+        .result
+        .ok_or_else(|| XploreError::InvalidResponse("No tweet in the CreateTweet response".into()))?;
+
+    let parsed = parse_result(&result, xplore.auth.urls.origin());
+    let mut tweet = match parsed.tweet {
+        Some(tweet) => tweet,
+        None => {
+            return Err(parsed.err.unwrap_or_else(|| XploreError::InvalidResponse("Unparsable posted tweet".into())))
         }
+    };
+
+    // The legacy text of a long tweet is cut off; the note holds all of it.
+    if let Some(text) = result
+        .note_tweet
+        .as_ref()
+        .and_then(|n| n.note_tweet_results.as_ref())
+        .and_then(|r| r.result.as_ref())
+        .and_then(|r| r.text.clone())
+    {
+        tweet.text = Some(text);
+    }
 
-        variables["media"]["media_entities"] = json!(media_entities);
+    let id = tweet.id.clone().unwrap_or_default();
+    let permalink = match &tweet.username {
+        Some(username) => format!("{}/{}/status/{}", xplore.auth.urls.origin(), username, id),
+        None => format!("{}/i/web/status/{}", xplore.auth.urls.origin(), id),
+    };
+
+    Ok(PostedTweet { id, permalink, tweet })
+}
+
+/// Sends a mutation on a tweet, returning `None` when X answers with the error `is_noop` picks
+/// out as "nothing to change".
+async fn tweet_mutation<T>(
+    xplore: &Xplore,
+    operation: &str,
+    variables: Value,
+    is_noop: fn(&XploreError) -> bool,
+) -> Result<Option<T>>
+where
+    T: DeserializeOwned,
+{
+    let endpoint = xplore.auth.operations.endpoint(operation)?.with_variables(variables);
+
+    match api::request_endpoint(&xplore.auth, &endpoint).await {
+        Ok((value, _)) => Ok(Some(value)),
+        Err(e) if is_noop(&e) => {
+            tracing::debug!(operation, error = %e, "Tweet already in the requested state");
+            Ok(None)
+        }
+        Err(e) => Err(e),
     }
+}
 
-    let url = "https://twitter.com/i/api/graphql/a1p9RWpkYKBjWv_I3WzS-A/CreateTweet";
-    let body = Some(json!({
-        "variables": variables,
-        "features": create_quote_tweet_features()
-    }));
-    let (v, _) = api::send_request(&mut xplore.auth, url, Method::POST, body).await?;
+async fn set_pinned(xplore: &Xplore, path: &str, tweet_id: &str) -> Result<()> {
+    if !xplore.auth.has_session().await {
+        return Err(XploreError::LoginRequired(path.to_string()));
+    }
 
-    Ok(v)
+    let url = xplore.auth.urls.api_url(path);
+    let form = vec![("id".to_string(), tweet_id.to_string())];
+    let _ = api::request_form::<Value>(&xplore.auth, &url, "/home", form).await?;
+
+    Ok(())
 }
 
-pub async fn like_tweet_(xplore: &mut Xplore, tweet_id: &str) -> Result<Value> {
-    let url = "https://twitter.com/i/api/graphql/lI07N6Otwv1PhnEgXILM7A/FavoriteTweet";
-    let body = Some(json!({
-        "variables": {
-            "tweet_id": tweet_id
-        }
-    }));
+fn outcome(applied: bool) -> TweetActionOutcome {
+    if applied {
+        TweetActionOutcome::Applied
+    } else {
+        TweetActionOutcome::AlreadyApplied
+    }
+}
 
-    let (value, _) = api::send_request(&mut xplore.auth, url, Method::POST, body).await?;
-    Ok(value)
+fn is_not_found(error: &XploreError) -> bool {
+    matches!(error, XploreError::NotFound(_))
 }
 
-pub async fn retweet_(xplore: &mut Xplore, tweet_id: &str) -> Result<Value> {
-    let url = "https://twitter.com/i/api/graphql/ojPdsZsimiJrUGLR1sjUtA/CreateRetweet";
-    let body = Some(json!({
-        "variables": {
-            "tweet_id": tweet_id,
-            "dark_request": false
-        }
-    }));
-    let (value, _) = api::send_request(&mut xplore.auth, url, Method::POST, body).await?;
-    Ok(value)
+fn is_already_applied(error: &XploreError) -> bool {
+    matches!(error, XploreError::AlreadyApplied(_))
 }
 
 pub async fn create_long_tweet_(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     text: &str,
     reply_to: Option<&str>,
     media_ids: Option<Vec<String>>,
 ) -> Result<Value> {
-    let mut variables = json!({
-        "tweet_text": text,
-        "dark_request": false,
-        "media": {
-            "media_entities": [],
-            "possibly_sensitive": false
-        },
-        "semantic_annotation_ids": []
-    });
-
-    if let Some(reply_id) = reply_to {
-        variables["reply"] = json!({
-            "in_reply_to_tweet_id": reply_id
-        });
-    }
+    let variables = tweet_variables(xplore.auth.urls.origin(), text, reply_to, None, &media_ids.unwrap_or_default());
 
-    if let Some(media) = media_ids {
-        variables["media"]["media_entities"] = json!(media
-            .iter()
-            .map(|id| json!({
-                "media_id": id,
-                "tagged_users": []
-            }))
-            .collect::<Vec<_>>());
-    }
-
-    let url = "https://twitter.com/i/api/graphql/YNXM2DGuE2Sff6a2JD3Ztw/CreateNoteTweet";
-    let body = Some(json!({
-        "variables": variables,
-        "features": get_long_tweet_features()
-    }));
-    let (value, _) = api::send_request(&mut xplore.auth, url, Method::POST, body).await?;
+    let endpoint = xplore.auth.operations.endpoint("CreateNoteTweet")?.with_variables(variables);
+    let (value, _) = api::request_endpoint(&xplore.auth, &endpoint).await?;
 
     Ok(value)
 }
 
 pub async fn fetch_liked_tweets(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     max_tweets: i32,
     cursor: Option<&str>,
@@ -584,17 +695,13 @@ pub async fn fetch_liked_tweets(
         variables["cursor"] = json!(cursor_val);
     }
 
-    let url = "https://twitter.com/i/api/graphql/YlkSUg4Czo2Zx7yRqpwDow/Likes";
-    let body = Some(json!({
-        "variables": variables,
-        "features": get_default_features()
-    }));
-    let (value, _) = api::send_request(&mut xplore.auth, url, Method::POST, body).await?;
+    let endpoint = xplore.auth.operations.endpoint("Likes")?.with_variables(variables);
+    let (value, _) = api::request_endpoint(&xplore.auth, &endpoint).await?;
     Ok(value)
 }
 
-pub async fn upload_media(xplore: &mut Xplore, file_data: Vec<u8>, media_type: &str) -> Result<String> {
-    let upload_url = "https://upload.twitter.com/1.1/media/upload.json";
+pub async fn upload_media(xplore: &Xplore, file_data: Vec<u8>, media_type: &str) -> Result<String> {
+    let upload_url = xplore.auth.urls.upload_url("1.1/media/upload.json");
 
     // Check if media is video
     let is_video = media_type.starts_with("video/");
@@ -604,9 +711,9 @@ pub async fn upload_media(xplore: &mut Xplore, file_data: Vec<u8>, media_type: &
         upload_video_in_chunks(xplore, file_data, media_type).await
     } else {
         // Handle image upload directly
-        let form = reqwest::multipart::Form::new().part("media", reqwest::multipart::Part::bytes(file_data));
+        let fields = vec![MultipartField::bytes("media", file_data)];
 
-        let (response, _) = api::request_multipart::<Value>(&mut xplore.auth, upload_url, form).await?;
+        let (response, _) = api::request_multipart::<Value>(&xplore.auth, &upload_url, fields).await?;
 
         response["media_id_string"]
             .as_str()
@@ -615,15 +722,15 @@ pub async fn upload_media(xplore: &mut Xplore, file_data: Vec<u8>, media_type: &
     }
 }
 
-async fn upload_video_in_chunks(xplore: &mut Xplore, file_data: Vec<u8>, media_type: &str) -> Result<String> {
-    let upload_url = "https://upload.twitter.com/1.1/media/upload.json";
+async fn upload_video_in_chunks(xplore: &Xplore, file_data: Vec<u8>, media_type: &str) -> Result<String> {
+    let upload_url = xplore.auth.urls.upload_url("1.1/media/upload.json");
 
     let body = Some(json!({
         "command": "INIT",
         "total_bytes": file_data.len(),
         "media_type": media_type
     }));
-    let (init_response, _) = api::send_request::<Value>(&mut xplore.auth, upload_url, Method::POST, body).await?;
+    let (init_response, _) = api::send_request::<Value>(&xplore.auth, &upload_url, Method::POST, body).await?;
 
     let media_id = init_response["media_id_string"]
         .as_str()
@@ -634,18 +741,19 @@ async fn upload_video_in_chunks(xplore: &mut Xplore, file_data: Vec<u8>, media_t
     let chunk_size = 5 * 1024 * 1024; // 5MB chunks
 
     for (segment_index, chunk) in file_data.chunks(chunk_size).enumerate() {
-        let form = reqwest::multipart::Form::new()
-            .text("command", "APPEND")
-            .text("media_id", media_id.clone())
-            .text("segment_index", segment_index.to_string())
-            .part("media", reqwest::multipart::Part::bytes(chunk.to_vec()));
-
-        let _ = api::request_multipart::<Value>(&mut xplore.auth, upload_url, form).await?;
+        let fields = vec![
+            MultipartField::text("command", "APPEND"),
+            MultipartField::text("media_id", media_id.clone()),
+            MultipartField::text("segment_index", segment_index.to_string()),
+            MultipartField::bytes("media", chunk.to_vec()),
+        ];
+
+        let _ = api::request_multipart::<Value>(&xplore.auth, &upload_url, fields).await?;
     }
 
     // FINALIZE command
     let (finalize_response, _) = api::send_request::<Value>(
-        &mut xplore.auth,
+        &xplore.auth,
         &format!("{}?command=FINALIZE&media_id={}", upload_url, media_id),
         Method::POST,
         None,
@@ -660,8 +768,8 @@ async fn upload_video_in_chunks(xplore: &mut Xplore, file_data: Vec<u8>, media_t
     Ok(media_id)
 }
 
-async fn check_upload_status(xplore: &mut Xplore, media_id: &str) -> Result<()> {
-    let upload_url = "https://upload.twitter.com/1.1/media/upload.json";
+async fn check_upload_status(xplore: &Xplore, media_id: &str) -> Result<()> {
+    let upload_url = xplore.auth.urls.upload_url("1.1/media/upload.json");
 
     for _ in 0..20 {
         // Maximum 20 attempts
@@ -670,7 +778,7 @@ async fn check_upload_status(xplore: &mut Xplore, media_id: &str) -> Result<()>
         let url = &format!("{}?command=STATUS&media_id={}", upload_url, media_id);
         let method = Method::GET;
         let body = None;
-        let (status_response, _) = api::send_request::<Value>(&mut xplore.auth, url, method, body).await?;
+        let (status_response, _) = api::send_request::<Value>(&xplore.auth, url, method, body).await?;
 
         if let Some(processing_info) = status_response.get("processing_info") {
             match processing_info["state"].as_str() {
@@ -684,228 +792,172 @@ async fn check_upload_status(xplore: &mut Xplore, media_id: &str) -> Result<()>
     Err(XploreError::Api("Video processing timeout".into()))
 }
 
-pub async fn get_tweet(xplore: &mut Xplore, id: &str) -> Result<Tweet> {
-    let tweet_detail_request = Endpoints::tweet_detail(id);
-    let url = tweet_detail_request.to_request_url();
+/// Posts a tweet with a poll. The poll is checked before anything is sent, then created as a
+/// card on the caps host and attached to the tweet.
+pub async fn post_poll(xplore: &Xplore, text: &str, poll: &NewPoll, reply_to: Option<&str>) -> Result<PostedTweet> {
+    poll.validate()?;
+    check_length(text, MAX_LONG_TWEET_LENGTH)?;
+
+    let card_uri = create_poll_card(xplore, poll).await?;
+    publish(xplore, text, reply_to, None, Some(&card_uri), None).await
+}
 
-    let (response, _) = api::send_request::<Value>(&mut xplore.auth, &url, Method::GET, None).await?;
+/// Creates the card for a poll `post_poll` has validated and returns its URI, e.g.
+/// `card://1790000000000000003`.
+async fn create_poll_card(xplore: &Xplore, poll: &NewPoll) -> Result<String> {
+    if !xplore.auth.has_session().await {
+        return Err(XploreError::LoginRequired("cards/create".into()));
+    }
+
+    let mut card_data = json!({
+        "twitter:card": format!("poll{}choice_text_only", poll.choices.len()),
+        "twitter:api:api:endpoint": "1",
+        "twitter:long:duration_minutes": poll.duration_minutes,
+    });
+    for (index, choice) in poll.choices.iter().enumerate() {
+        card_data[format!("twitter:string:choice{}_label", index + 1)] = json!(choice);
+    }
+
+    let url = xplore.auth.urls.caps_url("v2/cards/create.json");
+    let form = vec![("card_data".to_string(), card_data.to_string())];
+    let (response, _) = api::request_form::<Value>(&xplore.auth, &url, "/compose/post", form).await?;
+
+    response["card_uri"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| XploreError::InvalidResponse("No card_uri in the card creation response".into()))
+}
+
+/// Fetches the current choices and vote counts of a tweet's poll.
+pub async fn get_poll(xplore: &Xplore, tweet_id: &str) -> Result<PollV2> {
+    let endpoint = Endpoints::tweet_by_rest_id(&xplore.auth.operations, tweet_id)?;
+    let (response, _) = api::request_endpoint::<TweetResultByRestIdResponse>(&xplore.auth, &endpoint).await?;
+
+    let result = response
+        .data
+        .tweet_result
+        .result
+        .ok_or_else(|| XploreError::InvalidResponse(format!("No tweet {} in the response", tweet_id)))?;
+    // Tweets with visibility restrictions wrap the tweet once more.
+    let result = result.tweet.as_deref().unwrap_or(&result);
+
+    result
+        .card
+        .as_ref()
+        .and_then(parse_poll_card)
+        .ok_or_else(|| XploreError::InvalidResponse(format!("Tweet {} has no poll", tweet_id)))
+}
+
+pub async fn get_tweet(xplore: &Xplore, id: &str) -> Result<Tweet> {
+    let tweet_detail_request = Endpoints::tweet_detail(&xplore.auth.operations, id)?;
+
+    let (response, _) = api::request_endpoint::<Value>(&xplore.auth, &tweet_detail_request).await?;
     let data = response.clone();
     let conversation: ThreadedConversation = serde_json::from_value(data)?;
-    let tweets = parse_threaded_conversation(&conversation);
+    let tweets = parse_threaded_conversation(&conversation, xplore.auth.urls.origin());
     tweets.into_iter().next().ok_or_else(|| XploreError::Api("No tweets found".into()))
 }
 
-fn create_tweet_features() -> Value {
-    json!({
-        "interactive_text_enabled": true,
-        "longform_notetweets_inline_media_enabled": false,
-        "responsive_web_text_conversations_enabled": false,
-        "tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled": false,
-        "vibe_api_enabled": false,
-        "rweb_lists_timeline_redesign_enabled": true,
-        "responsive_web_graphql_exclude_directive_enabled": true,
-        "verified_phone_label_enabled": false,
-        "creator_subscriptions_tweet_preview_api_enabled": true,
-        "responsive_web_graphql_timeline_navigation_enabled": true,
-        "responsive_web_graphql_skip_user_profile_image_extensions_enabled": false,
-        "tweetypie_unmention_optimization_enabled": true,
-        "responsive_web_edit_tweet_api_enabled": true,
-        "graphql_is_translatable_rweb_tweet_is_translatable_enabled": true,
-        "view_counts_everywhere_api_enabled": true,
-        "longform_notetweets_consumption_enabled": true,
-        "tweet_awards_web_tipping_enabled": false,
-        "freedom_of_speech_not_reach_fetch_enabled": true,
-        "standardized_nudges_misinfo": true,
-        "longform_notetweets_rich_text_read_enabled": true,
-        "responsive_web_enhance_cards_enabled": false,
-        "subscriptions_verification_info_enabled": true,
-        "subscriptions_verification_info_reason_enabled": true,
-        "subscriptions_verification_info_verified_since_enabled": true,
-        "super_follow_badge_privacy_enabled": false,
-        "super_follow_exclusive_tweet_notifications_enabled": false,
-        "super_follow_tweet_api_enabled": false,
-        "super_follow_user_api_enabled": false,
-        "android_graphql_skip_api_media_color_palette": false,
-        "creator_subscriptions_subscription_count_enabled": false,
-        "blue_business_profile_image_shape_enabled": false,
-        "unified_cards_ad_metadata_container_dynamic_card_content_query_enabled": false,
-        "rweb_video_timestamps_enabled": false,
-        "c9s_tweet_anatomy_moderator_badge_enabled": false,
-        "responsive_web_twitter_article_tweet_consumption_enabled": false
-    })
-}
-
-fn get_default_features() -> Value {
-    json!({
-        "interactive_text_enabled": true,
-        "longform_notetweets_inline_media_enabled": false,
-        "responsive_web_text_conversations_enabled": false,
-        "tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled": false,
-        "vibe_api_enabled": false,
-        "rweb_lists_timeline_redesign_enabled": true,
-        "responsive_web_graphql_exclude_directive_enabled": true,
-        "verified_phone_label_enabled": false,
-        "creator_subscriptions_tweet_preview_api_enabled": true,
-        "responsive_web_graphql_timeline_navigation_enabled": true,
-        "responsive_web_graphql_skip_user_profile_image_extensions_enabled": false,
-        "tweetypie_unmention_optimization_enabled": true,
-        "responsive_web_edit_tweet_api_enabled": true,
-        "graphql_is_translatable_rweb_tweet_is_translatable_enabled": true,
-        "view_counts_everywhere_api_enabled": true,
-        "longform_notetweets_consumption_enabled": true,
-        "tweet_awards_web_tipping_enabled": false,
-        "freedom_of_speech_not_reach_fetch_enabled": true,
-        "standardized_nudges_misinfo": true,
-        "longform_notetweets_rich_text_read_enabled": true,
-        "responsive_web_enhance_cards_enabled": false,
-        "subscriptions_verification_info_enabled": true,
-        "subscriptions_verification_info_reason_enabled": true,
-        "subscriptions_verification_info_verified_since_enabled": true,
-        "super_follow_badge_privacy_enabled": false,
-        "super_follow_exclusive_tweet_notifications_enabled": false,
-        "super_follow_tweet_api_enabled": false,
-        "super_follow_user_api_enabled": false,
-        "android_graphql_skip_api_media_color_palette": false,
-        "creator_subscriptions_subscription_count_enabled": false,
-        "blue_business_profile_image_shape_enabled": false,
-        "unified_cards_ad_metadata_container_dynamic_card_content_query_enabled": false,
-        "rweb_video_timestamps_enabled": true,
-        "c9s_tweet_anatomy_moderator_badge_enabled": true,
-        "responsive_web_twitter_article_tweet_consumption_enabled": false,
-        "creator_subscriptions_quote_tweet_preview_enabled": false,
-        "profile_label_improvements_pcf_label_in_post_enabled": false,
-        "rweb_tipjar_consumption_enabled": true,
-        "articles_preview_enabled": true
-    })
-}
-
-// Helper function for long tweet features
-fn get_long_tweet_features() -> Value {
-    json!({
-        "premium_content_api_read_enabled": false,
-        "communities_web_enable_tweet_community_results_fetch": true,
-        "c9s_tweet_anatomy_moderator_badge_enabled": true,
-        "responsive_web_grok_analyze_button_fetch_trends_enabled": true,
-        "responsive_web_edit_tweet_api_enabled": true,
-        "graphql_is_translatable_rweb_tweet_is_translatable_enabled": true,
-        "view_counts_everywhere_api_enabled": true,
-        "longform_notetweets_consumption_enabled": true,
-        "responsive_web_twitter_article_tweet_consumption_enabled": true,
-        "tweet_awards_web_tipping_enabled": false,
-        "longform_notetweets_rich_text_read_enabled": true,
-        "longform_notetweets_inline_media_enabled": true,
-        "responsive_web_graphql_exclude_directive_enabled": true,
-        "verified_phone_label_enabled": false,
-        "freedom_of_speech_not_reach_fetch_enabled": true,
-        "standardized_nudges_misinfo": true,
-        "tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled": true,
-        "responsive_web_graphql_timeline_navigation_enabled": true,
-        "responsive_web_enhance_cards_enabled": false
-    })
-}
-
 pub async fn create_tweet_request(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     text: &str,
     reply_to: Option<&str>,
     media_data: Option<Vec<(Vec<u8>, String)>>,
 ) -> Result<Value> {
-    // Prepare variables
+    let media_ids = upload_all(xplore, media_data).await?;
+    let variables = tweet_variables(xplore.auth.urls.origin(), text, reply_to, None, &media_ids);
+
+    let endpoint = xplore.auth.operations.endpoint("CreateTweet")?.with_variables(variables);
+    let (value, _) = api::request_endpoint(&xplore.auth, &endpoint).await?;
+    Ok(value)
+}
+
+/// Posts a tweet, as a long tweet if its weighted length is over `MAX_TWEET_LENGTH`.
+///
+/// Text over `MAX_LONG_TWEET_LENGTH` fails with `XploreError::TweetTooLong` before any media is
+/// uploaded.
+async fn publish(
+    xplore: &Xplore,
+    text: &str,
+    reply_to: Option<&str>,
+    quoted_tweet_id: Option<&str>,
+    card_uri: Option<&str>,
+    media_data: Option<Vec<(Vec<u8>, String)>>,
+) -> Result<PostedTweet> {
+    let length = check_length(text, MAX_LONG_TWEET_LENGTH)?;
+    let operation = if length > MAX_TWEET_LENGTH { "CreateNoteTweet" } else { "CreateTweet" };
+
+    let media_ids = upload_all(xplore, media_data).await?;
+    let mut variables = tweet_variables(xplore.auth.urls.origin(), text, reply_to, quoted_tweet_id, &media_ids);
+    if let Some(card_uri) = card_uri {
+        variables["card_uri"] = json!(card_uri);
+    }
+
+    let endpoint = xplore.auth.operations.endpoint(operation)?.with_variables(variables);
+    let (value, _) = api::request_endpoint(&xplore.auth, &endpoint).await?;
+    parse_posted_tweet(xplore, value)
+}
+
+/// The weighted length of `text`, or `XploreError::TweetTooLong` if it is over `max`.
+fn check_length(text: &str, max: usize) -> Result<usize> {
+    let length = weighted_length(text);
+    if length > max {
+        return Err(XploreError::TweetTooLong { length, max });
+    }
+    Ok(length)
+}
+
+async fn upload_all(xplore: &Xplore, media_data: Option<Vec<(Vec<u8>, String)>>) -> Result<Vec<String>> {
+    let mut media_ids = Vec::new();
+    for (file_data, media_type) in media_data.unwrap_or_default() {
+        media_ids.push(upload_media(xplore, file_data, &media_type).await?);
+    }
+    Ok(media_ids)
+}
+
+/// Variables shared by `CreateTweet` and `CreateNoteTweet`.
+fn tweet_variables(
+    origin: &str,
+    text: &str,
+    reply_to: Option<&str>,
+    quoted_tweet_id: Option<&str>,
+    media_ids: &[String],
+) -> Value {
+    let media_entities: Vec<Value> = media_ids.iter().map(|id| json!({ "media_id": id, "tagged_users": [] })).collect();
+
     let mut variables = json!({
         "tweet_text": text,
         "dark_request": false,
         "media": {
-            "media_entities": [],
+            "media_entities": media_entities,
             "possibly_sensitive": false
         },
         "semantic_annotation_ids": []
     });
 
-    // Add reply information if provided
     if let Some(reply_id) = reply_to {
         variables["reply"] = json!({
             "in_reply_to_tweet_id": reply_id
         });
     }
 
-    // Handle media uploads if provided
-    if let Some(media_files) = media_data {
-        let mut media_entities = Vec::new();
-
-        // Upload each media file and collect media IDs
-        for (file_data, media_type) in media_files {
-            let media_id = upload_media(xplore, file_data, &media_type).await?;
-            media_entities.push(json!({
-                "media_id": media_id,
-                "tagged_users": []
-            }));
-        }
-
-        variables["media"]["media_entities"] = json!(media_entities);
+    if let Some(quoted_tweet_id) = quoted_tweet_id {
+        variables["attachment_url"] = json!(format!("{}/twitter/status/{}", origin, quoted_tweet_id));
     }
-    let features = create_tweet_features();
-
-    let url = "https://twitter.com/i/api/graphql/a1p9RWpkYKBjWv_I3WzS-A/CreateTweet";
-    let body = Some(json!({
-        "variables": variables,
-        "features": features,
-        "fieldToggles": {}
-    }));
-    let (value, _) = api::send_request(&mut xplore.auth, url, Method::POST, body).await?;
-    Ok(value)
-}
 
-fn create_quote_tweet_features() -> Value {
-    json!({
-        "interactive_text_enabled": true,
-        "longform_notetweets_inline_media_enabled": false,
-        "responsive_web_text_conversations_enabled": false,
-        "tweet_with_visibility_results_prefer_gql_limited_actions_policy_enabled": false,
-        "vibe_api_enabled": false,
-        "rweb_lists_timeline_redesign_enabled": true,
-        "responsive_web_graphql_exclude_directive_enabled": true,
-        "verified_phone_label_enabled": false,
-        "creator_subscriptions_tweet_preview_api_enabled": true,
-        "responsive_web_graphql_timeline_navigation_enabled": true,
-        "responsive_web_graphql_skip_user_profile_image_extensions_enabled": false,
-        "tweetypie_unmention_optimization_enabled": true,
-        "responsive_web_edit_tweet_api_enabled": true,
-        "graphql_is_translatable_rweb_tweet_is_translatable_enabled": true,
-        "view_counts_everywhere_api_enabled": true,
-        "longform_notetweets_consumption_enabled": true,
-        "tweet_awards_web_tipping_enabled": false,
-        "freedom_of_speech_not_reach_fetch_enabled": true,
-        "standardized_nudges_misinfo": true,
-        "longform_notetweets_rich_text_read_enabled": true,
-        "responsive_web_enhance_cards_enabled": false,
-        "subscriptions_verification_info_enabled": true,
-        "subscriptions_verification_info_reason_enabled": true,
-        "subscriptions_verification_info_verified_since_enabled": true,
-        "super_follow_badge_privacy_enabled": false,
-        "super_follow_exclusive_tweet_notifications_enabled": false,
-        "super_follow_tweet_api_enabled": false,
-        "super_follow_user_api_enabled": false,
-        "android_graphql_skip_api_media_color_palette": false,
-        "creator_subscriptions_subscription_count_enabled": false,
-        "blue_business_profile_image_shape_enabled": false,
-        "unified_cards_ad_metadata_container_dynamic_card_content_query_enabled": false,
-        "rweb_video_timestamps_enabled": true,
-        "c9s_tweet_anatomy_moderator_badge_enabled": true,
-        "responsive_web_twitter_article_tweet_consumption_enabled": false
-    })
+    variables
 }
 
 pub async fn fetch_user_tweets(
-    xplore: &mut Xplore,
+    xplore: &Xplore,
     user_id: &str,
     max_tweets: i32,
     cursor: Option<&str>,
 ) -> Result<QueryTweetsResponse> {
-    let endpoint = Endpoints::user_tweets(user_id, max_tweets.min(200), cursor);
-    let url = &endpoint.to_request_url();
+    let endpoint = Endpoints::user_tweets(&xplore.auth.operations, user_id, max_tweets.min(200), cursor)?;
 
-    let (value, _) = api::send_request(&mut xplore.auth, url, Method::GET, None).await?;
+    let (value, _) = api::request_endpoint(&xplore.auth, &endpoint).await?;
 
-    let parsed_response = parse_timeline_tweets_v2(&value);
+    let parsed_response = parse_timeline_tweets_v2(&value, xplore.auth.urls.origin());
     Ok(parsed_response)
 }
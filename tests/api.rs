@@ -0,0 +1,122 @@
+use {
+    async_trait::async_trait,
+    reqwest::{Method, StatusCode},
+    std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    tokio::time::Instant,
+    xplore::{
+        rate_limit::ErrorRateLimitStrategy,
+        transport::{HttpRequest, HttpResponse, MockTransport, Transport},
+        Result, Xplore, XploreError, XploreOptions,
+    },
+};
+
+const USER: &str = r#"{"data":{"user":{"result":{"__typename":"User","rest_id":"12","legacy":{"location":"","name":"jack","screen_name":"jack"}}}}}"#;
+
+async fn client(transport: Arc<dyn Transport>, options: XploreOptions) -> Xplore {
+    let options = XploreOptions { transport: Some(transport), ..options };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+    xplore.set_cookie("auth_token=token; ct0=csrf").await.unwrap();
+    xplore
+}
+
+#[tokio::test(start_paused = true)]
+async fn server_errors_are_retried_with_backoff() {
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_response_once(Method::GET, "/UserByScreenName", StatusCode::SERVICE_UNAVAILABLE, "")
+            .with_response_once(Method::GET, "/UserByScreenName", StatusCode::BAD_GATEWAY, "")
+            .with_response(Method::GET, "/UserByScreenName", StatusCode::OK, USER),
+    );
+    let xplore = client(transport.clone(), XploreOptions::default()).await;
+
+    let start = Instant::now();
+    let profile = xplore.get_profile("jack").await.unwrap();
+
+    assert_eq!(profile.username, "jack");
+    assert_eq!(transport.requests().len(), 3);
+    // 500ms before the first retry, doubled before the second
+    assert_eq!(start.elapsed().as_millis() / 100, 15);
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_stop_at_max_retries() {
+    let transport = Arc::new(MockTransport::new().with_response(
+        Method::GET,
+        "/UserByScreenName",
+        StatusCode::SERVICE_UNAVAILABLE,
+        "",
+    ));
+    let xplore = client(transport.clone(), XploreOptions { max_retries: 2, ..Default::default() }).await;
+
+    let error = xplore.get_profile("jack").await.unwrap_err();
+
+    assert!(matches!(error, XploreError::Response(details) if details.status == Some(503)));
+    assert_eq!(transport.requests().len(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn rate_limit_is_returned_by_the_error_strategy() {
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_response(Method::GET, "/UserByScreenName", StatusCode::TOO_MANY_REQUESTS, "")
+            .with_header("x-rate-limit-remaining", "0")
+            .with_header("x-rate-limit-reset", "1900000000"),
+    );
+    let options = XploreOptions { rate_limit_strategy: Box::new(ErrorRateLimitStrategy), ..Default::default() };
+    let xplore = client(transport.clone(), options).await;
+
+    let error = xplore.get_profile("jack").await.unwrap_err();
+
+    let XploreError::RateLimited { reset_at, .. } = error else { panic!("unexpected error {:?}", error) };
+    assert_eq!(reset_at.map(|reset| reset.timestamp()), Some(1_900_000_000));
+    assert_eq!(transport.requests().len(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn server_errors_on_posts_are_not_retried() {
+    let transport =
+        Arc::new(MockTransport::new().with_response(Method::POST, "/CreateTweet", StatusCode::SERVICE_UNAVAILABLE, ""));
+    let xplore = client(transport.clone(), XploreOptions::default()).await;
+
+    let error = xplore.post_tweet("Hello", None, None).await.unwrap_err();
+
+    assert!(matches!(error, XploreError::Response(details) if details.status == Some(503)));
+    assert_eq!(transport.requests().len(), 1);
+}
+
+/// Refuses the first connection, then answers like `inner`.
+struct RefusedOnce {
+    attempts: AtomicUsize,
+    inner: MockTransport,
+}
+
+#[async_trait]
+impl Transport for RefusedOnce {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        if self.attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+            // nothing listens on port 1, so this fails to connect
+            reqwest::Client::new().get("http://127.0.0.1:1/").send().await?;
+        }
+        self.inner.send(request).await
+    }
+}
+
+#[tokio::test]
+async fn refused_connections_are_retried_for_posts() {
+    let inner = MockTransport::new().with_json(
+        Method::POST,
+        "/CreateTweet",
+        serde_json::from_str(include_str!("fixtures/create_tweet.json")).unwrap(),
+    );
+    let transport = Arc::new(RefusedOnce { attempts: AtomicUsize::new(0), inner });
+    let xplore = client(transport.clone(), XploreOptions::default()).await;
+
+    let posted = xplore.post_tweet("Hello from xplore #rust", None, None).await.unwrap();
+
+    assert_eq!(posted.id, "1790000000000000001");
+    assert_eq!(transport.attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(transport.inner.requests().len(), 1);
+}
@@ -0,0 +1,136 @@
+use {
+    reqwest::{Method, StatusCode},
+    serde_json::{json, Value},
+    std::sync::Arc,
+    xplore::{login::LogoutReport, transport::MockTransport, Xplore, XploreError, XploreOptions},
+};
+
+fn user(screen_name: &str) -> Value {
+    json!({"data": {"user": {"result": {
+        "__typename": "User",
+        "rest_id": "12",
+        "legacy": {"location": "", "name": screen_name, "screen_name": screen_name}
+    }}}})
+}
+
+async fn client(transport: Arc<MockTransport>) -> Xplore {
+    let options = XploreOptions { transport: Some(transport), ..Default::default() };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+    xplore.set_cookie("auth_token=token; ct0=csrf").await.unwrap();
+    xplore
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn clones_share_one_session_across_tasks() {
+    let transport = Arc::new(MockTransport::new().with_json(Method::GET, "/UserByScreenName", user("jack")));
+    let xplore = client(transport.clone()).await;
+
+    let handles: Vec<_> = (0..16)
+        .map(|_| {
+            let xplore = xplore.clone();
+            tokio::spawn(async move { xplore.get_profile("jack").await })
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.await.unwrap().unwrap().username, "jack");
+    }
+
+    let requests = transport.requests();
+    assert_eq!(requests.len(), 16);
+    assert!(requests
+        .iter()
+        .all(|request| request.headers.get("x-csrf-token").and_then(|value| value.to_str().ok()) == Some("csrf")));
+
+    let clone = xplore.clone();
+    tokio::spawn(async move { clone.set_cookie("auth_token=other; ct0=rotated").await.unwrap() }).await.unwrap();
+
+    assert!(xplore.get_cookie().await.unwrap().contains("ct0=rotated"));
+}
+
+const LOGOUT: &str = "/account/logout.json";
+const REVOKE_ALL: &str = "/account/sessions/revoke_all.json";
+
+fn paths(transport: &MockTransport) -> Vec<String> {
+    transport.requests().into_iter().map(|request| request.url.rsplit("/1.1").next().unwrap().to_string()).collect()
+}
+
+#[tokio::test]
+async fn logout_clears_a_confirmed_session() {
+    let transport = Arc::new(MockTransport::new().with_json(Method::POST, LOGOUT, json!({})));
+    let xplore = client(transport.clone()).await;
+
+    assert!(xplore.logout().await.unwrap());
+
+    assert!(xplore.is_guest().await);
+    assert_eq!(paths(&transport), [LOGOUT]);
+}
+
+#[tokio::test]
+async fn logout_treats_401_as_unconfirmed() {
+    let transport = Arc::new(MockTransport::new().with_response(
+        Method::POST,
+        LOGOUT,
+        StatusCode::UNAUTHORIZED,
+        r#"{"errors":[{"code":32,"message":"Could not authenticate you."}]}"#,
+    ));
+    let xplore = client(transport).await;
+
+    assert!(!xplore.logout().await.unwrap());
+    assert!(xplore.is_guest().await);
+}
+
+#[tokio::test]
+async fn logout_keeps_the_session_after_other_errors() {
+    let transport = Arc::new(MockTransport::new().with_response(
+        Method::POST,
+        LOGOUT,
+        StatusCode::INTERNAL_SERVER_ERROR,
+        r#"{"errors":[{"message":"Internal error"}]}"#,
+    ));
+    let xplore = client(transport).await;
+
+    assert!(xplore.logout().await.is_err());
+    assert!(!xplore.is_guest().await);
+}
+
+#[tokio::test]
+async fn logging_out_twice_is_harmless() {
+    let transport = Arc::new(MockTransport::new().with_json(Method::POST, LOGOUT, json!({})));
+    let xplore = client(transport.clone()).await;
+
+    assert!(xplore.logout().await.unwrap());
+    assert!(!xplore.logout().await.unwrap());
+    assert_eq!(xplore.logout_everywhere().await.unwrap(), LogoutReport::default());
+
+    assert_eq!(paths(&transport), [LOGOUT]);
+}
+
+#[tokio::test]
+async fn logout_everywhere_revokes_other_sessions_first() {
+    let transport = Arc::new(MockTransport::new().with_json(Method::POST, REVOKE_ALL, json!({})).with_json(
+        Method::POST,
+        LOGOUT,
+        json!({}),
+    ));
+    let xplore = client(transport.clone()).await;
+
+    let report = xplore.logout_everywhere().await.unwrap();
+
+    assert_eq!(report, LogoutReport { server_confirmed: true, other_sessions_revoked: true });
+    assert_eq!(paths(&transport), [REVOKE_ALL, LOGOUT]);
+}
+
+#[tokio::test]
+async fn failed_revocation_stops_the_logout() {
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_response(Method::POST, REVOKE_ALL, StatusCode::FORBIDDEN, r#"{"errors":[{"message":"Forbidden"}]}"#)
+            .with_json(Method::POST, LOGOUT, json!({})),
+    );
+    let xplore = client(transport.clone()).await;
+
+    assert!(matches!(xplore.logout_everywhere().await, Err(XploreError::Forbidden(_))));
+
+    assert_eq!(paths(&transport), [REVOKE_ALL]);
+    assert!(!xplore.is_guest().await);
+}
@@ -0,0 +1,102 @@
+use {
+    chrono::{TimeZone, Utc},
+    std::{path::Path, sync::Arc},
+    xplore::{
+        cookie_import::{parse_browser_json, parse_cookie_export, parse_netscape},
+        session::StoredCookie,
+        transport::MockTransport,
+        Xplore, XploreError, XploreOptions,
+    },
+};
+
+const COOKIES_TXT: &str = include_str!("fixtures/cookies.txt");
+const EDIT_THIS_COOKIE: &str = include_str!("fixtures/cookies_editthiscookie.json");
+const COOKIE_EDITOR: &str = include_str!("fixtures/cookies_cookie_editor.json");
+
+fn find<'a>(cookies: &'a [StoredCookie], name: &str) -> &'a StoredCookie {
+    cookies.iter().find(|cookie| cookie.name == name).unwrap_or_else(|| panic!("no {} cookie", name))
+}
+
+fn names(cookies: &[StoredCookie]) -> Vec<&str> {
+    cookies.iter().map(|cookie| cookie.name.as_str()).collect()
+}
+
+async fn client() -> Xplore {
+    let options = XploreOptions { transport: Some(Arc::new(MockTransport::new())), ..Default::default() };
+    Xplore::new(Some(options)).await.unwrap()
+}
+
+#[test]
+fn parses_netscape_cookies_txt() {
+    let cookies = parse_netscape(COOKIES_TXT).unwrap();
+    assert_eq!(names(&cookies), ["ct0", "auth_token", "lang", "twid"]);
+
+    let ct0 = find(&cookies, "ct0");
+    assert_eq!(ct0.value, "csrf0123456789");
+    assert_eq!(ct0.domain.as_deref(), Some("x.com"));
+    assert_eq!(ct0.path.as_deref(), Some("/"));
+    assert_eq!(ct0.expires, Utc.timestamp_opt(4102444800, 0).single());
+    assert!(ct0.secure);
+    assert!(!ct0.http_only);
+
+    assert!(find(&cookies, "auth_token").http_only);
+    assert_eq!(find(&cookies, "lang").expires, None);
+    assert_eq!(find(&cookies, "twid").domain.as_deref(), Some("twitter.com"));
+}
+
+#[test]
+fn rejects_malformed_cookies_txt_lines() {
+    let error = parse_netscape(".x.com\tTRUE\t/\tTRUE\tct0\tvalue\n").unwrap_err();
+    assert!(matches!(error, XploreError::Cookie(message) if message.contains("Line 1")));
+}
+
+#[test]
+fn parses_edit_this_cookie_export() {
+    let cookies = parse_browser_json(EDIT_THIS_COOKIE).unwrap();
+    assert_eq!(names(&cookies), ["ct0", "auth_token", "lang"]);
+
+    let auth_token = find(&cookies, "auth_token");
+    assert_eq!(auth_token.domain.as_deref(), Some("x.com"));
+    assert_eq!(auth_token.expires, Utc.timestamp_opt(4102444800, 0).single());
+    assert!(auth_token.http_only);
+    assert_eq!(auth_token.same_site.as_deref(), Some("None"));
+
+    assert_eq!(find(&cookies, "ct0").same_site.as_deref(), Some("Lax"));
+
+    let lang = find(&cookies, "lang");
+    assert_eq!(lang.expires, None);
+    assert_eq!(lang.same_site, None);
+}
+
+#[test]
+fn skips_expired_cookies_in_cookie_editor_export() {
+    let cookies = parse_browser_json(COOKIE_EDITOR).unwrap();
+    assert_eq!(names(&cookies), ["auth_token"]);
+    assert_eq!(cookies[0].domain.as_deref(), Some("twitter.com"));
+}
+
+#[test]
+fn detects_the_export_format() {
+    assert_eq!(parse_cookie_export(COOKIES_TXT).unwrap(), parse_netscape(COOKIES_TXT).unwrap());
+    assert_eq!(parse_cookie_export(EDIT_THIS_COOKIE).unwrap(), parse_browser_json(EDIT_THIS_COOKIE).unwrap());
+}
+
+#[tokio::test]
+async fn imports_cookies_file_into_the_session() {
+    let xplore = client().await;
+    xplore.import_cookies(Path::new("tests/fixtures/cookies.txt")).await.unwrap();
+
+    assert!(!xplore.is_guest().await);
+    let session = xplore.session().await;
+    assert_eq!(find(&session.cookies, "auth_token").domain.as_deref(), Some("x.com"));
+    assert_eq!(find(&session.cookies, "twid").domain.as_deref(), Some("twitter.com"));
+}
+
+#[tokio::test]
+async fn refuses_an_export_whose_csrf_cookie_expired() {
+    let xplore = client().await;
+    let error = xplore.import_cookies("tests/fixtures/cookies_cookie_editor.json").await.unwrap_err();
+
+    assert!(matches!(error, XploreError::Cookie(_)));
+    assert!(xplore.is_guest().await);
+}
@@ -0,0 +1,70 @@
+use {
+    std::collections::HashMap,
+    xplore::{
+        discovery::{find_main_bundle_url, parse_bundle, parse_feature_defaults},
+        operations::{OperationKind, OperationRegistry},
+    },
+};
+
+const HOME_PAGE: &str = include_str!("fixtures/x_home.html");
+const BUNDLE: &str = include_str!("fixtures/main.bundle.js");
+
+#[test]
+fn finds_main_bundle_in_home_page() {
+    assert_eq!(
+        find_main_bundle_url(HOME_PAGE).as_deref(),
+        Some("https://abs.twimg.com/responsive-web/client-web/main.9f8e7d6a.js")
+    );
+}
+
+#[test]
+fn parses_operations_from_bundle() {
+    let operations = parse_bundle(BUNDLE);
+    let names = operations.iter().map(|op| op.operation_name.as_str()).collect::<Vec<_>>();
+
+    assert_eq!(names, ["UserByScreenName", "SearchTimeline", "CreateTweet", "DeleteTweet", "FavoriteTweet"]);
+
+    let user = &operations[0];
+    assert_eq!(user.query_id, "xc8f1g7BYqr6VTzTbvNlGw");
+    assert_eq!(user.kind, OperationKind::Query);
+    assert_eq!(user.feature_switches.len(), 12);
+    assert_eq!(user.field_toggles, ["withAuxiliaryUserLabels"]);
+
+    let create = &operations[2];
+    assert_eq!(create.kind, OperationKind::Mutation);
+    assert!(create.field_toggles.is_empty());
+}
+
+#[test]
+fn parses_feature_defaults_from_home_page() {
+    let defaults = parse_feature_defaults(HOME_PAGE);
+
+    assert_eq!(defaults.get("rweb_tipjar_consumption_enabled"), Some(&true));
+    assert_eq!(defaults.get("subscriptions_feature_can_gift_premium"), Some(&false));
+}
+
+#[test]
+fn refresh_updates_known_operations_only() {
+    let registry = OperationRegistry::default();
+    let changed = registry.refresh(&parse_bundle(BUNDLE), &parse_feature_defaults(HOME_PAGE));
+
+    assert_eq!(changed, ["CreateTweet", "SearchTimeline", "UserByScreenName"]);
+    assert!(registry.get("SubscribeToScheduledSpace").is_none());
+
+    let user = registry.get("UserByScreenName").unwrap();
+    assert_eq!(user.query_id, "xc8f1g7BYqr6VTzTbvNlGw");
+    assert_eq!(user.features.get("responsive_web_twitter_article_notes_tab_enabled"), Some(&true));
+    assert_eq!(user.features.get("subscriptions_feature_can_gift_premium"), Some(&false));
+    // Existing values are kept.
+    assert_eq!(user.features.get("hidden_profile_likes_enabled"), Some(&false));
+}
+
+#[test]
+fn refresh_without_defaults_adds_switches_as_disabled() {
+    let registry = OperationRegistry::default();
+    registry.refresh(&parse_bundle(BUNDLE), &HashMap::new());
+
+    let search = registry.get("SearchTimeline").unwrap();
+    assert_eq!(search.query_id, "MJpyQGqgklrVl_0X9gNy3A");
+    assert_eq!(search.features.get("rweb_tipjar_consumption_enabled"), Some(&false));
+}
@@ -0,0 +1,92 @@
+use {
+    reqwest::{Method, StatusCode},
+    serde_json::json,
+    std::sync::Arc,
+    xplore::{transport::MockTransport, Xplore, XploreError, XploreOptions},
+};
+
+async fn client(transport: MockTransport) -> Xplore {
+    let options = XploreOptions { transport: Some(Arc::new(transport)), max_retries: 0, ..Default::default() };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+    xplore.set_cookie("auth_token=token; ct0=csrf").await.unwrap();
+    xplore
+}
+
+/// Whether an error is of the expected variant.
+type Check = fn(&XploreError) -> bool;
+
+/// The error `get_profile` fails with when X answers with `status` and `body`.
+async fn profile_error(status: StatusCode, body: serde_json::Value) -> XploreError {
+    let xplore =
+        client(MockTransport::new().with_response(Method::GET, "/UserByScreenName", status, body.to_string())).await;
+    xplore.get_profile("jack").await.unwrap_err()
+}
+
+fn errors(code: i64, message: &str) -> serde_json::Value {
+    json!({ "errors": [{ "code": code, "message": message }] })
+}
+
+#[tokio::test]
+async fn error_codes_pick_the_variant() {
+    let cases: Vec<(StatusCode, i64, Check)> = vec![
+        (StatusCode::TOO_MANY_REQUESTS, 88, |e| matches!(e, XploreError::RateLimited { .. })),
+        (StatusCode::UNAUTHORIZED, 32, |e| matches!(e, XploreError::Unauthorized(_))),
+        (StatusCode::FORBIDDEN, 239, |e| matches!(e, XploreError::Unauthorized(_))),
+        (StatusCode::NOT_FOUND, 50, |e| matches!(e, XploreError::NotFound(_))),
+        (StatusCode::FORBIDDEN, 63, |e| matches!(e, XploreError::Suspended(_))),
+        (StatusCode::FORBIDDEN, 64, |e| matches!(e, XploreError::AccountSuspended(_))),
+        (StatusCode::FORBIDDEN, 179, |e| matches!(e, XploreError::Protected(_))),
+        (StatusCode::FORBIDDEN, 187, |e| matches!(e, XploreError::DuplicateTweet(_))),
+        (StatusCode::FORBIDDEN, 139, |e| matches!(e, XploreError::AlreadyApplied(_))),
+        (StatusCode::FORBIDDEN, 326, |e| matches!(e, XploreError::AccountLocked(_))),
+        (StatusCode::BAD_REQUEST, 399, |e| matches!(e, XploreError::InvalidCredentials(_))),
+        // unknown codes fall back to the status
+        (StatusCode::FORBIDDEN, 200, |e| matches!(e, XploreError::Forbidden(_))),
+        (StatusCode::BAD_REQUEST, 214, |e| matches!(e, XploreError::Response(_))),
+    ];
+
+    for (status, code, expected) in cases {
+        let error = profile_error(status, errors(code, "Something went wrong")).await;
+        assert!(expected(&error), "code {} gave {:?}", code, error);
+        let details = error.details().unwrap();
+        assert_eq!(details.code, Some(code));
+        assert_eq!(details.status, Some(status.as_u16()));
+        assert_eq!(details.message, "Something went wrong");
+    }
+}
+
+#[tokio::test]
+async fn status_is_used_without_an_error_code() {
+    let error = profile_error(StatusCode::UNAUTHORIZED, json!({})).await;
+    assert!(matches!(&error, XploreError::Unauthorized(details) if details.message == "401 Unauthorized"));
+
+    // GraphQL reports its code under `extensions`
+    let body = json!({ "errors": [{ "message": "Not found", "extensions": { "code": 34 } }] });
+    let error = profile_error(StatusCode::OK, body).await;
+    assert!(
+        matches!(&error, XploreError::NotFound(details) if details.code == Some(34) && details.status == Some(200))
+    );
+}
+
+#[tokio::test]
+async fn errors_without_data_fail_a_successful_response() {
+    let mut body = errors(64, "Your account is suspended");
+    body["data"] = json!({});
+
+    let error = profile_error(StatusCode::OK, body).await;
+
+    assert!(matches!(error, XploreError::AccountSuspended(_)));
+}
+
+#[tokio::test]
+async fn errors_alongside_data_are_ignored() {
+    let body = json!({
+        "data": { "user": { "result": { "__typename": "User", "rest_id": "12", "legacy": { "location": "", "name": "jack", "screen_name": "jack" } } } },
+        "errors": [{ "code": 37, "message": "Partial failure: highlights unavailable" }]
+    });
+    let xplore = client(MockTransport::new().with_json(Method::GET, "/UserByScreenName", body)).await;
+
+    let profile = xplore.get_profile("jack").await.unwrap();
+
+    assert_eq!(profile.id, "12");
+}
@@ -0,0 +1 @@
+(self.webpackChunk_twitter_responsive_web=self.webpackChunk_twitter_responsive_web||[]).push([[8236],{98745:e=>{e.exports={queryId:"xc8f1g7BYqr6VTzTbvNlGw",operationName:"UserByScreenName",operationType:"query",metadata:{featureSwitches:["hidden_profile_subscriptions_enabled","rweb_tipjar_consumption_enabled","responsive_web_graphql_exclude_directive_enabled","verified_phone_label_enabled","subscriptions_verification_info_is_identity_verified_enabled","subscriptions_verification_info_verified_since_enabled","highlights_tweets_tab_ui_enabled","responsive_web_twitter_article_notes_tab_enabled","subscriptions_feature_can_gift_premium","creator_subscriptions_tweet_preview_api_enabled","responsive_web_graphql_skip_user_profile_image_extensions_enabled","responsive_web_graphql_timeline_navigation_enabled"],fieldToggles:["withAuxiliaryUserLabels"]}}},51237:e=>{e.exports={queryId:"MJpyQGqgklrVl_0X9gNy3A",operationName:"SearchTimeline",operationType:"query",metadata:{featureSwitches:["rweb_tipjar_consumption_enabled","responsive_web_graphql_exclude_directive_enabled","verified_phone_label_enabled","creator_subscriptions_tweet_preview_api_enabled","responsive_web_graphql_timeline_navigation_enabled"],fieldToggles:[]}}},62321:e=>{e.exports={queryId:"oB-5XsHNAbjvARJEc8CZFw",operationName:"CreateTweet",operationType:"mutation",metadata:{featureSwitches:["communities_web_enable_tweet_community_results_fetch","c9s_tweet_anatomy_moderator_badge_enabled","tweetypie_unmention_optimization_enabled"]}}},71002:e=>{e.exports={queryId:"VaenaVgh5q5ih7kvyVjgtg",operationName:"DeleteTweet",operationType:"mutation",metadata:{featureSwitches:[]}}},12004:e=>{e.exports={queryId:"lI07N6Otwv1PhnEgXILM7A",operationName:"FavoriteTweet",operationType:"mutation",metadata:{featureSwitches:[]}}},44431:e=>{e.exports={queryId:"Hx9kWzA8kIpp4YEQ5KUn7g",operationName:"SubscribeToScheduledSpace",operationType:"subscription",metadata:{featureSwitches:[]}}},90210:(e,t,n)=>{"use strict";n.d(t,{Z:()=>a});const a=function(e){return e.replace(/queryId/g,"")}}}]);
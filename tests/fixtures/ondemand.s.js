@@ -0,0 +1 @@
+"use strict";(self.webpackChunk_twitter_responsive_web=self.webpackChunk_twitter_responsive_web||[]).push([["ondemand.s"],{81234:(t,n,e)=>{e.d(n,{default:()=>o});var r=e(12345);function o(){const t=r.x(),n=t.key;return[parseInt(n[2], 16),parseInt(n[12], 16),parseInt(n[15], 16),parseInt(n[37], 16)].join("")}}}]);
@@ -0,0 +1,163 @@
+use {
+    chrono::{Duration, Utc},
+    reqwest::{Method, StatusCode},
+    serde_json::{json, Value},
+    std::sync::Arc,
+    xplore::{
+        search::SearchMode,
+        session::{Session, SESSION_FORMAT_VERSION},
+        session_store::{MemorySessionStore, SessionStore},
+        transport::MockTransport,
+        Xplore, XploreError, XploreOptions,
+    },
+};
+
+const ACTIVATE: &str = "/guest/activate.json";
+const PROFILE: &str = "/UserByScreenName";
+
+fn user() -> Value {
+    json!({"data": {"user": {"result": {
+        "__typename": "User",
+        "rest_id": "12",
+        "legacy": {"location": "", "name": "jack", "screen_name": "jack"}
+    }}}})
+}
+
+fn activations(tokens: &[&str]) -> MockTransport {
+    tokens.iter().fold(MockTransport::new(), |transport, token| {
+        transport.with_response_once(
+            Method::POST,
+            ACTIVATE,
+            StatusCode::OK,
+            json!({ "guest_token": token }).to_string(),
+        )
+    })
+}
+
+async fn guest(transport: Arc<MockTransport>, session: Option<Session>) -> Xplore {
+    let store = MemorySessionStore::new();
+    if let Some(session) = session {
+        store.save(&session).await.unwrap();
+    }
+    let options =
+        XploreOptions { transport: Some(transport), session_store: Some(Arc::new(store)), ..Default::default() };
+    Xplore::new(Some(options)).await.unwrap()
+}
+
+fn guest_session(token: &str, age: Duration) -> Session {
+    Session {
+        version: SESSION_FORMAT_VERSION,
+        cookies: Vec::new(),
+        guest_token: Some(token.into()),
+        guest_token_created_at: Some(Utc::now() - age),
+        bearer_token: "bearer".into(),
+        user_id: None,
+        screen_name: None,
+        saved_at: Utc::now(),
+    }
+}
+
+/// The `x-guest-token` of every request to `path`.
+fn guest_tokens(transport: &MockTransport, path: &str) -> Vec<String> {
+    transport
+        .requests()
+        .into_iter()
+        .filter(|request| request.url.contains(path))
+        .map(|request| request.headers["x-guest-token"].to_str().unwrap().to_string())
+        .collect()
+}
+
+fn count(transport: &MockTransport, path: &str) -> usize {
+    transport.requests().iter().filter(|request| request.url.contains(path)).count()
+}
+
+#[tokio::test]
+async fn guests_only_send_guest_operations() {
+    let transport = Arc::new(activations(&["g1"]).with_json(Method::GET, PROFILE, user()));
+    let xplore = guest(transport.clone(), None).await;
+    assert!(xplore.is_guest().await);
+
+    assert_eq!(xplore.get_profile("jack").await.unwrap().username, "jack");
+    let error = xplore.search_profiles("rust", 10, None).await.unwrap_err();
+    let tweets_error = xplore.search_tweets("rust", 10, SearchMode::Latest, None).await.unwrap_err();
+
+    assert!(
+        matches!(&error, XploreError::LoginRequired(operation) if operation.contains("SearchTimeline")),
+        "{:?}",
+        error
+    );
+    assert!(matches!(tweets_error, XploreError::LoginRequired(_)));
+    assert_eq!(guest_tokens(&transport, PROFILE), ["g1"]);
+    assert_eq!(count(&transport, "SearchTimeline"), 0);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_guest_requests_share_one_activation() {
+    let transport = Arc::new(activations(&["g1", "g2"]).with_json(Method::GET, PROFILE, user()));
+    let xplore = guest(transport.clone(), None).await;
+
+    let handles: Vec<_> = (0..8)
+        .map(|_| {
+            let xplore = xplore.clone();
+            tokio::spawn(async move { xplore.get_profile("jack").await })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    assert_eq!(count(&transport, ACTIVATE), 1);
+    assert!(guest_tokens(&transport, PROFILE).iter().all(|token| token == "g1"));
+}
+
+#[tokio::test]
+async fn old_guest_tokens_are_replaced() {
+    let transport = Arc::new(activations(&["g1"]).with_json(Method::GET, PROFILE, user()));
+    let xplore = guest(transport.clone(), Some(guest_session("stored", Duration::hours(3)))).await;
+
+    xplore.get_profile("jack").await.unwrap();
+
+    assert_eq!(guest_tokens(&transport, PROFILE), ["g1"]);
+}
+
+#[tokio::test]
+async fn recent_guest_tokens_are_kept() {
+    let transport = Arc::new(activations(&["g1"]).with_json(Method::GET, PROFILE, user()));
+    let xplore = guest(transport.clone(), Some(guest_session("stored", Duration::minutes(5)))).await;
+
+    xplore.get_profile("jack").await.unwrap();
+
+    assert_eq!(count(&transport, ACTIVATE), 0);
+    assert_eq!(guest_tokens(&transport, PROFILE), ["stored"]);
+}
+
+#[tokio::test]
+async fn rejected_guest_tokens_are_refreshed_once() {
+    for status in [StatusCode::FORBIDDEN, StatusCode::TOO_MANY_REQUESTS] {
+        let transport = Arc::new(
+            activations(&["g1", "g2"])
+                .with_response_once(Method::GET, PROFILE, status, r#"{"errors":[{"message":"Bad guest token"}]}"#)
+                .with_json(Method::GET, PROFILE, user()),
+        );
+        let xplore = guest(transport.clone(), None).await;
+
+        assert_eq!(xplore.get_profile("jack").await.unwrap().username, "jack");
+        assert_eq!(guest_tokens(&transport, PROFILE), ["g1", "g2"], "{}", status);
+    }
+}
+
+#[tokio::test]
+async fn a_second_rejection_is_returned() {
+    let transport = Arc::new(activations(&["g1", "g2", "g3"]).with_response(
+        Method::GET,
+        PROFILE,
+        StatusCode::FORBIDDEN,
+        r#"{"errors":[{"message":"Forbidden"}]}"#,
+    ));
+    let xplore = guest(transport.clone(), None).await;
+
+    let error = xplore.get_profile("jack").await.unwrap_err();
+
+    assert!(matches!(error, XploreError::Forbidden(_)), "{:?}", error);
+    assert_eq!(guest_tokens(&transport, PROFILE), ["g1", "g2"]);
+}
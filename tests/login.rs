@@ -0,0 +1,277 @@
+use {
+    async_trait::async_trait,
+    reqwest::{Method, StatusCode},
+    serde_json::{json, Value},
+    std::sync::{Arc, Mutex},
+    xplore::{
+        login::{LoginChallengeHandler, LoginFlow, LoginInput, LoginSubtask, SubtaskType},
+        transport::{MockTransport, RequestBody},
+        Xplore, XploreError, XploreOptions,
+    },
+};
+
+const TASK: &str = "/onboarding/task.json";
+
+fn flow(token: &str, subtasks: &[Value]) -> String {
+    json!({ "flow_token": token, "subtasks": subtasks }).to_string()
+}
+
+fn subtask(id: &str) -> Value {
+    json!({ "subtask_id": id })
+}
+
+/// A transport answering the guest activation and then each task request with the next of `steps`.
+fn transport(steps: &[String]) -> MockTransport {
+    let transport =
+        MockTransport::new().with_json(Method::POST, "/guest/activate.json", json!({ "guest_token": "guest" }));
+    steps
+        .iter()
+        .fold(transport, |transport, body| transport.with_response_once(Method::POST, TASK, StatusCode::OK, body))
+}
+
+async fn client(transport: Arc<MockTransport>) -> Xplore {
+    client_with_handler(transport, None).await
+}
+
+async fn client_with_handler(transport: Arc<MockTransport>, handler: Option<Arc<Handler>>) -> Xplore {
+    let options = XploreOptions {
+        transport: Some(transport),
+        login_challenge_handler: handler.map(|handler| handler as Arc<dyn LoginChallengeHandler>),
+        ..Default::default()
+    };
+    Xplore::new(Some(options)).await.unwrap()
+}
+
+/// Answers `LoginAcid` with a fixed code and `ArkoseLogin` with a raw input, and records every
+/// subtask it is asked about.
+#[derive(Default)]
+struct Handler {
+    asked: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl LoginChallengeHandler for Handler {
+    async fn on_challenge(&self, subtask: &LoginSubtask) -> xplore::Result<Option<LoginInput>> {
+        self.asked.lock().unwrap().push(subtask.id().to_string());
+        Ok(match subtask.id() {
+            "LoginAcid" => Some(LoginInput::Acid("424242".into())),
+            "ArkoseLogin" => Some(LoginInput::Raw(json!({ "subtask_id": "ArkoseLogin", "link": "next_link" }))),
+            _ => None,
+        })
+    }
+}
+
+/// The bodies of the task requests answering subtasks, i.e. all but the one starting the flow.
+fn answers(transport: &MockTransport) -> Vec<Value> {
+    transport
+        .requests()
+        .into_iter()
+        .filter(|request| request.url.ends_with(TASK))
+        .skip(1)
+        .map(|request| match request.body {
+            RequestBody::Json(body) => body,
+            other => panic!("unexpected body {:?}", other),
+        })
+        .collect()
+}
+
+async fn start(xplore: &Xplore) -> LoginFlow {
+    xplore.start_login().await.unwrap()
+}
+
+#[tokio::test]
+async fn answers_each_subtask_with_its_input() {
+    let transport = Arc::new(transport(&[
+        flow("t0", &[subtask("LoginJsInstrumentationSubtask")]),
+        flow("t1", &[subtask("LoginEnterUserIdentifierSSO")]),
+        flow("t2", &[subtask("LoginEnterPassword")]),
+        flow("t3", &[subtask("AccountDuplicationCheck")]),
+        flow("t4", &[subtask("LoginAcid")]),
+        flow("t5", &[subtask("LoginSuccessSubtask")]),
+        flow("t6", &[]),
+    ]));
+    let xplore = client(transport.clone()).await;
+
+    let mut flow = start(&xplore).await;
+    let inputs = [
+        LoginInput::Continue,
+        LoginInput::UserIdentifier("jack".into()),
+        LoginInput::Password("hunter2".into()),
+        LoginInput::Continue,
+        LoginInput::Acid("jack@example.com".into()),
+        LoginInput::Continue,
+    ];
+    for input in inputs {
+        xplore.submit_login(&mut flow, input).await.unwrap();
+    }
+    assert!(flow.is_complete());
+
+    let answers = answers(&transport);
+    let tokens: Vec<&str> = answers.iter().map(|body| body["flow_token"].as_str().unwrap()).collect();
+    assert_eq!(tokens, ["t0", "t1", "t2", "t3", "t4", "t5"]);
+
+    let inputs: Vec<&Value> = answers.iter().map(|body| &body["subtask_inputs"]).collect();
+    assert_eq!(inputs[0][0]["js_instrumentation"]["link"], "next_link");
+    assert_eq!(inputs[1][0]["settings_list"]["setting_responses"][0]["response_data"]["text_data"]["result"], "jack");
+    assert_eq!(inputs[2][0]["enter_password"]["password"], "hunter2");
+    assert_eq!(inputs[3][0]["check_logged_in_account"]["link"], "AccountDuplicationCheck_false");
+    assert_eq!(
+        inputs[4][0],
+        json!({ "subtask_id": "LoginAcid", "enter_text": { "text": "jack@example.com", "link": "next_link" } })
+    );
+    // the success subtask is acknowledged without an input
+    assert_eq!(*inputs[5], json!([]));
+}
+
+#[tokio::test]
+async fn sends_raw_inputs_as_is() {
+    let transport = Arc::new(transport(&[flow("t0", &[subtask("ArkoseLogin")]), flow("t1", &[])]));
+    let xplore = client(transport.clone()).await;
+
+    let mut flow = start(&xplore).await;
+    assert_eq!(flow.current().unwrap().subtask_type, SubtaskType::Unknown("ArkoseLogin".into()));
+    let raw = json!({ "subtask_id": "ArkoseLogin", "web_modal": { "completion_deeplink": "x", "link": "next_link" } });
+    xplore.submit_login(&mut flow, LoginInput::Raw(raw.clone())).await.unwrap();
+
+    assert_eq!(answers(&transport)[0]["subtask_inputs"], json!([raw]));
+}
+
+#[tokio::test]
+async fn rejects_inputs_that_do_not_fit_the_subtask() {
+    let transport = Arc::new(transport(&[flow("t0", &[subtask("LoginEnterPassword")])]));
+    let xplore = client(transport.clone()).await;
+
+    let mut flow = start(&xplore).await;
+    let error = xplore.submit_login(&mut flow, LoginInput::Acid("123456".into())).await.unwrap_err();
+
+    assert!(
+        matches!(&error, XploreError::LoginChallenge(message) if message.contains("LoginInput::Acid")),
+        "{:?}",
+        error
+    );
+    assert!(answers(&transport).is_empty());
+    assert_eq!(flow.flow_token(), "t0");
+}
+
+#[tokio::test]
+async fn deny_login_fails_with_its_prompt() {
+    let denied = json!({
+        "subtask_id": "DenyLoginSubtask",
+        "cta": { "primary_text": { "text": "Suspicious login prevented" } }
+    });
+    let transport = Arc::new(transport(&[flow("t0", &[denied])]));
+    let xplore = client(transport.clone()).await;
+
+    let mut flow = start(&xplore).await;
+    let error = xplore.submit_login(&mut flow, LoginInput::Continue).await.unwrap_err();
+
+    assert!(
+        matches!(&error, XploreError::LoginDenied(message) if message == "Suspicious login prevented"),
+        "{:?}",
+        error
+    );
+    assert!(answers(&transport).is_empty());
+}
+
+#[tokio::test]
+async fn complete_flows_take_no_answer() {
+    let transport = Arc::new(transport(&[flow("t0", &[])]));
+    let xplore = client(transport).await;
+
+    let mut flow = start(&xplore).await;
+
+    assert!(matches!(xplore.submit_login(&mut flow, LoginInput::Continue).await, Err(XploreError::Auth(_))));
+}
+
+#[tokio::test]
+async fn wrong_password_is_invalid_credentials() {
+    let transport = Arc::new(transport(&[flow("t0", &[subtask("LoginEnterPassword")])]).with_response(
+        Method::POST,
+        TASK,
+        StatusCode::BAD_REQUEST,
+        r#"{"errors":[{"code":399,"message":"Wrong password!"}]}"#,
+    ));
+    let xplore = client(transport).await;
+
+    let mut flow = start(&xplore).await;
+    let error = xplore.submit_login(&mut flow, LoginInput::Password("wrong".into())).await.unwrap_err();
+
+    assert!(matches!(&error, XploreError::InvalidCredentials(details) if details.code == Some(399)), "{:?}", error);
+}
+
+#[test]
+fn inputs_hide_secrets_when_printed() {
+    let printed = format!("{:?}", LoginInput::Password("hunter2".into()));
+
+    assert_eq!(printed, "LoginInput::Password");
+}
+
+fn text_answer(body: &Value) -> &Value {
+    &body["subtask_inputs"][0]["enter_text"]["text"]
+}
+
+#[tokio::test]
+async fn login_asks_the_handler_before_using_the_email() {
+    let transport = Arc::new(transport(&[
+        flow("t0", &[subtask("LoginEnterUserIdentifierSSO")]),
+        flow("t1", &[subtask("LoginEnterPassword")]),
+        flow("t2", &[subtask("LoginAcid")]),
+        flow("t3", &[subtask("LoginEnterAlternateIdentifierSubtask")]),
+        flow("t4", &[]),
+    ]));
+    let handler = Arc::new(Handler::default());
+    let xplore = client_with_handler(transport.clone(), Some(handler.clone())).await;
+
+    xplore.login("jack", "hunter2", Some("jack@example.com"), None).await.unwrap();
+
+    // only challenges reach the handler; the alternate identifier falls back to the email
+    assert_eq!(*handler.asked.lock().unwrap(), ["LoginAcid", "LoginEnterAlternateIdentifierSubtask"]);
+    let answers = answers(&transport);
+    assert_eq!(text_answer(&answers[2]), "424242");
+    assert_eq!(text_answer(&answers[3]), "jack@example.com");
+}
+
+#[tokio::test]
+async fn login_passes_unknown_subtasks_to_the_handler() {
+    let transport = Arc::new(transport(&[flow("t0", &[subtask("ArkoseLogin")]), flow("t1", &[])]));
+    let handler = Arc::new(Handler::default());
+    let xplore = client_with_handler(transport.clone(), Some(handler.clone())).await;
+
+    xplore.login("jack", "hunter2", None, None).await.unwrap();
+
+    assert_eq!(*handler.asked.lock().unwrap(), ["ArkoseLogin"]);
+    assert_eq!(answers(&transport)[0]["subtask_inputs"][0]["subtask_id"], "ArkoseLogin");
+}
+
+#[tokio::test]
+async fn login_fails_on_subtasks_nobody_can_answer() {
+    for (subtask_id, expected) in [
+        ("ArkoseLogin", "Unhandled subtask: ArkoseLogin"),
+        ("LoginAcid", "Email required for verification"),
+        ("LoginTwoFactorAuthChallenge", "Two factor authentication required"),
+    ] {
+        let transport = Arc::new(transport(&[flow("t0", &[subtask(subtask_id)])]));
+        let xplore = client(transport.clone()).await;
+
+        let error = xplore.login("jack", "hunter2", None, None).await.unwrap_err();
+
+        assert!(matches!(&error, XploreError::LoginChallenge(message) if message == expected), "{:?}", error);
+        assert!(answers(&transport).is_empty());
+    }
+}
+
+#[tokio::test]
+async fn login_gives_up_when_x_keeps_asking() {
+    let looping = flow("t", &[subtask("LoginJsInstrumentationSubtask")]);
+    let transport = Arc::new(transport(&[]).with_response(Method::POST, TASK, StatusCode::OK, looping));
+    let xplore = client(transport.clone()).await;
+
+    let error = xplore.login("jack", "hunter2", None, None).await.unwrap_err();
+
+    assert!(
+        matches!(&error, XploreError::LoginChallenge(message) if message.contains("LoginJsInstrumentationSubtask")),
+        "{:?}",
+        error
+    );
+    assert_eq!(answers(&transport).len(), 20);
+}
@@ -0,0 +1,122 @@
+use {
+    std::collections::{BTreeMap, HashMap},
+    xplore::{
+        operations::{OperationKind, OperationPatch, OperationRegistry},
+        XploreError,
+    },
+};
+
+#[test]
+fn patch_merges_features_and_replaces_toggles() {
+    let registry = OperationRegistry::default();
+    let before = registry.get("UserByScreenName").unwrap();
+
+    let patch = OperationPatch {
+        query_id: Some("newQueryId".into()),
+        features: BTreeMap::from([("brand_new_feature".into(), true), ("hidden_profile_likes_enabled".into(), true)]),
+        field_toggles: Some(BTreeMap::from([("withAuxiliaryUserLabels".into(), true)])),
+        ..Default::default()
+    };
+    registry.patch("UserByScreenName", patch).unwrap();
+
+    let after = registry.get("UserByScreenName").unwrap();
+    assert_eq!(after.query_id, "newQueryId");
+    assert_eq!(after.kind, before.kind);
+    assert_eq!(after.features.len(), before.features.len() + 1);
+    assert_eq!(after.features.get("hidden_profile_likes_enabled"), Some(&true));
+    assert_eq!(after.field_toggles, Some(BTreeMap::from([("withAuxiliaryUserLabels".into(), true)])));
+}
+
+#[test]
+fn patch_adds_unknown_operations_only_with_a_query_id() {
+    let registry = OperationRegistry::empty();
+
+    let error = registry.set_feature("Bookmarks", "graphql_timeline_v2_bookmark_timeline", true).unwrap_err();
+    assert!(matches!(error, XploreError::Config(message) if message.contains("Bookmarks")));
+
+    let patch =
+        OperationPatch { query_id: Some("abc".into()), kind: Some(OperationKind::Mutation), ..Default::default() };
+    registry.patch("Bookmarks", patch).unwrap();
+
+    let added = registry.get("Bookmarks").unwrap();
+    assert_eq!(added.query_id, "abc");
+    assert_eq!(added.kind, OperationKind::Mutation);
+    assert_eq!(registry.names(), ["Bookmarks"]);
+}
+
+#[test]
+fn apply_is_all_or_nothing() {
+    let registry = OperationRegistry::default();
+    let patches = HashMap::from([
+        ("UserByScreenName".to_string(), OperationPatch { query_id: Some("changed".into()), ..Default::default() }),
+        ("NotAnOperation".to_string(), OperationPatch::default()),
+    ]);
+
+    let error = registry.apply(patches).unwrap_err();
+
+    assert!(matches!(error, XploreError::Config(message) if message.contains("NotAnOperation")));
+    assert_ne!(registry.get("UserByScreenName").unwrap().query_id, "changed");
+}
+
+#[test]
+fn loads_toml_and_json_patches() {
+    let registry = OperationRegistry::default();
+
+    registry
+        .load_toml(
+            r#"
+            [UserByScreenName]
+            query_id = "fromToml"
+
+            [SearchTimeline.features]
+            rweb_video_timestamps_enabled = true
+            "#,
+        )
+        .unwrap();
+    registry.load_json(r#"{ "Likes": { "query_id": "fromJson", "kind": "query" } }"#).unwrap();
+
+    assert_eq!(registry.get("UserByScreenName").unwrap().query_id, "fromToml");
+    assert_eq!(registry.get("SearchTimeline").unwrap().features.get("rweb_video_timestamps_enabled"), Some(&true));
+    assert_eq!(registry.get("Likes").unwrap().query_id, "fromJson");
+
+    for invalid in [r#"{ "Likes": { "query_id": 3 } }"#, "not json"] {
+        assert!(matches!(registry.load_json(invalid), Err(XploreError::Config(_))));
+    }
+    assert!(matches!(registry.load_toml("[Likes]\nkind = \"sometimes\""), Err(XploreError::Config(_))));
+}
+
+#[test]
+fn to_json_round_trips() {
+    let registry = OperationRegistry::default();
+    registry.set_query_id("TweetDetail", "rotated").unwrap();
+
+    let copy = OperationRegistry::empty();
+    copy.load_json(&registry.to_json().unwrap()).unwrap();
+
+    assert_eq!(copy.names(), registry.names());
+    for name in registry.names() {
+        assert_eq!(copy.get(&name), registry.get(&name), "{}", name);
+    }
+}
+
+#[test]
+fn clones_share_the_table() {
+    let registry = OperationRegistry::default();
+    let clone = registry.clone();
+
+    clone.set_query_id("Followers", "shared").unwrap();
+
+    assert_eq!(registry.get("Followers").unwrap().query_id, "shared");
+}
+
+#[test]
+fn home_timeline_keeps_its_own_features() {
+    let registry = OperationRegistry::default();
+
+    let home = registry.get("HomeTimeline").unwrap();
+    let user_tweets = registry.get("UserTweets").unwrap();
+
+    assert!(!home.features.contains_key("tweetypie_unmention_optimization_enabled"));
+    assert_eq!(user_tweets.features.get("tweetypie_unmention_optimization_enabled"), Some(&true));
+    assert_eq!(home.features.len() + 1, user_tweets.features.len());
+}
@@ -0,0 +1,141 @@
+use {
+    futures::{pin_mut, StreamExt, TryStreamExt},
+    reqwest::{header::HeaderMap, Method, StatusCode},
+    serde_json::Value,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    },
+    xplore::{
+        pagination::{paginate, Page},
+        rate_limit::ErrorRateLimitStrategy,
+        search::SearchMode,
+        transport::MockTransport,
+        Result, XploreError, XploreOptions,
+    },
+};
+
+/// Serves the pages of a fake timeline, keyed by the cursor that requests them (`""` for the
+/// first), and records the cursors it was asked for.
+#[derive(Clone, Default)]
+struct Timeline {
+    pages: HashMap<&'static str, (Vec<u32>, Option<&'static str>, HeaderMap)>,
+    requested: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+impl Timeline {
+    fn page(mut self, cursor: &'static str, items: &[u32], next: Option<&'static str>) -> Self {
+        self.pages.insert(cursor, (items.to_vec(), next, HeaderMap::new()));
+        self
+    }
+
+    fn exhausted(mut self, cursor: &'static str) -> Self {
+        let headers = &mut self.pages.get_mut(cursor).unwrap().2;
+        headers.insert("x-rate-limit-limit", "50".parse().unwrap());
+        headers.insert("x-rate-limit-remaining", "0".parse().unwrap());
+        headers.insert("x-rate-limit-reset", "4102444800".parse().unwrap());
+        self
+    }
+
+    /// Streams the timeline, keeping the items yielded before an error in `items`.
+    async fn stream_into(&self, limit: Option<usize>, items: &Mutex<Vec<u32>>) -> Result<()> {
+        let timeline = self.clone();
+        let stream = paginate(Arc::new(ErrorRateLimitStrategy), "Fake", limit, move |cursor| {
+            let timeline = timeline.clone();
+            async move {
+                timeline.requested.lock().unwrap().push(cursor.clone());
+                let (items, next, headers) = timeline.pages[cursor.as_deref().unwrap_or("")].clone();
+                Ok(Page { items, next: next.map(String::from), headers })
+            }
+        });
+        pin_mut!(stream);
+        while let Some(item) = stream.next().await {
+            items.lock().unwrap().push(item?);
+        }
+        Ok(())
+    }
+
+    async fn collect(&self, limit: Option<usize>) -> Result<Vec<u32>> {
+        let items = Mutex::new(Vec::new());
+        self.stream_into(limit, &items).await?;
+        Ok(items.into_inner().unwrap())
+    }
+
+    fn requested(&self) -> Vec<Option<String>> {
+        self.requested.lock().unwrap().clone()
+    }
+}
+
+fn cursors(cursors: &[Option<&str>]) -> Vec<Option<String>> {
+    cursors.iter().map(|cursor| cursor.map(String::from)).collect()
+}
+
+#[tokio::test]
+async fn follows_next_cursors_to_the_last_page() {
+    let timeline = Timeline::default().page("", &[1, 2], Some("b")).page("b", &[3], Some("c")).page("c", &[4], None);
+
+    assert_eq!(timeline.collect(None).await.unwrap(), [1, 2, 3, 4]);
+    assert_eq!(timeline.requested(), cursors(&[None, Some("b"), Some("c")]));
+}
+
+#[tokio::test]
+async fn stops_on_a_repeated_cursor() {
+    let timeline = Timeline::default().page("", &[1], Some("b")).page("b", &[2], Some("b"));
+
+    assert_eq!(timeline.collect(None).await.unwrap(), [1, 2]);
+    assert_eq!(timeline.requested(), cursors(&[None, Some("b")]));
+}
+
+#[tokio::test]
+async fn stops_on_an_empty_page() {
+    let timeline = Timeline::default().page("", &[1], Some("b")).page("b", &[], Some("c")).page("c", &[3], None);
+
+    assert_eq!(timeline.collect(None).await.unwrap(), [1]);
+    assert_eq!(timeline.requested(), cursors(&[None, Some("b")]));
+}
+
+#[tokio::test]
+async fn stops_requesting_once_the_limit_is_reached() {
+    let timeline = Timeline::default().page("", &[1, 2], Some("b")).page("b", &[3, 4], Some("c")).page("c", &[5], None);
+
+    assert_eq!(timeline.collect(Some(3)).await.unwrap(), [1, 2, 3]);
+    assert_eq!(timeline.requested(), cursors(&[None, Some("b")]));
+}
+
+#[tokio::test]
+async fn hands_an_exhausted_window_to_the_strategy() {
+    let timeline = Timeline::default().page("", &[1, 2], Some("b")).exhausted("").page("b", &[3], None);
+
+    let items = Mutex::new(Vec::new());
+    let error = timeline.stream_into(None, &items).await.unwrap_err();
+
+    assert_eq!(*items.lock().unwrap(), [1, 2]);
+    assert!(matches!(error, XploreError::RateLimited { reset_at: Some(_), .. }), "{:?}", error);
+    assert_eq!(timeline.requested(), cursors(&[None]));
+}
+
+#[tokio::test]
+async fn search_stream_sends_the_cursor_of_each_page() {
+    let cassette: Value = serde_json::from_str(include_str!("fixtures/cassettes/search_timeline.json")).unwrap();
+    let body = |index: usize| cassette["interactions"][index]["response"]["body"].as_str().unwrap().to_string();
+
+    // the second page points at a third, which repeats the second page and so ends the stream
+    let transport =
+        Arc::new(
+            MockTransport::new()
+                .with_response_once(Method::GET, "/SearchTimeline", StatusCode::OK, body(0))
+                .with_response(Method::GET, "/SearchTimeline", StatusCode::OK, body(1)),
+        );
+    let options = XploreOptions { transport: Some(transport.clone()), ..Default::default() };
+    let xplore = xplore::Xplore::new(Some(options)).await.unwrap();
+    xplore.set_cookie("auth_token=token; ct0=csrf").await.unwrap();
+
+    let tweets: Vec<_> = xplore.search_tweets_stream("rust", SearchMode::Latest, None).try_collect().await.unwrap();
+
+    assert!(!tweets.is_empty());
+    let urls: Vec<String> = transport.requests().into_iter().map(|request| request.url).collect();
+    assert_eq!(urls.len(), 3);
+    assert!(!urls[0].contains("cursor"));
+    assert!(urls[1].contains("DAADDAABCgAB_search_page2"));
+    assert!(urls[2].contains("DAADDAABCgAB_search_page3"));
+}
@@ -0,0 +1,137 @@
+use {
+    chrono::{TimeZone, Utc},
+    reqwest::{Method, StatusCode},
+    serde_json::{json, Value},
+    std::sync::Arc,
+    xplore::{
+        pool::{AccountHealth, XplorePool},
+        transport::MockTransport,
+        Xplore, XploreError, XploreOptions,
+    },
+};
+
+const RESET: i64 = 4102444800;
+const RATE_LIMITED: &str = r#"{"errors":[{"code":88,"message":"Rate limit exceeded"}]}"#;
+const UNAUTHORIZED: &str = r#"{"errors":[{"code":32,"message":"Could not authenticate you."}]}"#;
+
+fn user(screen_name: &str) -> Value {
+    json!({"data": {"user": {"result": {
+        "__typename": "User",
+        "rest_id": "12",
+        "legacy": {"location": "", "name": screen_name, "screen_name": screen_name}
+    }}}})
+}
+
+/// A client with the default, waiting, rate-limit strategy.
+async fn client(transport: MockTransport) -> Xplore {
+    let options = XploreOptions { transport: Some(Arc::new(transport)), ..Default::default() };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+    xplore.set_cookie("auth_token=token; ct0=csrf").await.unwrap();
+    xplore
+}
+
+fn serving(screen_name: &str) -> MockTransport {
+    MockTransport::new().with_json(Method::GET, "/UserByScreenName", user(screen_name))
+}
+
+fn health(pool: &XplorePool, name: &str) -> AccountHealth {
+    pool.accounts().into_iter().find(|account| account.name == name).unwrap().health
+}
+
+async fn profile_name(pool: &XplorePool) -> xplore::Result<String> {
+    pool.run(|xplore| async move { xplore.get_profile("jack").await.map(|profile| profile.username) }).await
+}
+
+#[tokio::test]
+async fn rotates_over_healthy_accounts() {
+    let mut pool = XplorePool::new();
+    pool.add("alice", client(serving("from_alice")).await).add("bob", client(serving("from_bob")).await);
+
+    let mut names = vec![profile_name(&pool).await.unwrap(), profile_name(&pool).await.unwrap()];
+    names.sort();
+
+    assert_eq!(names, ["from_alice", "from_bob"]);
+    assert!(pool.accounts().iter().all(|account| account.stats.requests == 1 && account.stats.successes == 1));
+}
+
+#[tokio::test]
+async fn rate_limited_accounts_cool_down_without_waiting() {
+    let limited = MockTransport::new()
+        .with_response(Method::GET, "/UserByScreenName", StatusCode::TOO_MANY_REQUESTS, RATE_LIMITED)
+        .with_header("x-rate-limit-limit", "95")
+        .with_header("x-rate-limit-remaining", "0")
+        .with_header("x-rate-limit-reset", &RESET.to_string());
+    let mut pool = XplorePool::new();
+    pool.add("alice", client(limited).await).add("bob", client(serving("from_bob")).await);
+
+    // whichever account is picked first, the request ends up on bob
+    for _ in 0..2 {
+        assert_eq!(profile_name(&pool).await.unwrap(), "from_bob");
+    }
+
+    assert_eq!(health(&pool, "alice"), AccountHealth::CoolingDown { until: Utc.timestamp_opt(RESET, 0).unwrap() });
+    assert_eq!(health(&pool, "bob"), AccountHealth::Healthy);
+}
+
+#[tokio::test]
+async fn rejected_sessions_are_quarantined_until_released() {
+    let rejected =
+        MockTransport::new().with_response(Method::GET, "/UserByScreenName", StatusCode::UNAUTHORIZED, UNAUTHORIZED);
+    let mut pool = XplorePool::new();
+    pool.add("alice", client(rejected).await);
+
+    let error = profile_name(&pool).await.unwrap_err();
+
+    assert!(matches!(&error, XploreError::Auth(message) if message.contains("quarantined")), "{:?}", error);
+    assert!(matches!(health(&pool, "alice"), AccountHealth::Quarantined { .. }));
+    assert_eq!(pool.accounts()[0].stats.auth_failures, 1);
+
+    assert!(pool.release("alice"));
+    assert!(!pool.release("carol"));
+    assert_eq!(health(&pool, "alice"), AccountHealth::Healthy);
+}
+
+#[tokio::test]
+async fn other_errors_are_returned_as_is() {
+    let mut pool = XplorePool::new();
+    pool.add("alice", client(MockTransport::new()).await);
+
+    let error = profile_name(&pool).await.unwrap_err();
+
+    assert!(matches!(error, XploreError::Api(_)), "{:?}", error);
+    assert_eq!(health(&pool, "alice"), AccountHealth::Healthy);
+}
+
+#[tokio::test]
+async fn exhausting_any_endpoint_of_a_request_cools_down() {
+    // the request exhausts UserByScreenName, then calls an endpoint with room left; only the
+    // last response's headers would show the window as open
+    let transport = serving("jack")
+        .with_header("x-rate-limit-remaining", "0")
+        .with_header("x-rate-limit-reset", &RESET.to_string())
+        .with_response(
+            Method::GET,
+            "/TweetResultByRestId",
+            StatusCode::OK,
+            include_str!("fixtures/tweet_result_poll.json"),
+        )
+        .with_header("x-rate-limit-remaining", "40");
+    let mut pool = XplorePool::new();
+    pool.add("alice", client(transport).await);
+
+    pool.run(|xplore| async move {
+        xplore.get_profile("jack").await?;
+        xplore.get_poll("1790000000000000003").await
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(health(&pool, "alice"), AccountHealth::CoolingDown { until: Utc.timestamp_opt(RESET, 0).unwrap() });
+    let error = profile_name(&pool).await.unwrap_err();
+    assert!(matches!(error, XploreError::RateLimited { reset_at: Some(_), .. }), "{:?}", error);
+}
+
+#[tokio::test]
+async fn empty_pools_fail() {
+    assert!(matches!(profile_name(&XplorePool::new()).await, Err(XploreError::Auth(_))));
+}
@@ -0,0 +1,90 @@
+use {
+    reqwest::{Method, StatusCode},
+    serde_json::Value,
+    std::{path::PathBuf, sync::Arc},
+    xplore::{
+        search::{SearchMode, SearchSession},
+        transport::MockTransport,
+        Xplore, XploreError, XploreOptions,
+    },
+};
+
+fn page(index: usize) -> String {
+    let cassette: Value = serde_json::from_str(include_str!("fixtures/cassettes/search_timeline.json")).unwrap();
+    cassette["interactions"][index]["response"]["body"].as_str().unwrap().to_string()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("xplore-search-{}-{}.json", name, std::process::id()))
+}
+
+async fn client(transport: Arc<MockTransport>) -> Xplore {
+    let options = XploreOptions { transport: Some(transport), ..Default::default() };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+    xplore.set_cookie("auth_token=token; ct0=csrf").await.unwrap();
+    xplore
+}
+
+#[tokio::test]
+async fn session_sends_its_cursor_and_saves_it() {
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_response_once(Method::GET, "/SearchTimeline", StatusCode::OK, page(0))
+            .with_response_once(Method::GET, "/SearchTimeline", StatusCode::OK, page(1)),
+    );
+    let xplore = client(transport.clone()).await;
+    let path = temp_path("cursor");
+    let _ = std::fs::remove_file(&path);
+
+    let mut session = SearchSession::open(&path, "rust", SearchMode::Latest).unwrap();
+    assert!(!xplore.next_search_tweets(&mut session, 20).await.unwrap().is_empty());
+    assert_eq!(session.cursor.as_deref(), Some("DAADDAABCgAB_search_page2"));
+
+    // a new process resumes from the file
+    let mut resumed = SearchSession::open(&path, "rust", SearchMode::Latest).unwrap();
+    assert_eq!(resumed.cursor, session.cursor);
+    xplore.next_search_tweets(&mut resumed, 20).await.unwrap();
+
+    let urls: Vec<String> = transport.requests().into_iter().map(|request| request.url).collect();
+    assert!(!urls[0].contains("cursor"));
+    assert!(urls[1].contains("DAADDAABCgAB_search_page2"));
+    assert_eq!(
+        SearchSession::open(&path, "rust", SearchMode::Latest).unwrap().cursor.as_deref(),
+        Some("DAADDAABCgAB_search_page3")
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn open_rejects_a_file_for_another_search() {
+    let path = temp_path("mismatch");
+    let mut session = SearchSession::new("rust", SearchMode::Latest);
+    session.cursor = Some("abc".into());
+    session.save(&path).unwrap();
+
+    let saved = SearchSession::open(&path, "rust", SearchMode::Latest).unwrap();
+    assert_eq!(saved.cursor.as_deref(), Some("abc"));
+    assert!(!saved.exhausted);
+
+    for (query, mode) in [("golang", SearchMode::Latest), ("rust", SearchMode::Top)] {
+        let error = SearchSession::open(&path, query, mode).unwrap_err();
+        assert!(matches!(error, XploreError::Config(_)), "{:?}", error);
+    }
+
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(SearchSession::open(&path, "golang", SearchMode::Top).unwrap().cursor, None);
+}
+
+#[tokio::test]
+async fn sessions_must_match_what_they_fetch() {
+    let transport = Arc::new(MockTransport::new());
+    let xplore = client(transport.clone()).await;
+
+    let mut tweets = SearchSession::new("rust", SearchMode::Users);
+    let mut profiles = SearchSession::new("rust", SearchMode::Latest);
+
+    assert!(matches!(xplore.next_search_tweets(&mut tweets, 20).await, Err(XploreError::Config(_))));
+    assert!(matches!(xplore.next_search_profiles(&mut profiles, 20).await, Err(XploreError::Config(_))));
+    assert!(transport.requests().is_empty());
+}
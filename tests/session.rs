@@ -0,0 +1,90 @@
+use {
+    chrono::{TimeZone, Utc},
+    cookie::{Cookie, SameSite},
+    xplore::{
+        session::{Session, StoredCookie, SESSION_FORMAT_VERSION},
+        XploreError,
+    },
+};
+
+fn session() -> Session {
+    let cookie = Cookie::parse("auth_token=secret-auth-token; Domain=.x.com; Path=/; Secure; HttpOnly").unwrap();
+    Session {
+        version: SESSION_FORMAT_VERSION,
+        cookies: vec![StoredCookie::from_cookie(&cookie)],
+        guest_token: Some("secret-guest-token".into()),
+        guest_token_created_at: None,
+        bearer_token: "secret-bearer-token".into(),
+        user_id: Some("12".into()),
+        screen_name: Some("jack".into()),
+        saved_at: Utc::now(),
+    }
+}
+
+#[test]
+fn upgrades_legacy_cookie_files() {
+    let session = Session::from_json(r#"[["auth_token", "token"], ["ct0", "csrf"]]"#).unwrap();
+
+    assert_eq!(session.version, SESSION_FORMAT_VERSION);
+    let cookies: Vec<(&str, &str)> =
+        session.cookies.iter().map(|cookie| (cookie.name.as_str(), cookie.value.as_str())).collect();
+    assert_eq!(cookies, [("auth_token", "token"), ("ct0", "csrf")]);
+    assert!(session.cookies.iter().all(|cookie| cookie.secure && cookie.expires.is_none()));
+    assert!(!session.bearer_token.is_empty());
+    assert_eq!(session.user_id, None);
+}
+
+#[test]
+fn rejects_sessions_from_newer_releases() {
+    let mut json: serde_json::Value = serde_json::from_str(&session().to_json().unwrap()).unwrap();
+    json["version"] = (SESSION_FORMAT_VERSION + 1).into();
+
+    let error = Session::from_json(&json.to_string()).unwrap_err();
+
+    assert!(matches!(&error, XploreError::Config(message) if message.contains("newer")), "{:?}", error);
+}
+
+#[test]
+fn cookie_attributes_round_trip() {
+    let expires = Utc.with_ymd_and_hms(2030, 1, 2, 3, 4, 5).unwrap();
+    let header = "ct0=csrf; Domain=.x.com; Path=/i; Expires=Wed, 02 Jan 2030 03:04:05 GMT; Secure; SameSite=Lax";
+    let stored = StoredCookie::from_cookie(&Cookie::parse(header).unwrap());
+
+    assert_eq!(stored.domain.as_deref(), Some("x.com"));
+    assert_eq!(stored.path.as_deref(), Some("/i"));
+    assert_eq!(stored.expires, Some(expires));
+    assert!(stored.secure && !stored.http_only);
+    assert_eq!(stored.same_site.as_deref(), Some("Lax"));
+
+    let cookie = stored.to_cookie();
+    assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+    assert_eq!(StoredCookie::from_cookie(&cookie), stored);
+
+    let session = Session { cookies: vec![stored.clone()], ..session() };
+    assert_eq!(Session::from_json(&session.to_json().unwrap()).unwrap().cookies, [stored]);
+}
+
+#[test]
+fn save_writes_a_private_file() {
+    let path = std::env::temp_dir().join(format!("xplore-session-{}.json", std::process::id()));
+
+    session().save(&path).unwrap();
+
+    assert_eq!(Session::load(&path).unwrap().screen_name.as_deref(), Some("jack"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn debug_output_leaves_out_credentials() {
+    let printed = format!("{:?}", session());
+
+    for secret in ["secret-auth-token", "secret-guest-token", "secret-bearer-token"] {
+        assert!(!printed.contains(secret), "{} in {}", secret, printed);
+    }
+    assert!(printed.contains("auth_token") && printed.contains("jack"));
+}
@@ -0,0 +1,174 @@
+use {
+    chrono::Utc,
+    reqwest::Method,
+    std::{path::PathBuf, sync::Arc},
+    xplore::{
+        session::{Session, StoredCookie, SESSION_FORMAT_VERSION},
+        session_store::{EncryptedFileStore, SessionStore},
+        transport::MockTransport,
+        Xplore, XploreError, XploreOptions,
+    },
+};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("xplore-store-{}-{}", name, std::process::id()))
+}
+
+fn key_file(name: &str, len: usize) -> PathBuf {
+    let path = temp_path(name);
+    std::fs::write(&path, vec![7u8; len]).unwrap();
+    path
+}
+
+fn session(auth_token: &str) -> Session {
+    let cookie = cookie::Cookie::parse(format!("auth_token={}; Domain=.x.com; Path=/", auth_token)).unwrap();
+    Session {
+        version: SESSION_FORMAT_VERSION,
+        cookies: vec![StoredCookie::from_cookie(&cookie)],
+        guest_token: None,
+        guest_token_created_at: None,
+        bearer_token: "bearer".into(),
+        user_id: Some("12".into()),
+        screen_name: Some("jack".into()),
+        saved_at: Utc::now(),
+    }
+}
+
+fn config_error(result: xplore::Result<impl std::fmt::Debug>, expected: &str) {
+    match result {
+        Err(XploreError::Config(message)) => assert!(message.contains(expected), "{}", message),
+        other => panic!("expected a Config error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn passphrase_store_round_trips_privately() {
+    let path = temp_path("passphrase");
+    let store = EncryptedFileStore::with_passphrase(&path, "correct horse");
+    assert!(store.load().await.unwrap().is_none());
+
+    store.save(&session("secret-token")).await.unwrap();
+
+    let on_disk = std::fs::read_to_string(&path).unwrap();
+    assert!(!on_disk.contains("secret-token") && on_disk.contains("argon2id"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    // a new store derives the key again from the salt in the file
+    let loaded = EncryptedFileStore::with_passphrase(&path, "correct horse").load().await.unwrap().unwrap();
+    assert_eq!(loaded.cookies, session("secret-token").cookies);
+
+    store.clear().await.unwrap();
+    store.clear().await.unwrap();
+    assert!(!path.exists());
+}
+
+#[tokio::test]
+async fn wrong_passphrase_is_rejected() {
+    let path = temp_path("wrong-passphrase");
+    EncryptedFileStore::with_passphrase(&path, "correct horse").save(&session("token")).await.unwrap();
+
+    config_error(EncryptedFileStore::with_passphrase(&path, "battery staple").load().await, "wrong passphrase");
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn key_files_must_be_long_enough() {
+    let short = key_file("short.key", 16);
+    config_error(EncryptedFileStore::with_key_file(temp_path("unused"), &short).map(|_| ()), "at least 32");
+
+    let key = key_file("good.key", 32);
+    let path = temp_path("key-file");
+    let store = EncryptedFileStore::with_key_file(&path, &key).unwrap();
+    store.save(&session("token")).await.unwrap();
+    assert_eq!(store.load().await.unwrap().unwrap().screen_name.as_deref(), Some("jack"));
+
+    for file in [short, key, path] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn kdf_mismatch_is_rejected() {
+    let key = key_file("mismatch.key", 32);
+    let path = temp_path("kdf-mismatch");
+    EncryptedFileStore::with_key_file(&path, &key).unwrap().save(&session("token")).await.unwrap();
+
+    config_error(EncryptedFileStore::with_passphrase(&path, "correct horse").load().await, "key-file");
+
+    std::fs::remove_file(key).unwrap();
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_saves_leave_a_whole_file() {
+    let path = temp_path("concurrent");
+    let store = Arc::new(EncryptedFileStore::with_passphrase(&path, "correct horse"));
+    store.save(&session("first")).await.unwrap();
+
+    let handles: Vec<_> = (0..16)
+        .map(|i| {
+            let store = store.clone();
+            tokio::spawn(async move { store.save(&session(&format!("token-{}", i))).await })
+        })
+        .collect();
+    for handle in handles {
+        handle.await.unwrap().unwrap();
+    }
+
+    let saved = store.load().await.unwrap().unwrap();
+    assert!(saved.cookies[0].value.starts_with("token-"));
+    let leftovers = std::fs::read_dir(std::env::temp_dir())
+        .unwrap()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry.file_name().to_string_lossy().starts_with(&format!("xplore-store-concurrent-{}.", std::process::id()))
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn client_starts_without_a_session_it_cannot_decrypt() {
+    let path = temp_path("client");
+    EncryptedFileStore::with_passphrase(&path, "old passphrase").save(&session("token")).await.unwrap();
+
+    let options = XploreOptions {
+        transport: Some(Arc::new(MockTransport::new().with_json(Method::GET, "/unused", serde_json::json!({})))),
+        session_store: Some(Arc::new(EncryptedFileStore::with_passphrase(&path, "new passphrase"))),
+        ..Default::default()
+    };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+
+    assert!(xplore.is_guest().await);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn client_starts_without_a_damaged_session() {
+    let path = temp_path("damaged");
+    let store = EncryptedFileStore::with_passphrase(&path, "passphrase");
+    store.save(&session("token")).await.unwrap();
+    let saved = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, &saved[..saved.len() / 2]).unwrap();
+
+    config_error(store.load().await, "is not a session store");
+
+    let options = XploreOptions {
+        transport: Some(Arc::new(MockTransport::new())),
+        session_store: Some(Arc::new(store)),
+        ..Default::default()
+    };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+
+    assert!(xplore.is_guest().await);
+
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,42 @@
+use xplore::text::{weighted_length, URL_LENGTH};
+
+#[test]
+fn latin_text_counts_one_per_character() {
+    assert_eq!(weighted_length("Hello, world!"), 13);
+    assert_eq!(weighted_length("Ünïcödé — “quotes”"), 18);
+}
+
+#[test]
+fn cjk_counts_two_per_character() {
+    assert_eq!(weighted_length("日本語"), 6);
+    assert_eq!(weighted_length("한국어 ok"), 9);
+}
+
+#[test]
+fn emoji_sequences_count_two() {
+    assert_eq!(weighted_length("😀"), 2);
+    assert_eq!(weighted_length("👍🏽"), 2);
+    assert_eq!(weighted_length("👨‍👩‍👧‍👦"), 2);
+    assert_eq!(weighted_length("🇯🇵🇫🇷"), 4);
+    assert_eq!(weighted_length("❤️"), 2);
+    assert_eq!(weighted_length("1️⃣ go"), 5);
+}
+
+#[test]
+fn text_is_normalized_before_counting() {
+    assert_eq!(weighted_length("e\u{301}"), 1);
+}
+
+#[test]
+fn urls_count_as_a_tco_link() {
+    assert_eq!(weighted_length("https://example.com/a/very/long/path/that/goes/on/and/on"), URL_LENGTH);
+    assert_eq!(weighted_length("see http://t.co/x."), 4 + URL_LENGTH + 1);
+    assert_eq!(weighted_length("docs.rs/xplore"), URL_LENGTH);
+    assert_eq!(weighted_length("(https://en.wikipedia.org/wiki/Rust_(programming_language))"), URL_LENGTH + 2);
+}
+
+#[test]
+fn mentions_and_email_addresses_are_not_shortened() {
+    assert_eq!(weighted_length("@jack"), 5);
+    assert_eq!(weighted_length("mail me@example.com"), 19);
+}
@@ -0,0 +1,142 @@
+use {
+    base64::{engine::general_purpose::STANDARD_NO_PAD, Engine},
+    reqwest::{Method, StatusCode},
+    serde_json::json,
+    std::{sync::Arc, time::Duration},
+    xplore::{
+        client_profile::ClientProfile,
+        transaction::{find_ondemand_url, TransactionKey},
+        transport::MockTransport,
+        Xplore, XploreError, XploreOptions,
+    },
+};
+
+const HOME_PAGE: &str = include_str!("fixtures/x_home.html");
+const ONDEMAND: &str = include_str!("fixtures/ondemand.s.js");
+
+const PATH: &str = "/i/api/graphql/G3KGOASz96M-Qu0nwmGXNg/UserByScreenName";
+
+#[test]
+fn finds_ondemand_script_in_home_page() {
+    assert_eq!(
+        find_ondemand_url(HOME_PAGE).as_deref(),
+        Some("https://abs.twimg.com/responsive-web/client-web/ondemand.s.7c8d9e0fa.js")
+    );
+}
+
+#[test]
+fn matches_the_reference_implementation() {
+    let key = TransactionKey::from_pages(HOME_PAGE, ONDEMAND).unwrap();
+
+    // Produced by a Python port of the XClientTransaction algorithm, run independently on the
+    // same fixtures with the same time and random byte.
+    assert_eq!(
+        key.generate_at("GET", PATH, 100_000_000, 77),
+        "TQDIfuobQJ+da3kN7r+rnycr7+bll2K2gmQkMVwqfWcszNzRzh5xRsU6PTRRS9TCC02suEgZ6K23jgipVMqXViF1JqdkTg"
+    );
+}
+
+#[test]
+fn masks_every_byte_after_the_first() {
+    let key = TransactionKey::from_pages(HOME_PAGE, ONDEMAND).unwrap();
+    let bytes = STANDARD_NO_PAD.decode(key.generate_at("POST", PATH, 0x0102_0304, 0xA5)).unwrap();
+
+    // mask, 48 key bytes, 4 time bytes, 16 hash bytes, fixed trailing byte
+    assert_eq!(bytes.len(), 70);
+    assert_eq!(bytes[0], 0xA5);
+    let unmasked: Vec<u8> = bytes[1..].iter().map(|byte| byte ^ 0xA5).collect();
+    assert_eq!(unmasked[48..52], [4, 3, 2, 1]);
+    assert_eq!(unmasked[68], 3);
+
+    let random = key.generate("POST", PATH);
+    assert_eq!(STANDARD_NO_PAD.decode(random).unwrap().len(), 70);
+}
+
+#[test]
+fn reports_missing_page_parts() {
+    let error = TransactionKey::from_pages("<html></html>", ONDEMAND).unwrap_err();
+    assert!(matches!(error, XploreError::InvalidResponse(message) if message.contains("twitter-site-verification")));
+
+    let error = TransactionKey::from_pages(HOME_PAGE, "").unwrap_err();
+    assert!(matches!(error, XploreError::InvalidResponse(message) if message.contains("key byte indices")));
+}
+
+const ONDEMAND_PATH: &str = "/ondemand.s.7c8d9e0fa.js";
+
+async fn client(transport: Arc<MockTransport>, transaction_id: Option<bool>) -> Xplore {
+    let options = XploreOptions {
+        transport: Some(transport),
+        client_profile: ClientProfile { transaction_id, ..Default::default() },
+        ..Default::default()
+    };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+    xplore.set_cookie("auth_token=token; ct0=csrf").await.unwrap();
+    xplore
+}
+
+fn user_transport(home_status: StatusCode) -> Arc<MockTransport> {
+    let user = json!({"data": {"user": {"result": {
+        "__typename": "User",
+        "rest_id": "12",
+        "legacy": {"location": "", "name": "jack", "screen_name": "jack"}
+    }}}});
+    Arc::new(
+        MockTransport::new()
+            .with_response(Method::GET, ONDEMAND_PATH, StatusCode::OK, ONDEMAND)
+            .with_json(Method::GET, "/UserByScreenName", user)
+            // the home page, registered last as every path ending in "/" matches it
+            .with_response(Method::GET, "/", home_status, HOME_PAGE),
+    )
+}
+
+fn home_page_fetches(transport: &MockTransport) -> usize {
+    transport.requests().iter().filter(|request| request.url.ends_with(".com/")).count()
+}
+
+fn last_has_transaction_id(transport: &MockTransport) -> bool {
+    transport.requests().last().unwrap().headers.contains_key("x-client-transaction-id")
+}
+
+#[tokio::test(start_paused = true)]
+async fn refetches_the_key_once_it_is_old() {
+    let transport = user_transport(StatusCode::OK);
+    let xplore = client(transport.clone(), Some(true)).await;
+
+    xplore.get_profile("jack").await.unwrap();
+    xplore.get_profile("jack").await.unwrap();
+    assert_eq!(home_page_fetches(&transport), 1);
+    assert!(last_has_transaction_id(&transport));
+
+    tokio::time::advance(Duration::from_secs(61 * 60)).await;
+    xplore.get_profile("jack").await.unwrap();
+
+    assert_eq!(home_page_fetches(&transport), 2);
+    assert!(last_has_transaction_id(&transport));
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_a_failed_key_fetch_later() {
+    let transport = user_transport(StatusCode::NOT_FOUND);
+    let xplore = client(transport.clone(), Some(true)).await;
+
+    xplore.get_profile("jack").await.unwrap();
+    xplore.get_profile("jack").await.unwrap();
+    assert_eq!(home_page_fetches(&transport), 1);
+    assert!(!last_has_transaction_id(&transport));
+
+    tokio::time::advance(Duration::from_secs(11 * 60)).await;
+    xplore.get_profile("jack").await.unwrap();
+
+    assert_eq!(home_page_fetches(&transport), 2);
+}
+
+#[tokio::test]
+async fn custom_transports_only_send_it_when_asked() {
+    let transport = user_transport(StatusCode::OK);
+    let xplore = client(transport.clone(), None).await;
+
+    xplore.get_profile("jack").await.unwrap();
+
+    assert_eq!(transport.requests().len(), 1);
+    assert!(!last_has_transaction_id(&transport));
+}
@@ -0,0 +1,83 @@
+use {
+    reqwest::{header::HeaderMap, Method, StatusCode},
+    serde_json::json,
+    xplore::{
+        transport::{BaseUrls, HttpRequest, MockTransport, RequestBody, Transport},
+        XploreError,
+    },
+};
+
+fn request(method: Method, url: &str) -> HttpRequest {
+    HttpRequest { method, url: url.to_string(), headers: HeaderMap::new(), body: RequestBody::Empty }
+}
+
+#[tokio::test]
+async fn routes_match_method_and_path_suffix() {
+    let transport = MockTransport::new()
+        .with_json(Method::GET, "/UserByScreenName", json!({ "user": 1 }))
+        .with_response(Method::POST, "/UserByScreenName", StatusCode::FORBIDDEN, "denied");
+
+    let response = transport
+        .send(request(Method::GET, "https://x.com/i/api/graphql/abc/UserByScreenName?variables=%7B%7D"))
+        .await
+        .unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.body, r#"{"user":1}"#);
+
+    let response =
+        transport.send(request(Method::POST, "https://x.com/i/api/graphql/def/UserByScreenName")).await.unwrap();
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.body, "denied");
+}
+
+#[tokio::test]
+async fn once_routes_answer_once_then_fall_through() {
+    let transport = MockTransport::new()
+        .with_response_once(Method::GET, "/TweetDetail", StatusCode::SERVICE_UNAVAILABLE, "")
+        .with_response(Method::GET, "/TweetDetail", StatusCode::OK, "first")
+        .with_header("x-rate-limit-remaining", "7")
+        .with_response(Method::GET, "/Likes", StatusCode::OK, "likes");
+
+    let url = "https://x.com/i/api/graphql/abc/TweetDetail";
+    let statuses = [
+        transport.send(request(Method::GET, url)).await.unwrap(),
+        transport.send(request(Method::GET, url)).await.unwrap(),
+        transport.send(request(Method::GET, url)).await.unwrap(),
+    ];
+    assert_eq!(statuses.iter().map(|r| r.status.as_u16()).collect::<Vec<_>>(), [503, 200, 200]);
+    assert_eq!(statuses[2].body, "first");
+    assert_eq!(statuses[2].headers["x-rate-limit-remaining"], "7");
+    assert!(statuses[0].headers.is_empty());
+
+    let other = transport.send(request(Method::GET, "https://x.com/i/api/graphql/abc/Likes")).await.unwrap();
+    assert_eq!(other.body, "likes");
+}
+
+#[tokio::test]
+async fn requests_are_recorded_even_without_a_route() {
+    let transport = MockTransport::new();
+
+    let error = transport.send(request(Method::DELETE, "https://api.x.com/1.1/thing.json?id=1")).await.unwrap_err();
+    assert!(matches!(error, XploreError::Api(message) if message.contains("DELETE /1.1/thing.json")));
+
+    let error = transport.send(request(Method::GET, "not a url")).await.unwrap_err();
+    assert!(matches!(error, XploreError::Api(_)));
+
+    let requests = transport.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].url, "https://api.x.com/1.1/thing.json?id=1");
+}
+
+#[test]
+fn base_urls_follow_the_domain() {
+    let urls = BaseUrls::for_domain("twitter.com");
+    assert_eq!(urls.api_url("/1.1/guest/activate.json"), "https://api.twitter.com/1.1/guest/activate.json");
+    assert_eq!(urls.upload_url("1.1/media/upload.json"), "https://upload.twitter.com/1.1/media/upload.json");
+    assert_eq!(urls.graphql_url("abc/SearchTimeline"), "https://twitter.com/i/api/graphql/abc/SearchTimeline");
+    assert_eq!(urls.caps_url("v2/cards/create.json"), "https://caps.twitter.com/v2/cards/create.json");
+
+    let urls = BaseUrls::single("http://127.0.0.1:8080/");
+    assert_eq!(urls.api_url("2/guide.json"), "http://127.0.0.1:8080/2/guide.json");
+    assert_eq!(urls.graphql_url("abc/Likes"), "http://127.0.0.1:8080/i/api/graphql/abc/Likes");
+    assert_eq!(urls.web_url(""), "http://127.0.0.1:8080/");
+}
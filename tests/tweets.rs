@@ -0,0 +1,393 @@
+use {
+    reqwest::{Method, StatusCode},
+    serde_json::{json, Value},
+    std::sync::Arc,
+    xplore::{
+        client_profile::{ClientProfile, HostDomain},
+        transport::{BaseUrls, MockTransport, RequestBody},
+        tweets::{NewPoll, ThreadPart, TweetActionOutcome, MAX_LONG_TWEET_LENGTH, MAX_TWEET_LENGTH},
+        Xplore, XploreError, XploreOptions,
+    },
+};
+
+const CREATE_TWEET: &str = include_str!("fixtures/create_tweet.json");
+const CREATE_NOTE_TWEET: &str = include_str!("fixtures/create_note_tweet.json");
+const TWEET_WITH_POLL: &str = include_str!("fixtures/tweet_result_poll.json");
+
+async fn client(transport: Arc<MockTransport>, host: HostDomain) -> Xplore {
+    let options = XploreOptions {
+        transport: Some(transport),
+        client_profile: ClientProfile { host, ..Default::default() },
+        ..Default::default()
+    };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+    xplore.set_cookie("auth_token=token; ct0=csrf").await.unwrap();
+    xplore
+}
+
+fn variables(transport: &MockTransport, operation: &str) -> Vec<Value> {
+    transport
+        .requests()
+        .into_iter()
+        .filter(|request| request.url.ends_with(operation))
+        .map(|request| match request.body {
+            RequestBody::Json(body) => body["variables"].clone(),
+            other => panic!("unexpected body {:?}", other),
+        })
+        .collect()
+}
+
+fn json(body: &str) -> Value {
+    serde_json::from_str(body).unwrap()
+}
+
+#[tokio::test]
+async fn post_tweet_returns_the_posted_tweet() {
+    let transport = Arc::new(MockTransport::new().with_json(Method::POST, "/CreateTweet", json(CREATE_TWEET)));
+    let xplore = client(transport, HostDomain::X).await;
+
+    let posted = xplore.post_tweet("Hello from xplore #rust", None, None).await.unwrap();
+
+    assert_eq!(posted.id, "1790000000000000001");
+    assert_eq!(posted.permalink, "https://x.com/modbot/status/1790000000000000001");
+    assert_eq!(posted.tweet.text.as_deref(), Some("Hello from xplore #rust"));
+    assert_eq!(posted.tweet.username.as_deref(), Some("modbot"));
+    assert_eq!(posted.tweet.hashtags, ["rust"]);
+    assert_eq!(posted.tweet.timestamp, Some(1715599200));
+}
+
+#[tokio::test]
+async fn long_tweet_keeps_the_full_note_text() {
+    let transport = Arc::new(MockTransport::new().with_json(Method::POST, "/CreateNoteTweet", json(CREATE_NOTE_TWEET)));
+    let xplore = client(transport, HostDomain::Twitter).await;
+
+    let posted = xplore.create_long_tweet("A long announcement", None, None).await.unwrap();
+
+    assert_eq!(posted.permalink, "https://twitter.com/modbot/status/1790000000000000002");
+    assert_eq!(
+        posted.tweet.text.as_deref(),
+        Some("A long announcement that goes on well past the usual limit and ends here.")
+    );
+}
+
+#[tokio::test]
+async fn thread_parts_reply_to_the_previous_part() {
+    let transport = MockTransport::new().with_json(Method::POST, "/CreateTweet", json(CREATE_TWEET)).with_json(
+        Method::POST,
+        "/CreateNoteTweet",
+        json(CREATE_NOTE_TWEET),
+    );
+    let transport = Arc::new(transport);
+    let xplore = client(transport.clone(), HostDomain::X).await;
+
+    let long = "a".repeat(MAX_TWEET_LENGTH + 1);
+    let posted =
+        xplore.post_thread(vec!["1/3".into(), long.as_str().into(), ThreadPart::new("3/3")], false).await.unwrap();
+
+    let ids: Vec<&str> = posted.iter().map(|tweet| tweet.id.as_str()).collect();
+    assert_eq!(ids, ["1790000000000000001", "1790000000000000002", "1790000000000000001"]);
+
+    let short = variables(&transport, "/CreateTweet");
+    assert_eq!(short[0].get("reply"), None);
+    assert_eq!(short[1]["reply"]["in_reply_to_tweet_id"], "1790000000000000002");
+    let note = variables(&transport, "/CreateNoteTweet");
+    assert_eq!(note[0]["reply"]["in_reply_to_tweet_id"], "1790000000000000001");
+    assert_eq!(note[0]["tweet_text"], long.as_str());
+}
+
+#[tokio::test]
+async fn failed_thread_rolls_back_the_posted_parts() {
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_response_once(Method::POST, "/CreateTweet", StatusCode::OK, CREATE_TWEET)
+            .with_response_once(
+                Method::POST,
+                "/CreateTweet",
+                StatusCode::OK,
+                CREATE_TWEET.replace("1790000000000000001", "1790000000000000011"),
+            )
+            .with_response(
+                Method::POST,
+                "/CreateNoteTweet",
+                StatusCode::FORBIDDEN,
+                json!({ "errors": [{ "message": "Not allowed", "code": 200 }] }).to_string(),
+            )
+            .with_json(Method::POST, "/DeleteTweet", json!({ "data": { "delete_tweet": { "tweet_results": {} } } })),
+    );
+    let xplore = client(transport.clone(), HostDomain::X).await;
+
+    let parts = vec![ThreadPart::new("1/3"), ThreadPart::new("2/3"), ThreadPart::new("b".repeat(MAX_TWEET_LENGTH + 1))];
+    let error = xplore.post_thread(parts, true).await.unwrap_err();
+
+    let XploreError::ThreadFailed { posted, deleted, source } = error else { panic!("unexpected error {:?}", error) };
+    assert_eq!(posted, ["1790000000000000001", "1790000000000000011"]);
+    assert_eq!(deleted, posted);
+    assert!(matches!(*source, XploreError::Forbidden(_)));
+
+    // replies go first so no part is left replying to a deleted tweet
+    let deletes: Vec<Value> =
+        variables(&transport, "/DeleteTweet").into_iter().map(|variables| variables["tweet_id"].clone()).collect();
+    assert_eq!(deletes, ["1790000000000000011", "1790000000000000001"]);
+}
+
+#[tokio::test]
+async fn post_tweet_picks_the_operation_from_the_weighted_length() {
+    let transport = MockTransport::new().with_json(Method::POST, "/CreateTweet", json(CREATE_TWEET)).with_json(
+        Method::POST,
+        "/CreateNoteTweet",
+        json(CREATE_NOTE_TWEET),
+    );
+    let transport = Arc::new(transport);
+    let xplore = client(transport.clone(), HostDomain::X).await;
+
+    // 140 CJK characters weigh 280 and still fit a regular tweet; one more does not.
+    xplore.post_tweet(&"語".repeat(140), None, None).await.unwrap();
+    xplore.post_tweet(&"語".repeat(141), None, None).await.unwrap();
+
+    assert_eq!(variables(&transport, "/CreateTweet").len(), 1);
+    assert_eq!(variables(&transport, "/CreateNoteTweet").len(), 1);
+}
+
+#[tokio::test]
+async fn over_long_text_fails_before_sending() {
+    let transport = Arc::new(MockTransport::new());
+    let xplore = client(transport.clone(), HostDomain::X).await;
+
+    let error = xplore.post_tweet(&"a".repeat(MAX_LONG_TWEET_LENGTH + 1), None, None).await.unwrap_err();
+    assert!(
+        matches!(error, XploreError::TweetTooLong { length, max } if length == MAX_LONG_TWEET_LENGTH + 1 && max == MAX_LONG_TWEET_LENGTH)
+    );
+
+    let parts = vec![ThreadPart::new("1/2"), ThreadPart::new("b".repeat(MAX_LONG_TWEET_LENGTH + 1))];
+    let error = xplore.post_thread(parts, true).await.unwrap_err();
+    assert!(matches!(error, XploreError::TweetTooLong { .. }));
+
+    assert!(transport.requests().is_empty());
+}
+
+#[tokio::test]
+async fn post_poll_attaches_a_poll_card() {
+    let transport = MockTransport::new()
+        .with_json(Method::POST, "/v2/cards/create.json", json!({ "card_uri": "card://1790000000000000009" }))
+        .with_json(Method::POST, "/CreateTweet", json(CREATE_TWEET));
+    let transport = Arc::new(transport);
+    let xplore = client(transport.clone(), HostDomain::X).await;
+
+    let poll = NewPoll::new(["Tabs", "Spaces", "Both"], 10080);
+    xplore.post_poll("Weekly poll: tabs or spaces?", &poll, None).await.unwrap();
+
+    let requests = transport.requests();
+    assert_eq!(requests[0].url, "https://caps.x.com/v2/cards/create.json");
+    let RequestBody::Form(form) = &requests[0].body else { panic!("unexpected body {:?}", requests[0].body) };
+    let card_data: Value = serde_json::from_str(&form[0].1).unwrap();
+    assert_eq!(card_data["twitter:card"], "poll3choice_text_only");
+    assert_eq!(card_data["twitter:long:duration_minutes"], 10080);
+    assert_eq!(card_data["twitter:string:choice2_label"], "Spaces");
+
+    assert_eq!(variables(&transport, "/CreateTweet")[0]["card_uri"], "card://1790000000000000009");
+}
+
+#[tokio::test]
+async fn invalid_polls_fail_before_sending() {
+    let transport = Arc::new(MockTransport::new());
+    let xplore = client(transport.clone(), HostDomain::X).await;
+
+    for poll in [
+        NewPoll::new(["Only one"], 60),
+        NewPoll::new(["a", "b", "c", "d", "e"], 60),
+        NewPoll::new(["a", "this choice is far too long to fit"], 60),
+        NewPoll::new(["a", "b"], 2),
+        NewPoll::new(["a", "b"], 8 * 24 * 60),
+    ] {
+        let error = xplore.post_poll("Poll", &poll, None).await.unwrap_err();
+        assert!(matches!(error, XploreError::Config(_)), "{:?} gave {:?}", poll, error);
+    }
+
+    assert!(transport.requests().is_empty());
+}
+
+#[tokio::test]
+async fn get_poll_reads_the_live_counts() {
+    let transport =
+        Arc::new(MockTransport::new().with_json(Method::GET, "/TweetResultByRestId", json(TWEET_WITH_POLL)));
+    let xplore = client(transport, HostDomain::X).await;
+
+    let poll = xplore.get_poll("1790000000000000003").await.unwrap();
+
+    assert_eq!(poll.id.as_deref(), Some("card://1790000000000000009"));
+    assert_eq!(poll.end_datetime.as_deref(), Some("2024-05-20T11:30:00Z"));
+    assert_eq!(poll.voting_status.as_deref(), Some("open"));
+    let results: Vec<(&str, Option<i32>)> =
+        poll.options.iter().map(|option| (option.label.as_str(), option.votes)).collect();
+    assert_eq!(results, [("Tabs", Some(12)), ("Spaces", Some(57)), ("Both", Some(3))]);
+}
+
+const ALREADY_FAVORITED: &str = r#"{"errors":[{"code":139,"message":"You have already favorited this status."}]}"#;
+const ALREADY_RETWEETED: &str = r#"{"errors":[{"code":327,"message":"You have already retweeted this Tweet."}]}"#;
+const NO_STATUS: &str = r#"{"errors":[{"code":144,"message":"No status found with that ID."}]}"#;
+
+#[tokio::test]
+async fn like_and_retweet_report_whether_they_changed_anything() {
+    let retweeted = json!({"data":{"create_retweet":{"retweet_results":{"result":{
+        "rest_id":"1790000000000000020","legacy":{"full_text":"RT @jack: just setting up my twttr"}
+    }}}}});
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_json(Method::POST, "/FavoriteTweet", json!({"data":{"favorite_tweet":"Done"}}))
+            .with_json(Method::POST, "/CreateRetweet", retweeted),
+    );
+    let xplore = client(transport.clone(), HostDomain::X).await;
+
+    assert_eq!(xplore.like_tweet("20").await.unwrap(), TweetActionOutcome::Applied);
+    assert_eq!(xplore.retweet("20").await.unwrap(), TweetActionOutcome::Applied);
+
+    assert_eq!(variables(&transport, "/FavoriteTweet"), [json!({"tweet_id":"20"})]);
+    assert_eq!(variables(&transport, "/CreateRetweet"), [json!({"tweet_id":"20","dark_request":false})]);
+}
+
+#[tokio::test]
+async fn liking_or_retweeting_twice_is_already_applied() {
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_response(Method::POST, "/FavoriteTweet", StatusCode::FORBIDDEN, ALREADY_FAVORITED)
+            .with_response(Method::POST, "/CreateRetweet", StatusCode::OK, ALREADY_RETWEETED)
+            .with_response(Method::POST, "/CreateBookmark", StatusCode::OK, ALREADY_FAVORITED),
+    );
+    let xplore = client(transport, HostDomain::X).await;
+
+    assert_eq!(xplore.like_tweet("20").await.unwrap(), TweetActionOutcome::AlreadyApplied);
+    assert_eq!(xplore.retweet("20").await.unwrap(), TweetActionOutcome::AlreadyApplied);
+    assert_eq!(xplore.bookmark_tweet("20").await.unwrap(), TweetActionOutcome::AlreadyApplied);
+}
+
+#[tokio::test]
+async fn other_errors_are_not_treated_as_already_applied() {
+    let transport =
+        Arc::new(
+            MockTransport::new()
+                .with_response(Method::POST, "/FavoriteTweet", StatusCode::FORBIDDEN, "{}")
+                .with_response(Method::POST, "/DeleteTweet", StatusCode::OK, ALREADY_FAVORITED),
+        );
+    let xplore = client(transport, HostDomain::X).await;
+
+    assert!(matches!(xplore.like_tweet("20").await, Err(XploreError::Forbidden(_))));
+    // 139 only means "nothing to do" when adding, not when removing
+    assert!(matches!(xplore.delete_tweet("20").await, Err(XploreError::AlreadyApplied(_))));
+}
+
+#[tokio::test]
+async fn undoing_actions_reports_applied() {
+    let unretweeted = json!({"data":{"unretweet":{"source_tweet_results":{"result":{
+        "rest_id":"20","legacy":{"full_text":"just setting up my twttr"}
+    }}}}});
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_json(Method::POST, "/DeleteTweet", json!({"data":{"delete_tweet":{"tweet_results":{}}}}))
+            .with_json(Method::POST, "/UnfavoriteTweet", json!({"data":{"unfavorite_tweet":"Done"}}))
+            .with_json(Method::POST, "/DeleteRetweet", unretweeted)
+            .with_json(Method::POST, "/CreateBookmark", json!({"data":{"tweet_bookmark_put":"Done"}}))
+            .with_json(Method::POST, "/DeleteBookmark", json!({"data":{"tweet_bookmark_delete":"Done"}})),
+    );
+    let xplore = client(transport.clone(), HostDomain::X).await;
+
+    assert_eq!(xplore.delete_tweet("20").await.unwrap(), TweetActionOutcome::Applied);
+    assert_eq!(xplore.unlike_tweet("20").await.unwrap(), TweetActionOutcome::Applied);
+    assert_eq!(xplore.unretweet("20").await.unwrap(), TweetActionOutcome::Applied);
+    assert_eq!(xplore.bookmark_tweet("20").await.unwrap(), TweetActionOutcome::Applied);
+    assert_eq!(xplore.unbookmark_tweet("20").await.unwrap(), TweetActionOutcome::Applied);
+
+    assert_eq!(variables(&transport, "/DeleteTweet"), [json!({"tweet_id":"20","dark_request":false})]);
+    assert_eq!(variables(&transport, "/DeleteRetweet"), [json!({"source_tweet_id":"20","dark_request":false})]);
+    assert_eq!(variables(&transport, "/DeleteBookmark"), [json!({"tweet_id":"20"})]);
+}
+
+#[tokio::test]
+async fn undoing_what_is_not_there_is_already_applied() {
+    let transport =
+        Arc::new(
+            MockTransport::new()
+                .with_response(Method::POST, "/DeleteTweet", StatusCode::NOT_FOUND, NO_STATUS)
+                .with_json(Method::POST, "/DeleteRetweet", json!({"data":{"unretweet":{"source_tweet_results":{}}}})),
+        );
+    let xplore = client(transport, HostDomain::X).await;
+
+    assert_eq!(xplore.delete_tweet("20").await.unwrap(), TweetActionOutcome::AlreadyApplied);
+    assert_eq!(xplore.unretweet("20").await.unwrap(), TweetActionOutcome::AlreadyApplied);
+}
+
+#[tokio::test]
+async fn undoing_on_a_missing_tweet_is_not_found() {
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_response(Method::POST, "/UnfavoriteTweet", StatusCode::OK, NO_STATUS)
+            .with_response(Method::POST, "/DeleteRetweet", StatusCode::NOT_FOUND, NO_STATUS)
+            .with_response(Method::POST, "/DeleteBookmark", StatusCode::NOT_FOUND, "{}")
+            .with_response(Method::POST, "/account/unpin_tweet.json", StatusCode::NOT_FOUND, NO_STATUS),
+    );
+    let xplore = client(transport, HostDomain::X).await;
+
+    assert!(matches!(xplore.unlike_tweet("20").await, Err(XploreError::NotFound(_))));
+    assert!(matches!(xplore.unretweet("20").await, Err(XploreError::NotFound(_))));
+    assert!(matches!(xplore.unbookmark_tweet("20").await, Err(XploreError::NotFound(_))));
+    assert!(matches!(xplore.unpin_tweet("20").await, Err(XploreError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn pinning_posts_the_tweet_id_from_the_home_page() {
+    let transport = Arc::new(
+        MockTransport::new()
+            .with_json(Method::POST, "/account/pin_tweet.json", json!({"pinned_tweets":["20"]}))
+            .with_json(Method::POST, "/account/unpin_tweet.json", json!({"pinned_tweets":[]})),
+    );
+    let xplore = client(transport.clone(), HostDomain::X).await;
+
+    assert_eq!(xplore.pin_tweet("20").await.unwrap(), TweetActionOutcome::Applied);
+    assert_eq!(xplore.unpin_tweet("21").await.unwrap(), TweetActionOutcome::Applied);
+
+    let requests = transport.requests();
+    assert_eq!(requests.len(), 2);
+    for (request, id) in requests.iter().zip(["20", "21"]) {
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.headers.get("referer").unwrap().to_str().unwrap(), "https://x.com/home");
+        match &request.body {
+            RequestBody::Form(form) => assert_eq!(form, &[("id".to_string(), id.to_string())]),
+            other => panic!("unexpected body {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn pinning_needs_a_session() {
+    let transport = Arc::new(MockTransport::new());
+    let options = XploreOptions { transport: Some(transport.clone()), ..Default::default() };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+
+    assert!(matches!(xplore.pin_tweet("20").await, Err(XploreError::LoginRequired(_))));
+    assert!(transport.requests().is_empty());
+}
+
+#[tokio::test]
+async fn links_follow_the_configured_web_host() {
+    let transport =
+        Arc::new(MockTransport::new().with_json(Method::POST, "/CreateTweet", json(CREATE_TWEET)).with_json(
+            Method::POST,
+            "/account/pin_tweet.json",
+            json!({"pinned_tweets":["20"]}),
+        ));
+    let options = XploreOptions {
+        transport: Some(transport.clone()),
+        base_urls: Some(BaseUrls::single("http://127.0.0.1:8080/")),
+        ..Default::default()
+    };
+    let xplore = Xplore::new(Some(options)).await.unwrap();
+    xplore.set_cookie("auth_token=token; ct0=csrf").await.unwrap();
+
+    let posted = xplore.send_quote_tweet("Quoting", "20", None).await.unwrap();
+    xplore.pin_tweet("20").await.unwrap();
+
+    assert_eq!(posted.permalink, "http://127.0.0.1:8080/modbot/status/1790000000000000001");
+    assert_eq!(posted.tweet.permanent_url.as_deref(), Some(posted.permalink.as_str()));
+    assert_eq!(variables(&transport, "/CreateTweet")[0]["attachment_url"], "http://127.0.0.1:8080/twitter/status/20");
+    let pin = transport.requests().pop().unwrap();
+    assert_eq!(pin.headers.get("referer").unwrap().to_str().unwrap(), "http://127.0.0.1:8080/home");
+}